@@ -0,0 +1,191 @@
+//! Benchmarks for the http header parts parser.
+//!
+//! Compares parsing every request with a fresh buffer against reusing one buffer across
+//! keep-alive requests, and copying header values against slicing them out of a shared buffer.
+
+use bytes::{Bytes, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use futures::{executor::block_on, io::Cursor};
+use futures_http::reader::{Requester, Responser};
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+const REQUEST: &[u8] = b"GET /api/v1/users/42?fields=name,email HTTP/1.1\r\n\
+Host: example.com\r\n\
+User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: en-US,en;q=0.5\r\n\
+Accept-Encoding: gzip, deflate, br\r\n\
+Cookie: session=0123456789abcdef0123456789abcdef; theme=dark; lang=en\r\n\
+X-Request-Id: 4b3c2a1f-8e9d-4c7b-a6f5-0e1d2c3b4a59\r\n\
+X-Forwarded-For: 203.0.113.195, 70.41.3.18, 150.172.238.178\r\n\
+Cache-Control: no-cache\r\n\
+Connection: keep-alive\r\n\r\n";
+
+const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\n\
+Server: futures-http\r\n\
+Date: Sun, 18 Oct 2026 08:00:00 GMT\r\n\
+Content-Type: application/json; charset=utf-8\r\n\
+Cache-Control: private, max-age=0, must-revalidate\r\n\
+ETag: \"33a64df551425fcc55e4d42a148795d9f25f89d4\"\r\n\
+Set-Cookie: session=0123456789abcdef0123456789abcdef; Path=/; HttpOnly\r\n\
+X-Request-Id: 4b3c2a1f-8e9d-4c7b-a6f5-0e1d2c3b4a59\r\n\
+Connection: keep-alive\r\n\r\n";
+
+/// A stream of `count` pipelined copies of `packet`.
+fn pipelined(packet: &[u8], count: usize) -> Cursor<Vec<u8>> {
+    Cursor::new(packet.repeat(count))
+}
+
+fn bench_request(c: &mut Criterion) {
+    let mut group = c.benchmark_group("request");
+
+    group.bench_function("parse_parts", |b| {
+        b.iter(|| {
+            block_on(async {
+                let (parts, _, _) = Requester::new(Cursor::new(REQUEST))
+                    .parse_parts()
+                    .await
+                    .unwrap();
+
+                black_box(parts);
+            })
+        })
+    });
+
+    group.bench_function("parse_parts_with_buf", |b| {
+        let mut stream = pipelined(REQUEST, 1);
+        let mut buf = BytesMut::new();
+
+        b.iter(|| {
+            stream.set_position(0);
+
+            block_on(async {
+                let (parts, _) = Requester::new(&mut stream)
+                    .parse_parts_with_buf(&mut buf)
+                    .await
+                    .unwrap();
+
+                black_box(parts);
+            })
+        })
+    });
+
+    group.bench_function("parse_parts_with_buf/pipelined", |b| {
+        let mut stream = pipelined(REQUEST, 16);
+        let mut buf = BytesMut::new();
+
+        b.iter(|| {
+            stream.set_position(0);
+
+            block_on(async {
+                for _ in 0..16 {
+                    let (parts, _) = Requester::new(&mut stream)
+                        .parse_parts_with_buf(&mut buf)
+                        .await
+                        .unwrap();
+
+                    black_box(parts);
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_response(c: &mut Criterion) {
+    let mut group = c.benchmark_group("response");
+
+    group.bench_function("parse_parts", |b| {
+        b.iter(|| {
+            block_on(async {
+                let (parts, _, _) = Responser::new(Cursor::new(RESPONSE))
+                    .parse_parts()
+                    .await
+                    .unwrap();
+
+                black_box(parts);
+            })
+        })
+    });
+
+    group.bench_function("parse_parts_with_buf", |b| {
+        let mut stream = pipelined(RESPONSE, 1);
+        let mut buf = BytesMut::new();
+
+        b.iter(|| {
+            stream.set_position(0);
+
+            block_on(async {
+                let (parts, _) = Responser::new(&mut stream)
+                    .parse_parts_with_buf(&mut buf)
+                    .await
+                    .unwrap();
+
+                black_box(parts);
+            })
+        })
+    });
+
+    group.finish();
+}
+
+/// The `(name, value)` byte ranges of the headers in [`REQUEST`].
+fn header_ranges() -> Vec<((usize, usize), (usize, usize))> {
+    let mut ranges = vec![];
+    let mut offset = REQUEST.iter().position(|c| *c == b'\n').unwrap() + 1;
+
+    while REQUEST[offset] != b'\r' {
+        let end = offset + REQUEST[offset..].iter().position(|c| *c == b'\r').unwrap();
+        let colon = offset + REQUEST[offset..].iter().position(|c| *c == b':').unwrap();
+
+        ranges.push(((offset, colon), (colon + 2, end)));
+
+        offset = end + 2;
+    }
+
+    ranges
+}
+
+fn bench_header_value(c: &mut Criterion) {
+    let mut group = c.benchmark_group("header_value");
+
+    let ranges = header_ranges();
+
+    group.bench_function("copy", |b| {
+        b.iter(|| {
+            let buf = Bytes::copy_from_slice(REQUEST);
+            let mut headers = HeaderMap::new();
+
+            for ((name_start, name_end), (value_start, value_end)) in ranges.iter().cloned() {
+                headers.append(
+                    HeaderName::from_bytes(&buf[name_start..name_end]).unwrap(),
+                    HeaderValue::from_bytes(&buf[value_start..value_end]).unwrap(),
+                );
+            }
+
+            black_box(headers);
+        })
+    });
+
+    group.bench_function("shared", |b| {
+        b.iter(|| {
+            let buf = Bytes::copy_from_slice(REQUEST);
+            let mut headers = HeaderMap::new();
+
+            for ((name_start, name_end), (value_start, value_end)) in ranges.iter().cloned() {
+                headers.append(
+                    HeaderName::from_bytes(&buf[name_start..name_end]).unwrap(),
+                    HeaderValue::from_maybe_shared(buf.slice(value_start..value_end)).unwrap(),
+                );
+            }
+
+            black_box(headers);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_request, bench_response, bench_header_value);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+#![no_main]
+
+use futures::{executor::block_on, io::Cursor, TryStreamExt};
+use futures_http::body::BodyReader;
+use http::{header::TRANSFER_ENCODING, HeaderMap, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut headers = HeaderMap::new();
+
+    headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+
+    block_on(async {
+        if let Ok(mut body) = BodyReader::parse(&headers, Cursor::new(data.to_vec())).await {
+            while let Ok(Some(_)) = body.try_next().await {}
+        }
+    })
+});
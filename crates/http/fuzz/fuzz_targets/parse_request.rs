@@ -0,0 +1,15 @@
+#![no_main]
+
+use futures::{executor::block_on, io::Cursor, TryStreamExt};
+use futures_http::reader::Requester;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        if let Ok(request) = Requester::new(Cursor::new(data.to_vec())).parse().await {
+            let mut body = request.into_body();
+
+            while let Ok(Some(_)) = body.try_next().await {}
+        }
+    })
+});
@@ -1,9 +1,9 @@
-use std::{fmt::Debug, task::Poll};
+use std::{fmt::Debug, pin::Pin, task::Poll};
 
 use futures::{
-    io::{BufReader, Lines},
-    stream::{once, BoxStream},
-    AsyncBufReadExt, AsyncRead, AsyncReadExt, Stream, StreamExt,
+    io::BufReader,
+    stream::{once, unfold, BoxStream},
+    AsyncBufRead, AsyncRead, AsyncReadExt, Stream, StreamExt,
 };
 use http::{
     header::{CONTENT_LENGTH, TRANSFER_ENCODING},
@@ -86,8 +86,22 @@ impl BodyReader {
         self.length
     }
 
+    /// Calls `f` once the whole body has been read without error.
+    pub(crate) fn on_finished<F>(self, f: F) -> Self
+    where
+        F: FnOnce() + Send + Unpin + 'static,
+    {
+        Self {
+            length: self.length,
+            stream: Box::pin(OnFinished {
+                stream: self.stream,
+                f: Some(f),
+            }),
+        }
+    }
+
     /// Parse headers and generate property `BodyReader`.
-    pub async fn parse<R>(headers: &HeaderMap, mut read: R) -> BodyReaderResult<Self>
+    pub async fn parse<R>(headers: &HeaderMap, read: R) -> BodyReaderResult<Self>
     where
         R: AsyncRead + Unpin + Send + 'static,
     {
@@ -115,11 +129,10 @@ impl BodyReader {
             let content_length = usize::from_str_radix(content_length, 10)
                 .map_err(|err| BodyReaderError::ParseContentLength(err.to_string()))?;
 
-            let mut buf = vec![0u8; content_length];
-
-            read.read_exact(&mut buf).await?;
-
-            return Ok(buf.into());
+            return Ok(Self {
+                length: Some(content_length),
+                stream: Box::pin(fixed_length_body_stream(read, content_length)),
+            });
         }
 
         Ok(Self::from(vec![]))
@@ -137,9 +150,105 @@ impl Stream for BodyReader {
     }
 }
 
+/// A body stream wrapper returned by [`BodyReader::on_finished`].
+struct OnFinished<F> {
+    stream: BoxStream<'static, std::io::Result<Vec<u8>>>,
+    f: Option<F>,
+}
+
+impl<F> Stream for OnFinished<F>
+where
+    F: FnOnce() + Unpin,
+{
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let poll = self.stream.poll_next_unpin(cx);
+
+        match &poll {
+            Poll::Ready(Some(Err(_))) => {
+                self.f = None;
+            }
+            Poll::Ready(None) => {
+                if let Some(f) = self.f.take() {
+                    f();
+                }
+            }
+            _ => {}
+        }
+
+        poll
+    }
+}
+
+/// The max length of one chunk yielded by a `CONTENT_LENGTH` body stream.
+const MAX_FIXED_LENGTH_CHUNK_LEN: usize = 16 * 1024;
+
+/// Stream `content_length` bytes from `read` without buffering the whole body.
+fn fixed_length_body_stream<R>(
+    read: R,
+    content_length: usize,
+) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    unfold(
+        (read, content_length),
+        move |(mut read, remaining)| async move {
+            if remaining == 0 {
+                return None;
+            }
+
+            // Don't trust the peer's `CONTENT_LENGTH`, the chunk buffer is bounded.
+            let mut buf = vec![0; remaining.min(MAX_FIXED_LENGTH_CHUNK_LEN)];
+
+            match read.read(&mut buf).await {
+                Ok(0) => Some((
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "Body length mismatch, content_length={}, received={}",
+                            content_length,
+                            content_length - remaining
+                        ),
+                    )),
+                    (read, 0),
+                )),
+                Ok(read_size) => {
+                    buf.truncate(read_size);
+                    Some((Ok(buf), (read, remaining - read_size)))
+                }
+                Err(err) => Some((Err(err), (read, 0))),
+            }
+        },
+    )
+}
+
+/// The max length of the chunk size line (includes chunk extensions) and trailer lines.
+const MAX_CHUNKED_LINE_LEN: usize = 4096;
+
+/// The statemachine of [`ChunkedBodyStream`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChunkedState {
+    /// Reading the chunk size line.
+    Size,
+    /// Reading chunk data, the remaining length is provided as associated data.
+    Data(usize),
+    /// Reading the CRLF that follows the chunk data.
+    DataEnd,
+    /// Reading the trailer section after the last chunk.
+    Trailer,
+    /// The last chunk and trailer section have been read.
+    Finished,
+}
+
 struct ChunkedBodyStream<R> {
-    lines: Lines<BufReader<R>>,
-    chunk_len: Option<usize>,
+    reader: BufReader<R>,
+    state: ChunkedState,
+    line: Vec<u8>,
 }
 
 impl<R> From<R> for ChunkedBodyStream<R>
@@ -148,12 +257,97 @@ where
 {
     fn from(value: R) -> Self {
         Self {
-            lines: BufReader::new(value).lines(),
-            chunk_len: None,
+            reader: BufReader::new(value),
+            state: ChunkedState::Size,
+            line: vec![],
         }
     }
 }
 
+fn invalid_chunked_data<E>(err: E) -> std::io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+}
+
+impl<R> ChunkedBodyStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Read one line terminated by `\n`, the line terminator(`\r\n` or `\n`) is trimmed.
+    ///
+    /// Returns `None` if the underlying stream reaches EOF before any byte of a new line is read.
+    fn poll_line(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<Option<Vec<u8>>>> {
+        loop {
+            let buf = match Pin::new(&mut self.reader).poll_fill_buf(cx) {
+                Poll::Ready(Ok(buf)) => buf,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if buf.is_empty() {
+                if self.line.is_empty() {
+                    return Poll::Ready(Ok(None));
+                }
+
+                return Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Incomplete chunked body line",
+                )));
+            }
+
+            let (consumed, completed) = match buf.iter().position(|c| *c == b'\n') {
+                Some(offset) => {
+                    self.line.extend_from_slice(&buf[..offset]);
+                    (offset + 1, true)
+                }
+                None => {
+                    self.line.extend_from_slice(buf);
+                    (buf.len(), false)
+                }
+            };
+
+            Pin::new(&mut self.reader).consume(consumed);
+
+            if self.line.len() > MAX_CHUNKED_LINE_LEN {
+                return Poll::Ready(Err(invalid_chunked_data(format!(
+                    "Chunked body line overflow, max={}",
+                    MAX_CHUNKED_LINE_LEN
+                ))));
+            }
+
+            if completed {
+                let mut line = std::mem::take(&mut self.line);
+
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+
+                return Poll::Ready(Ok(Some(line)));
+            }
+        }
+    }
+
+    fn parse_chunk_size(line: &[u8]) -> std::io::Result<usize> {
+        // ignore chunk extensions.
+        let size = match line.iter().position(|c| *c == b';') {
+            Some(offset) => &line[..offset],
+            None => line,
+        };
+
+        let size = std::str::from_utf8(size).map_err(|err| {
+            invalid_chunked_data(format!("Parse chunck length with error: {}", err))
+        })?;
+
+        usize::from_str_radix(size.trim_matches(|c| c == ' ' || c == '\t'), 16)
+            .map_err(|err| invalid_chunked_data(format!("Parse chunck length with error: {}", err)))
+    }
+}
+
 impl<R> Stream for ChunkedBodyStream<R>
 where
     R: AsyncRead + Unpin,
@@ -165,49 +359,86 @@ where
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
         loop {
-            if let Some(mut len) = self.chunk_len {
-                match self.lines.poll_next_unpin(cx) {
-                    Poll::Ready(Some(Ok(buf))) => {
-                        if buf.len() > len {
+            match self.state {
+                ChunkedState::Size => {
+                    let line = match self.poll_line(cx) {
+                        Poll::Ready(Ok(Some(line))) => line,
+                        Poll::Ready(Ok(None)) => {
                             return Poll::Ready(Some(Err(std::io::Error::new(
-                                std::io::ErrorKind::InvalidData,
-                                "chunck data overflow",
+                                std::io::ErrorKind::UnexpectedEof,
+                                "Chunked body is not terminated by the last chunk",
                             ))));
                         }
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                        Poll::Pending => return Poll::Pending,
+                    };
+
+                    match Self::parse_chunk_size(&line) {
+                        // body last chunk.
+                        Ok(0) => self.state = ChunkedState::Trailer,
+                        Ok(len) => self.state = ChunkedState::Data(len),
+                        Err(err) => {
+                            self.state = ChunkedState::Finished;
+                            return Poll::Ready(Some(Err(err)));
+                        }
+                    }
+                }
+                ChunkedState::Data(len) => {
+                    let buf = match Pin::new(&mut self.reader).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(buf)) => buf,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                        Poll::Pending => return Poll::Pending,
+                    };
+
+                    if buf.is_empty() {
+                        self.state = ChunkedState::Finished;
+
+                        return Poll::Ready(Some(Err(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            "Incomplete chunk data",
+                        ))));
+                    }
 
-                        len -= buf.len();
+                    let read_size = buf.len().min(len);
 
-                        if len == 0 {
-                            self.chunk_len.take();
-                        } else {
-                            self.chunk_len = Some(len);
-                        }
+                    let chunk = buf[..read_size].to_vec();
 
-                        return Poll::Ready(Some(Ok(buf.into_bytes())));
+                    Pin::new(&mut self.reader).consume(read_size);
+
+                    if read_size == len {
+                        self.state = ChunkedState::DataEnd;
+                    } else {
+                        self.state = ChunkedState::Data(len - read_size);
                     }
-                    poll => return poll.map_ok(|s| s.into_bytes()),
+
+                    return Poll::Ready(Some(Ok(chunk)));
                 }
-            } else {
-                match self.lines.poll_next_unpin(cx) {
-                    Poll::Ready(Some(Ok(line))) => match usize::from_str_radix(&line, 16) {
-                        Ok(len) => {
-                            // body last chunk.
-                            if len == 0 {
-                                return Poll::Ready(None);
-                            }
-
-                            self.chunk_len = Some(len);
-                            continue;
-                        }
-                        Err(err) => {
-                            return Poll::Ready(Some(Err(std::io::Error::new(
-                                std::io::ErrorKind::InvalidData,
-                                format!("Parse chunck length with error: {}", err),
-                            ))))
+                ChunkedState::DataEnd => match self.poll_line(cx) {
+                    Poll::Ready(Ok(Some(line))) if line.is_empty() => {
+                        self.state = ChunkedState::Size;
+                    }
+                    Poll::Ready(Ok(_)) => {
+                        self.state = ChunkedState::Finished;
+
+                        return Poll::Ready(Some(Err(invalid_chunked_data(
+                            "chunck data overflow",
+                        ))));
+                    }
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ChunkedState::Trailer => match self.poll_line(cx) {
+                    // Some peers omit the final CRLF, so EOF is treated as the end of the trailer section.
+                    Poll::Ready(Ok(None)) => self.state = ChunkedState::Finished,
+                    Poll::Ready(Ok(Some(line))) => {
+                        if line.is_empty() {
+                            self.state = ChunkedState::Finished;
                         }
-                    },
-                    poll => return poll.map_ok(|s| s.into_bytes()),
-                }
+                    }
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ChunkedState::Finished => return Poll::Ready(None),
             }
         }
     }
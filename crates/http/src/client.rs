@@ -31,20 +31,41 @@ impl HttpSend for Request<BodyReader> {
 #[cfg(feature = "with_rasi")]
 pub mod rasio {
     use std::{
+        collections::HashMap,
         future::Future,
         io::{Error, ErrorKind, Result},
         net::{SocketAddr, ToSocketAddrs},
         path::{Path, PathBuf},
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
     };
 
+    use bytes::BytesMut;
+    use futures::{io::Cursor, task::noop_waker, AsyncRead, AsyncReadExt, AsyncWrite};
     use futures_boring::{
         connect,
         ssl::{SslConnector, SslMethod},
     };
-    use http::{uri::Scheme, Request, Response};
+    use http::{
+        header::{CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING},
+        uri::Scheme,
+        HeaderMap, Method, Request, Response, StatusCode, Uri, Version,
+    };
+    #[cfg(unix)]
+    use rasi::net::unix::UnixStream;
     use rasi::net::TcpStream;
 
-    use crate::body::BodyReader;
+    use crate::{
+        body::BodyReader,
+        reader::{ParseError, Responser},
+        writer::HttpWriter,
+    };
+
+    /// A transport stream to http server.
+    pub trait Transport: AsyncRead + AsyncWrite + Send + Unpin {}
+
+    impl<T> Transport for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
 
     /// Options and flags which can be used to configure how a http client is opened.
     #[derive(Default, Debug, Clone)]
@@ -53,6 +74,8 @@ pub mod rasio {
         server_name: Option<String>,
         ca_file: Option<PathBuf>,
         use_server_name_indication: bool,
+        #[cfg(unix)]
+        unix_socket: Option<PathBuf>,
     }
 
     impl HttpClientOptions {
@@ -66,24 +89,19 @@ pub mod rasio {
             }
         }
 
-        async fn send(self, request: Request<BodyReader>) -> Result<Response<BodyReader>> {
-            let scheme = request.uri().scheme().ok_or(Error::new(
-                ErrorKind::InvalidInput,
-                "Unspecified request scheme",
-            ))?;
-
-            let host = request.uri().host().ok_or(Error::new(
-                ErrorKind::InvalidInput,
-                "Unspecified request uri",
-            ))?;
-
-            let port = request.uri().port_u16().unwrap_or_else(|| {
-                if scheme == &Scheme::HTTP {
-                    80
-                } else {
-                    443
-                }
-            });
+        /// Open a new transport stream to the server of `uri`.
+        ///
+        /// The stream is secured by tls if the scheme of `uri` is `https` or `wss`,
+        /// this is useful to run other protocols upgraded from http, e.g. websocket.
+        pub async fn connect(&self, uri: &Uri) -> Result<Box<dyn Transport>> {
+            let (scheme, host, port) = split_uri(uri)?;
+
+            #[cfg(unix)]
+            if let Some(unix_socket) = &self.unix_socket {
+                let stream = UnixStream::connect(unix_socket).await?;
+
+                return self.handshake(scheme, host, stream).await;
+            }
 
             let raddrs = if let Some(raddrs) = &self.raddrs {
                 raddrs.to_owned()
@@ -93,37 +111,80 @@ pub mod rasio {
                     .collect::<Vec<_>>()
             };
 
-            if scheme == &Scheme::HTTP {
-                let transport = TcpStream::connect(raddrs.as_slice()).await?;
+            let stream = TcpStream::connect(raddrs.as_slice()).await?;
 
-                return super::HttpSend::send(request, transport).await;
-            } else {
-                let stream = TcpStream::connect(raddrs.as_slice()).await?;
+            self.handshake(scheme, host, stream).await
+        }
+
+        /// Returns `stream` for `http`/`ws` scheme, or a tls stream on top of `stream` otherwise.
+        async fn handshake<S>(
+            &self,
+            scheme: &Scheme,
+            host: &str,
+            stream: S,
+        ) -> Result<Box<dyn Transport>>
+        where
+            S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        {
+            if is_plaintext(scheme) {
+                return Ok(Box::new(stream));
+            }
 
-                let mut config = SslConnector::builder(SslMethod::tls_client())
+            let mut config = SslConnector::builder(SslMethod::tls_client())
+                .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+
+            if let Some(ca_file) = self.ca_file.to_owned() {
+                log::trace!("load trust root ca: {:?}", ca_file);
+
+                config
+                    .set_ca_file(ca_file)
                     .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+            }
 
-                if let Some(ca_file) = self.ca_file.to_owned() {
-                    log::trace!("load trust root ca: {:?}", ca_file);
+            let mut config = config.build().configure().unwrap();
 
-                    config
-                        .set_ca_file(ca_file)
-                        .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
-                }
+            config.set_use_server_name_indication(self.use_server_name_indication);
 
-                let mut config = config.build().configure().unwrap();
+            let domain = self.server_name.as_deref().unwrap_or(host);
 
-                config.set_use_server_name_indication(self.use_server_name_indication);
+            let transport = connect(config, domain, stream)
+                .await
+                .map_err(|err| Error::new(ErrorKind::ConnectionRefused, err.to_string()))?;
 
-                let transport = connect(config, host, stream)
-                    .await
-                    .map_err(|err| Error::new(ErrorKind::ConnectionRefused, err))?;
+            Ok(Box::new(transport))
+        }
 
-                return super::HttpSend::send(request, transport).await;
-            }
+        async fn send(self, request: Request<BodyReader>) -> Result<Response<BodyReader>> {
+            let transport = self.connect(request.uri()).await?;
+
+            super::HttpSend::send(request, transport).await
         }
     }
 
+    /// Returns the scheme, host and port of `uri`.
+    fn split_uri(uri: &Uri) -> Result<(&Scheme, &str, u16)> {
+        let scheme = uri.scheme().ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            "Unspecified request scheme",
+        ))?;
+
+        let host = uri.host().ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            "Unspecified request uri",
+        ))?;
+
+        let port = uri
+            .port_u16()
+            .unwrap_or_else(|| if is_plaintext(scheme) { 80 } else { 443 });
+
+        Ok((scheme, host, port))
+    }
+
+    /// Returns true if `scheme` is `http` or `ws`.
+    fn is_plaintext(scheme: &Scheme) -> bool {
+        scheme == &Scheme::HTTP || scheme.as_str().eq_ignore_ascii_case("ws")
+    }
+
     impl TryInto<HttpClientOptions> for &HttpClientOptions {
         type Error = std::io::Error;
 
@@ -177,6 +238,17 @@ pub mod rasio {
             })
         }
 
+        /// Connect to the unix domain socket at `path` instead of the request uri's host:port,
+        /// the uri's host is still used as the `Host` header.
+        #[cfg(unix)]
+        pub fn with_unix_socket<P: AsRef<Path>>(self, path: P) -> Self {
+            self.and_then(|mut ops| {
+                ops.unix_socket = Some(path.as_ref().to_path_buf());
+
+                Ok(ops)
+            })
+        }
+
         fn and_then<F>(self, func: F) -> Self
         where
             F: FnOnce(HttpClientOptions) -> Result<HttpClientOptions>,
@@ -216,4 +288,263 @@ pub mod rasio {
             }
         }
     }
+
+    /// A http client that keeps connections alive and reuses them across requests.
+    ///
+    /// Idle connections are grouped by the scheme, host and port of the request uri.
+    #[derive(Clone)]
+    pub struct HttpClientPool {
+        inner: Arc<RawHttpClientPool>,
+    }
+
+    struct RawHttpClientPool {
+        ops: HttpClientOptions,
+        max_idle_per_host: usize,
+        idle: Mutex<HashMap<String, Vec<IdleConn>>>,
+    }
+
+    /// An idle connection with its read buffer, which is reused to parse the next response.
+    struct IdleConn {
+        transport: Box<dyn Transport>,
+        buf: BytesMut,
+    }
+
+    impl RawHttpClientPool {
+        fn take_idle(&self, key: &str) -> Option<IdleConn> {
+            let mut idle = self.idle.lock().unwrap();
+
+            let conns = idle.get_mut(key)?;
+
+            while let Some(mut conn) = conns.pop() {
+                if !is_closed(&mut conn.transport) {
+                    return Some(conn);
+                }
+            }
+
+            None
+        }
+
+        fn release(&self, key: String, conn: IdleConn) {
+            let mut idle = self.idle.lock().unwrap();
+
+            let conns = idle.entry(key).or_default();
+
+            if conns.len() < self.max_idle_per_host {
+                conns.push(conn);
+            }
+        }
+    }
+
+    impl HttpClientPool {
+        /// Create a new pool that opens connections with `ops`, and keeps at most
+        /// `max_idle_per_host` idle connections for each server.
+        pub fn new<Op>(ops: Op, max_idle_per_host: usize) -> Result<Self>
+        where
+            Op: TryInto<HttpClientOptions, Error = std::io::Error>,
+        {
+            Ok(Self {
+                inner: Arc::new(RawHttpClientPool {
+                    ops: ops.try_into()?,
+                    max_idle_per_host,
+                    idle: Default::default(),
+                }),
+            })
+        }
+
+        /// Returns the number of idle connections in this pool.
+        pub fn idle_len(&self) -> usize {
+            self.inner.idle.lock().unwrap().values().map(Vec::len).sum()
+        }
+
+        /// Send `request` via an idle connection or a new one.
+        ///
+        /// The response body is read from the connection on demand, the connection is returned to
+        /// the pool once the whole body has been read.
+        pub async fn send(&self, request: Request<BodyReader>) -> Result<Response<BodyReader>> {
+            let (scheme, host, port) = split_uri(request.uri())?;
+
+            let key = format!("{}://{}:{}", scheme, host, port);
+
+            let keep_alive =
+                request.version() == Version::HTTP_11 && !is_connection_close(request.headers());
+
+            let head = request.method() == Method::HEAD;
+
+            let IdleConn { transport, mut buf } = match self.inner.take_idle(&key) {
+                Some(conn) => conn,
+                None => IdleConn {
+                    transport: self.inner.ops.connect(request.uri()).await?,
+                    buf: BytesMut::new(),
+                },
+            };
+
+            let release = Arc::new(Release {
+                key,
+                pool: self.inner.clone(),
+                state: Default::default(),
+            });
+
+            let mut transport = PooledTransport {
+                transport: Some(transport),
+                buf: BytesMut::new(),
+                release: release.clone(),
+            };
+
+            transport.write_request(request).await?;
+
+            let (parts, mut transport) = Responser::new(transport)
+                .parse_parts_with_buf(&mut buf)
+                .await?;
+
+            // the bytes following the header parts, the rest of the buffer goes back to the pool
+            // with the connection.
+            let cached = buf.split().freeze();
+
+            transport.buf = buf;
+
+            let keep_alive = keep_alive
+                && parts.version == Version::HTTP_11
+                && !is_connection_close(&parts.headers);
+
+            if head || !has_body(parts.status) {
+                if keep_alive && cached.is_empty() {
+                    release.reusable();
+                }
+
+                return Ok(Response::from_parts(parts, BodyReader::empty()));
+            }
+
+            let framed = parts.headers.contains_key(TRANSFER_ENCODING)
+                || parts.headers.contains_key(CONTENT_LENGTH);
+
+            let body = BodyReader::parse(&parts.headers, Cursor::new(cached).chain(transport))
+                .await
+                .map_err(ParseError::from)?;
+
+            let body = if keep_alive && framed {
+                body.on_finished(move || release.reusable())
+            } else {
+                body
+            };
+
+            Ok(Response::from_parts(parts, body))
+        }
+    }
+
+    #[derive(Default)]
+    struct ReleaseState {
+        reusable: bool,
+        conn: Option<IdleConn>,
+    }
+
+    /// Returns a connection to [`HttpClientPool`] once the response has been read and the
+    /// connection has been dropped, whichever comes last.
+    struct Release {
+        key: String,
+        pool: Arc<RawHttpClientPool>,
+        state: Mutex<ReleaseState>,
+    }
+
+    impl Release {
+        /// Mark the connection as reusable.
+        fn reusable(&self) {
+            let mut state = self.state.lock().unwrap();
+
+            state.reusable = true;
+
+            if let Some(conn) = state.conn.take() {
+                self.pool.release(self.key.clone(), conn);
+            }
+        }
+
+        fn dropped(&self, conn: IdleConn) {
+            let mut state = self.state.lock().unwrap();
+
+            if state.reusable {
+                self.pool.release(self.key.clone(), conn);
+            } else {
+                state.conn = Some(conn);
+            }
+        }
+    }
+
+    /// A connection borrowed from [`HttpClientPool`].
+    struct PooledTransport {
+        transport: Option<Box<dyn Transport>>,
+        /// The read buffer of the connection.
+        buf: BytesMut,
+        release: Arc<Release>,
+    }
+
+    impl Drop for PooledTransport {
+        fn drop(&mut self) {
+            if let Some(transport) = self.transport.take() {
+                self.release.dropped(IdleConn {
+                    transport,
+                    buf: std::mem::take(&mut self.buf),
+                });
+            }
+        }
+    }
+
+    impl AsyncRead for PooledTransport {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize>> {
+            Pin::new(self.transport.as_mut().unwrap()).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for PooledTransport {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize>> {
+            Pin::new(self.transport.as_mut().unwrap()).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            Pin::new(self.transport.as_mut().unwrap()).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            Pin::new(self.transport.as_mut().unwrap()).poll_close(cx)
+        }
+    }
+
+    /// Checks if an idle `transport` was closed by peer, or received unexpected data.
+    fn is_closed(transport: &mut Box<dyn Transport>) -> bool {
+        let waker = noop_waker();
+
+        let mut cx = Context::from_waker(&waker);
+
+        let mut buf = [0u8; 1];
+
+        !Pin::new(transport)
+            .poll_read(&mut cx, &mut buf)
+            .is_pending()
+    }
+
+    fn is_connection_close(headers: &HeaderMap) -> bool {
+        headers.get_all(CONNECTION).iter().any(|value| {
+            value
+                .to_str()
+                .map(|value| {
+                    value
+                        .split(',')
+                        .any(|token| token.trim().eq_ignore_ascii_case("close"))
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    /// Responses to these status codes never carry a body.
+    pub(crate) fn has_body(status: StatusCode) -> bool {
+        !(status.is_informational()
+            || status == StatusCode::NO_CONTENT
+            || status == StatusCode::NOT_MODIFIED)
+    }
 }
@@ -0,0 +1,391 @@
+//! A fluent http client api on top of [`client::rasio`](crate::client::rasio).
+//!
+//! ```no_run
+//! # async fn example() -> futures_http::fluent::ClientResult<()> {
+//! use futures_http::{
+//!     client::rasio::HttpClientOptions,
+//!     fluent::{Client, ResponseExt},
+//! };
+//!
+//! let client = Client::new(HttpClientOptions::new())?;
+//!
+//! let text = client
+//!     .get("http://localhost/hello")
+//!     .bearer_auth("token")
+//!     .send()
+//!     .await?
+//!     .error_for_status()
+//!     .await?
+//!     .text()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{future::Future, io, time::Duration};
+
+use futures::TryStreamExt;
+#[cfg(feature = "json")]
+use http::header::CONTENT_TYPE;
+use http::{
+    header::AUTHORIZATION, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri,
+};
+use rasi::timer::TimeoutExt;
+
+use crate::{
+    body::BodyReader,
+    client::rasio::{HttpClient, HttpClientOptions, HttpClientPool},
+};
+
+/// The max length of the response body carried by [`ClientError::Status`].
+const MAX_ERROR_BODY_LEN: usize = 64 * 1024;
+
+/// Error type returned by [`RequestBuilder`] and [`ResponseExt`].
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Http(#[from] http::Error),
+
+    #[error("Request timeout, duration={0:?}")]
+    Timeout(Duration),
+
+    #[error("Body length too long, limit={0}")]
+    BodyTooLarge(usize),
+
+    /// A non-2xx response, carrying at most 64KiB of the response body.
+    #[error("Response with status {status}")]
+    Status { status: StatusCode, body: Vec<u8> },
+
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Form(#[from] serde_urlencoded::ser::Error),
+}
+
+impl ClientError {
+    /// Returns the status code of a non-2xx response error.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl From<ClientError> for io::Error {
+    fn from(value: ClientError) -> Self {
+        match value {
+            ClientError::Io(err) => err,
+            ClientError::Timeout(_) => io::Error::new(io::ErrorKind::TimedOut, value),
+            err => io::Error::other(err),
+        }
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// A http client to build and send requests fluently.
+#[derive(Clone)]
+pub struct Client {
+    sender: Sender,
+}
+
+#[derive(Clone)]
+enum Sender {
+    Ops(HttpClientOptions),
+    Pool(HttpClientPool),
+}
+
+impl From<HttpClientPool> for Client {
+    fn from(value: HttpClientPool) -> Self {
+        Self {
+            sender: Sender::Pool(value),
+        }
+    }
+}
+
+impl Client {
+    /// Create a client that opens a new connection with `ops` for each request.
+    pub fn new<Op>(ops: Op) -> io::Result<Self>
+    where
+        Op: TryInto<HttpClientOptions, Error = std::io::Error>,
+    {
+        Ok(Self {
+            sender: Sender::Ops(ops.try_into()?),
+        })
+    }
+
+    /// Create a client that reuses connections, see [`HttpClientPool`] for more details.
+    pub fn pooled<Op>(ops: Op, max_idle_per_host: usize) -> io::Result<Self>
+    where
+        Op: TryInto<HttpClientOptions, Error = std::io::Error>,
+    {
+        Ok(HttpClientPool::new(ops, max_idle_per_host)?.into())
+    }
+
+    /// Start building a `GET` request to `uri`.
+    pub fn get<T>(&self, uri: T) -> RequestBuilder
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.request(Method::GET, uri)
+    }
+
+    /// Start building a `POST` request to `uri`.
+    pub fn post<T>(&self, uri: T) -> RequestBuilder
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.request(Method::POST, uri)
+    }
+
+    /// Start building a `PUT` request to `uri`.
+    pub fn put<T>(&self, uri: T) -> RequestBuilder
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.request(Method::PUT, uri)
+    }
+
+    /// Start building a `DELETE` request to `uri`.
+    pub fn delete<T>(&self, uri: T) -> RequestBuilder
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.request(Method::DELETE, uri)
+    }
+
+    /// Start building a request with `method` to `uri`.
+    pub fn request<T>(&self, method: Method, uri: T) -> RequestBuilder
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        RequestBuilder {
+            client: self.clone(),
+            request: Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(BodyReader::empty())
+                .map_err(Into::into),
+            timeout: None,
+        }
+    }
+
+    /// Send `request` and returns the [`Response`] from peer.
+    pub async fn send(&self, request: Request<BodyReader>) -> ClientResult<Response<BodyReader>> {
+        let response = match &self.sender {
+            Sender::Ops(ops) => request.send(ops).await?,
+            Sender::Pool(pool) => pool.send(request).await?,
+        };
+
+        Ok(response)
+    }
+}
+
+/// A builder to create and send a request by [`Client`].
+pub struct RequestBuilder {
+    client: Client,
+    request: ClientResult<Request<BodyReader>>,
+    timeout: Option<Duration>,
+}
+
+impl RequestBuilder {
+    /// Append a header to the request.
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.and_then(|mut request| {
+            let key = HeaderName::try_from(key).map_err(Into::into)?;
+            let value = HeaderValue::try_from(value).map_err(Into::into)?;
+
+            request.headers_mut().append(key, value);
+
+            Ok(request)
+        })
+    }
+
+    /// Set the `Authorization` header with a bearer `token`.
+    pub fn bearer_auth<T: std::fmt::Display>(self, token: T) -> Self {
+        self.and_then(|mut request| {
+            let mut value =
+                HeaderValue::try_from(format!("Bearer {}", token)).map_err(http::Error::from)?;
+
+            value.set_sensitive(true);
+
+            request.headers_mut().insert(AUTHORIZATION, value);
+
+            Ok(request)
+        })
+    }
+
+    /// Set the request body.
+    pub fn body<B: Into<BodyReader>>(self, body: B) -> Self {
+        self.and_then(|mut request| {
+            *request.body_mut() = body.into();
+
+            Ok(request)
+        })
+    }
+
+    /// Set the request body to the json serialization of `value`,
+    /// and the `Content-Type` header to `application/json`.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(self, value: &T) -> Self {
+        self.and_then(|mut request| {
+            let body = serde_json::to_vec(value)?;
+
+            request
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            *request.body_mut() = body.into();
+
+            Ok(request)
+        })
+    }
+
+    /// Set the request body to the url encoded serialization of `value`,
+    /// and the `Content-Type` header to `application/x-www-form-urlencoded`.
+    #[cfg(feature = "json")]
+    pub fn form<T: serde::Serialize + ?Sized>(self, value: &T) -> Self {
+        self.and_then(|mut request| {
+            let body = serde_urlencoded::to_string(value)?;
+
+            request.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+
+            *request.body_mut() = body.into();
+
+            Ok(request)
+        })
+    }
+
+    /// Set the timeout duration to wait for the response headers.
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.timeout = Some(duration);
+        self
+    }
+
+    /// Consume builder and returns the built [`Request`].
+    pub fn build(self) -> ClientResult<Request<BodyReader>> {
+        self.request
+    }
+
+    /// Consume builder and send the request.
+    pub async fn send(self) -> ClientResult<Response<BodyReader>> {
+        let request = self.request?;
+
+        let send = self.client.send(request);
+
+        if let Some(duration) = self.timeout {
+            send.timeout(duration)
+                .await
+                .ok_or(ClientError::Timeout(duration))?
+        } else {
+            send.await
+        }
+    }
+
+    fn and_then<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Request<BodyReader>) -> ClientResult<Request<BodyReader>>,
+    {
+        RequestBuilder {
+            request: self.request.and_then(func),
+            ..self
+        }
+    }
+}
+
+/// An extension trait for [`Response`] with body helper methods.
+pub trait ResponseExt: Sized {
+    /// Returns [`ClientError::Status`] if the response status is not 2xx, otherwise returns self.
+    fn error_for_status(self) -> impl Future<Output = ClientResult<Self>>;
+
+    /// Read the whole body, returns [`ClientError::BodyTooLarge`] if it is longer than `limit`.
+    fn bytes_limited(self, limit: usize) -> impl Future<Output = ClientResult<Vec<u8>>>;
+
+    /// Read the whole body as an utf8 string.
+    fn text(self) -> impl Future<Output = ClientResult<String>>;
+
+    /// Read the whole body and deserialize it as json.
+    #[cfg(feature = "json")]
+    fn json<T: serde::de::DeserializeOwned>(self) -> impl Future<Output = ClientResult<T>>;
+}
+
+impl ResponseExt for Response<BodyReader> {
+    async fn error_for_status(self) -> ClientResult<Self> {
+        let status = self.status();
+
+        if status.is_success() {
+            return Ok(self);
+        }
+
+        let mut body = self.into_body();
+
+        let mut buf = vec![];
+
+        // the error body is only a hint, so a failed or truncated read is fine.
+        while let Ok(Some(mut chunk)) = body.try_next().await {
+            buf.append(&mut chunk);
+
+            if buf.len() >= MAX_ERROR_BODY_LEN {
+                buf.truncate(MAX_ERROR_BODY_LEN);
+                break;
+            }
+        }
+
+        Err(ClientError::Status { status, body: buf })
+    }
+
+    async fn bytes_limited(self, limit: usize) -> ClientResult<Vec<u8>> {
+        let mut body = self.into_body();
+
+        if body.len().map(|len| len > limit).unwrap_or(false) {
+            return Err(ClientError::BodyTooLarge(limit));
+        }
+
+        let mut buf = vec![];
+
+        while let Some(mut chunk) = body.try_next().await? {
+            buf.append(&mut chunk);
+
+            if buf.len() > limit {
+                return Err(ClientError::BodyTooLarge(limit));
+            }
+        }
+
+        Ok(buf)
+    }
+
+    async fn text(self) -> ClientResult<String> {
+        Ok(String::from_utf8(self.bytes_limited(usize::MAX).await?)?)
+    }
+
+    #[cfg(feature = "json")]
+    async fn json<T: serde::de::DeserializeOwned>(self) -> ClientResult<T> {
+        Ok(serde_json::from_slice(
+            &self.bytes_limited(usize::MAX).await?,
+        )?)
+    }
+}
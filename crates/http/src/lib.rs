@@ -6,3 +6,8 @@ pub use http as types;
 pub mod body;
 pub mod client;
 pub mod server;
+
+#[cfg(feature = "with_rasi")]
+pub mod fluent;
+#[cfg(feature = "with_rasi")]
+pub mod proxy;
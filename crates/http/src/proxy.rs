@@ -0,0 +1,634 @@
+//! A reverse proxy that forwards requests accepted by [`HttpServer`] to a group of upstream
+//! servers via [`HttpClientPool`].
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::{AsyncRead, AsyncWrite, Stream, TryStreamExt};
+use http::{
+    header::{
+        CONNECTION, CONTENT_LENGTH, FORWARDED, HOST, PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, TE,
+        TRAILER, TRANSFER_ENCODING, UPGRADE,
+    },
+    request::Parts,
+    uri::{Authority, PathAndQuery, Scheme},
+    Error as HttpError, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode,
+    Uri, Version,
+};
+use rasi::{task::spawn_ok, timer::TimeoutExt};
+
+use crate::{
+    body::BodyReader,
+    client::rasio::{has_body, HttpClientOptions, HttpClientOptionsBuilder, HttpClientPool},
+    server::{HttpServer, PeerAddr},
+    writer::HttpWriter,
+};
+
+/// The `X-Forwarded-For` header name.
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Hop-by-hop headers, which are meaningful only for a single transport-level connection.
+const HOP_BY_HOP_HEADERS: [HeaderName; 9] = [
+    CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    HeaderName::from_static("proxy-connection"),
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    UPGRADE,
+];
+
+/// The strategy to choose an upstream server for a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Balance {
+    /// Choose upstream servers in turn.
+    #[default]
+    RoundRobin,
+    /// Choose the upstream server with the fewest in-flight requests.
+    LeastConnections,
+}
+
+/// A builder to create a [`ReverseProxy`].
+pub struct ReverseProxyBuilder {
+    upstreams: std::result::Result<Vec<Uri>, HttpError>,
+    balance: Balance,
+    max_fails: usize,
+    fail_timeout: Duration,
+    max_retries: usize,
+    retry_body_limit: usize,
+    timeout: Duration,
+    max_idle_per_host: usize,
+    preserve_host: bool,
+    send_ops: HttpClientOptionsBuilder,
+}
+
+impl ReverseProxyBuilder {
+    /// Set the upstream selection strategy, defaults to [`Balance::RoundRobin`].
+    pub fn balance(mut self, balance: Balance) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    /// Set the number of consecutive failures after which an upstream server is considered
+    /// unavailable for [`fail_timeout`](Self::fail_timeout). Defaults to 3.
+    pub fn max_fails(mut self, max_fails: usize) -> Self {
+        self.max_fails = max_fails;
+        self
+    }
+
+    /// Set how long an upstream server is skipped after [`max_fails`](Self::max_fails)
+    /// consecutive failures. Defaults to 10 seconds.
+    pub fn fail_timeout(mut self, duration: Duration) -> Self {
+        self.fail_timeout = duration;
+        self
+    }
+
+    /// Set the max number of times an idempotent request is retried on another upstream server.
+    /// Defaults to 2.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the max body length of an idempotent request that is kept in memory to be replayed
+    /// on retry, requests with larger or streaming bodies are never retried. Defaults to 64KiB.
+    pub fn retry_body_limit(mut self, limit: usize) -> Self {
+        self.retry_body_limit = limit;
+        self
+    }
+
+    /// Set the timeout for sending a request and receiving the response header parts from an
+    /// upstream server. Defaults to 30 seconds.
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.timeout = duration;
+        self
+    }
+
+    /// Set the max number of idle connections kept for each upstream server. Defaults to 32.
+    pub fn max_idle_per_host(mut self, max_idle_per_host: usize) -> Self {
+        self.max_idle_per_host = max_idle_per_host;
+        self
+    }
+
+    /// Forward the client's `Host` header instead of rewriting it to the upstream's authority.
+    pub fn preserve_host(mut self, value: bool) -> Self {
+        self.preserve_host = value;
+        self
+    }
+
+    /// Set the upstream servers' verification ca file, this is useful for self signed server.
+    pub fn with_ca_file<P: AsRef<Path>>(mut self, ca_file: P) -> Self {
+        self.send_ops = self.send_ops.with_ca_file(ca_file);
+        self
+    }
+
+    /// Configures the use of Server Name Indication (SNI) when connecting to upstream servers.
+    /// Defaults to true.
+    pub fn set_use_server_name_indication(mut self, value: bool) -> Self {
+        self.send_ops = self.send_ops.set_use_server_name_indication(value);
+        self
+    }
+
+    /// Consume builder and create a new `ReverseProxy` instance.
+    pub fn create(self) -> Result<ReverseProxy> {
+        let upstreams = self
+            .upstreams
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?
+            .into_iter()
+            .map(Upstream::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        if upstreams.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Reverse proxy without upstream servers",
+            ));
+        }
+
+        let pool = HttpClientPool::new(self.send_ops, self.max_idle_per_host)?;
+
+        Ok(ReverseProxy {
+            inner: Arc::new(RawReverseProxy {
+                upstreams,
+                balance: self.balance,
+                next: AtomicUsize::new(0),
+                pool,
+                max_fails: self.max_fails,
+                fail_timeout: self.fail_timeout,
+                max_retries: self.max_retries,
+                retry_body_limit: self.retry_body_limit,
+                timeout: self.timeout,
+                preserve_host: self.preserve_host,
+            }),
+        })
+    }
+}
+
+/// An upstream server of [`ReverseProxy`].
+struct Upstream {
+    scheme: Scheme,
+    authority: Authority,
+    /// The path prefix prepended to the path of forwarded requests.
+    prefix: String,
+    /// The number of in-flight requests.
+    active: AtomicUsize,
+    /// The number of consecutive failures.
+    fails: AtomicUsize,
+    /// The upstream server is skipped until this instant.
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Upstream {
+    fn new(uri: Uri) -> Result<Arc<Self>> {
+        let scheme = uri.scheme().cloned().ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unspecified upstream scheme, {}", uri),
+        ))?;
+
+        let authority = uri.authority().cloned().ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unspecified upstream authority, {}", uri),
+        ))?;
+
+        Ok(Arc::new(Self {
+            scheme,
+            authority,
+            prefix: uri.path().trim_end_matches('/').to_owned(),
+            active: AtomicUsize::new(0),
+            fails: AtomicUsize::new(0),
+            down_until: Mutex::new(None),
+        }))
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        match *self.down_until.lock().unwrap() {
+            Some(down_until) => down_until <= now,
+            None => true,
+        }
+    }
+
+    fn on_success(&self) {
+        self.fails.store(0, Ordering::Release);
+        *self.down_until.lock().unwrap() = None;
+    }
+
+    fn on_failure(&self, max_fails: usize, fail_timeout: Duration) {
+        if self.fails.fetch_add(1, Ordering::AcqRel) + 1 >= max_fails {
+            log::warn!(
+                "upstream {}://{} is unavailable for {:?}",
+                self.scheme,
+                self.authority,
+                fail_timeout
+            );
+
+            self.fails.store(0, Ordering::Release);
+            *self.down_until.lock().unwrap() = Some(Instant::now() + fail_timeout);
+        }
+    }
+
+    /// Returns the uri of the forwarded request.
+    fn rewrite_uri(&self, uri: &Uri) -> Result<Uri> {
+        let path_and_query = uri
+            .path_and_query()
+            .map(PathAndQuery::as_str)
+            .unwrap_or("/");
+
+        Uri::builder()
+            .scheme(self.scheme.clone())
+            .authority(self.authority.clone())
+            .path_and_query(format!("{}{}", self.prefix, path_and_query))
+            .build()
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+    }
+}
+
+/// Counts an in-flight request of an upstream server until dropped.
+struct ActiveGuard(Arc<Upstream>);
+
+impl ActiveGuard {
+    fn new(upstream: Arc<Upstream>) -> Self {
+        upstream.active.fetch_add(1, Ordering::AcqRel);
+        Self(upstream)
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+struct RawReverseProxy {
+    upstreams: Vec<Arc<Upstream>>,
+    balance: Balance,
+    next: AtomicUsize,
+    pool: HttpClientPool,
+    max_fails: usize,
+    fail_timeout: Duration,
+    max_retries: usize,
+    retry_body_limit: usize,
+    timeout: Duration,
+    preserve_host: bool,
+}
+
+/// A reverse proxy with load balancing, passive health checks and retries.
+///
+/// Request and response bodies are streamed between the peers without being buffered.
+#[derive(Clone)]
+pub struct ReverseProxy {
+    inner: Arc<RawReverseProxy>,
+}
+
+impl ReverseProxy {
+    /// Create a new `ReverseProxy` builder with upstream server uris.
+    ///
+    /// The path of an upstream uri is used as the prefix of the forwarded requests' path.
+    pub fn builder<I, T>(upstreams: I) -> ReverseProxyBuilder
+    where
+        I: IntoIterator<Item = T>,
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<HttpError>,
+    {
+        ReverseProxyBuilder {
+            upstreams: upstreams
+                .into_iter()
+                .map(|uri| Uri::try_from(uri).map_err(Into::into))
+                .collect(),
+            balance: Balance::RoundRobin,
+            max_fails: 3,
+            fail_timeout: Duration::from_secs(10),
+            max_retries: 2,
+            retry_body_limit: 64 * 1024,
+            timeout: Duration::from_secs(30),
+            max_idle_per_host: 32,
+            preserve_host: false,
+            send_ops: HttpClientOptions::new(),
+        }
+    }
+
+    /// Forward `request` to an upstream server and returns the upstream's response.
+    ///
+    /// `peer_addr` is the address of the client, which is appended to the `X-Forwarded-For` and
+    /// `Forwarded` headers.
+    pub async fn forward(
+        &self,
+        request: Request<BodyReader>,
+        peer_addr: Option<SocketAddr>,
+    ) -> Result<Response<BodyReader>> {
+        let (mut parts, body) = request.into_parts();
+
+        let raw = &self.inner;
+
+        raw.rewrite_headers(&mut parts, peer_addr)?;
+
+        let head = parts.method == Method::HEAD;
+
+        // only idempotent requests with a small body are kept to be replayed on retry.
+        let (replay, mut body) = if is_idempotent(&parts.method)
+            && body.len().map(|len| len <= raw.retry_body_limit) == Some(true)
+        {
+            let chunks: Vec<Vec<u8>> = body.try_collect().await?;
+
+            (Some(chunks.concat()), None)
+        } else {
+            (None, Some(body))
+        };
+
+        let mut tried = vec![];
+
+        let mut last_error = None;
+
+        while let Some((index, upstream)) = raw.select(&tried) {
+            tried.push(index);
+
+            let body = match &replay {
+                Some(buf) => BodyReader::from(buf.clone()),
+                None => body.take().expect("the request body is consumed"),
+            };
+
+            let request = raw.upstream_request(upstream, &parts, body)?;
+
+            let active = ActiveGuard::new(upstream.clone());
+
+            let response = match raw.pool.send(request).timeout(raw.timeout).await {
+                Some(response) => response,
+                None => Err(Error::new(
+                    ErrorKind::TimedOut,
+                    format!(
+                        "Upstream {}://{} timeout",
+                        upstream.scheme, upstream.authority
+                    ),
+                )),
+            };
+
+            match response {
+                Ok(response) => {
+                    upstream.on_success();
+
+                    let (mut parts, body) = response.into_parts();
+
+                    strip_hop_by_hop_headers(&mut parts.headers);
+
+                    // the response writer generates the framing headers from the body, except
+                    // for the responses without a body.
+                    if !head && has_body(parts.status) {
+                        parts.headers.remove(CONTENT_LENGTH);
+                    }
+
+                    // the in-flight request is also released if the body is dropped early.
+                    let body = body.on_finished(move || drop(active));
+
+                    return Ok(Response::from_parts(parts, body));
+                }
+                Err(err) => {
+                    log::error!(
+                        "forward request to upstream {}://{}, {}",
+                        upstream.scheme,
+                        upstream.authority,
+                        err
+                    );
+
+                    upstream.on_failure(raw.max_fails, raw.fail_timeout);
+
+                    if replay.is_none() || tried.len() > raw.max_retries {
+                        return Err(err);
+                    }
+
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(Error::new(
+            ErrorKind::NotConnected,
+            "No upstream server is available",
+        )))
+    }
+
+    /// Forward `request` like [`forward`](Self::forward), and returns a `502 Bad Gateway` or
+    /// `504 Gateway Timeout` response if no upstream server responds.
+    pub async fn handle(
+        &self,
+        request: Request<BodyReader>,
+        peer_addr: Option<SocketAddr>,
+    ) -> Response<BodyReader> {
+        match self.forward(request, peer_addr).await {
+            Ok(response) => response,
+            Err(err) => {
+                let status = if err.kind() == ErrorKind::TimedOut {
+                    StatusCode::GATEWAY_TIMEOUT
+                } else {
+                    StatusCode::BAD_GATEWAY
+                };
+
+                Response::builder()
+                    .status(status)
+                    .body(BodyReader::empty())
+                    .unwrap()
+            }
+        }
+    }
+
+    /// Accept requests from `server` and forward them in background tasks.
+    ///
+    /// The remote address of each connection is appended to the forwarding headers.
+    pub async fn serve<I, S, E>(self, mut server: HttpServer<I>) -> Result<()>
+    where
+        I: Stream<Item = std::result::Result<S, E>> + Unpin,
+        S: AsyncRead + AsyncWrite + PeerAddr + Send + Unpin + 'static,
+        E: std::error::Error,
+    {
+        loop {
+            let (request, mut write) = server.accept_with_peer().await?;
+
+            let peer_addr = request.extensions().get::<SocketAddr>().copied();
+
+            let proxy = self.clone();
+
+            spawn_ok(async move {
+                let response = proxy.handle(request, peer_addr).await;
+
+                if let Err(err) = write.write_response(response).await {
+                    log::error!("reverse proxy, send response to client, {}", err);
+                }
+            });
+        }
+    }
+}
+
+impl RawReverseProxy {
+    /// Choose an upstream server that has not been tried for the current request.
+    fn select(&self, tried: &[usize]) -> Option<(usize, &Arc<Upstream>)> {
+        let now = Instant::now();
+
+        let untried = (0..self.upstreams.len()).filter(|index| !tried.contains(index));
+
+        let mut candidates = untried
+            .clone()
+            .filter(|index| self.upstreams[*index].is_available(now))
+            .collect::<Vec<_>>();
+
+        // all untried upstream servers are unavailable, try them anyway.
+        if candidates.is_empty() {
+            candidates = untried.collect();
+        }
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let offset = self.next.fetch_add(1, Ordering::Relaxed);
+
+        let index = match self.balance {
+            Balance::RoundRobin => candidates[offset % candidates.len()],
+            Balance::LeastConnections => (0..candidates.len())
+                .map(|i| candidates[(offset + i) % candidates.len()])
+                .min_by_key(|index| self.upstreams[*index].active.load(Ordering::Acquire))
+                .unwrap(),
+        };
+
+        Some((index, &self.upstreams[index]))
+    }
+
+    /// Strip hop-by-hop headers and append the client address to the forwarding headers.
+    fn rewrite_headers(&self, parts: &mut Parts, peer_addr: Option<SocketAddr>) -> Result<()> {
+        let host = match parts.headers.get(HOST) {
+            Some(host) => Some(host.clone()),
+            None => parts
+                .uri
+                .authority()
+                .map(|authority| HeaderValue::from_str(authority.as_str()))
+                .transpose()
+                .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?,
+        };
+
+        strip_hop_by_hop_headers(&mut parts.headers);
+
+        // the request writer generates the framing headers from the body.
+        parts.headers.remove(CONTENT_LENGTH);
+        parts.headers.remove(HOST);
+
+        if let Some(host) = host {
+            parts.headers.insert(HOST, host);
+        }
+
+        let Some(peer_addr) = peer_addr else {
+            return Ok(());
+        };
+
+        let ip = peer_addr.ip();
+
+        let x_forwarded_for = match joined_header_value(&parts.headers, &X_FORWARDED_FOR) {
+            Some(value) => format!("{}, {}", value, ip),
+            None => ip.to_string(),
+        };
+
+        let mut forwarded = if ip.is_ipv6() {
+            format!("for=\"[{}]\"", ip)
+        } else {
+            format!("for={}", ip)
+        };
+
+        if let Some(host) = parts.headers.get(HOST).and_then(|host| host.to_str().ok()) {
+            forwarded.push_str(&format!(";host=\"{}\"", host));
+        }
+
+        forwarded.push_str(&format!(
+            ";proto={}",
+            parts.uri.scheme_str().unwrap_or("http")
+        ));
+
+        if let Some(value) = joined_header_value(&parts.headers, &FORWARDED) {
+            forwarded = format!("{}, {}", value, forwarded);
+        }
+
+        for (name, value) in [(X_FORWARDED_FOR, x_forwarded_for), (FORWARDED, forwarded)] {
+            parts.headers.insert(
+                name,
+                HeaderValue::from_str(&value)
+                    .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Create the request sent to `upstream`.
+    fn upstream_request(
+        &self,
+        upstream: &Upstream,
+        parts: &Parts,
+        body: BodyReader,
+    ) -> Result<Request<BodyReader>> {
+        let mut request = Request::builder()
+            .method(parts.method.clone())
+            .uri(upstream.rewrite_uri(&parts.uri)?)
+            .version(Version::HTTP_11)
+            .body(body)
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+
+        *request.headers_mut() = parts.headers.clone();
+
+        if !self.preserve_host || !request.headers().contains_key(HOST) {
+            request.headers_mut().insert(
+                HOST,
+                HeaderValue::from_str(upstream.authority.as_str())
+                    .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?,
+            );
+        }
+
+        Ok(request)
+    }
+}
+
+/// Remove hop-by-hop headers, including the headers listed in the `Connection` header.
+fn strip_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let listed = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+
+    for name in listed.iter().chain(HOP_BY_HOP_HEADERS.iter()) {
+        headers.remove(name);
+    }
+}
+
+/// Returns all values of the header `name` joined by `, `.
+fn joined_header_value(headers: &HeaderMap, name: &HeaderName) -> Option<String> {
+    let values = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>();
+
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(", "))
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    [
+        Method::GET,
+        Method::HEAD,
+        Method::PUT,
+        Method::DELETE,
+        Method::OPTIONS,
+        Method::TRACE,
+    ]
+    .contains(method)
+}
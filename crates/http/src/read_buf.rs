@@ -21,16 +21,35 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 /// not, and if part of it turns out to be initialized, it must stay initialized.
 pub struct ReadBuf {
     inner: BytesMut,
+    /// The max number of bytes that can be filled into this buffer.
+    limit: usize,
+    /// The number of bytes split off from the front of this buffer.
+    consumed: usize,
 }
 
 impl ReadBuf {
+    #[allow(unused)]
     /// Creates a new `ReadBuf` with the specified capacity.
     ///
     /// The returned `ReadBuf` will be able to hold at least `capacity` bytes
     /// without reallocating.
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_bytes_mut(BytesMut::new(), capacity)
+    }
+
+    /// Creates a new `ReadBuf` on top of `inner`, at most `limit` bytes can be filled into it.
+    ///
+    /// The bytes already in `inner` are kept as the filled region. If every handle split off
+    /// from `inner`'s allocation has been dropped, the allocation is reused.
+    pub fn from_bytes_mut(mut inner: BytesMut, limit: usize) -> Self {
+        if inner.len() < limit {
+            inner.reserve(limit - inner.len());
+        }
+
         Self {
-            inner: BytesMut::with_capacity(capacity),
+            inner,
+            limit,
+            consumed: 0,
         }
     }
 
@@ -38,19 +57,26 @@ impl ReadBuf {
     /// length between 0 and [`ReadBuf::remaining_mut()`]. Note that this *can* be shorter than the
     /// whole remainder of the buffer (this allows non-continuous implementation).
     pub fn chunk_mut(&mut self) -> &mut [u8] {
+        let remaining_mut = self.remaining_mut();
+
+        if remaining_mut == 0 {
+            return &mut [];
+        }
+
         let dst = self.inner.chunk_mut();
 
-        unsafe { &mut *(dst as *mut _ as *mut [u8]) }
+        let len = dst.len().min(remaining_mut);
+
+        unsafe { std::slice::from_raw_parts_mut(dst.as_mut_ptr(), len) }
     }
 
-    #[allow(unused)]
     /// Returns the number of bytes that can be written from the current
     /// position until the end of the buffer is reached.
     ///
     /// This value is greater than or equal to the length of the slice returned
     /// by `chunk_mut()`.
     pub fn remaining_mut(&self) -> usize {
-        self.inner.remaining_mut()
+        self.limit.saturating_sub(self.consumed + self.inner.len())
     }
 
     /// Advance the internal cursor of the BufMut
@@ -72,6 +98,7 @@ impl ReadBuf {
         self.inner
     }
 
+    #[allow(unused)]
     /// Consume [`ReadBuf`] and convert into [`Bytes`]
     pub fn into_bytes(self, advance: Option<usize>) -> Bytes {
         self.into_bytes_mut(advance).into()
@@ -101,6 +128,8 @@ impl ReadBuf {
     /// This is an `O(1)` operation that just increases the reference count and
     /// sets a few indices.
     pub fn split_to(&mut self, at: usize) -> BytesMut {
+        self.consumed += at;
+
         self.inner.split_to(at)
     }
 }
@@ -127,17 +127,41 @@ impl<S> Requester<S> {
 
 impl<S> Requester<S>
 where
-    S: AsyncRead + Unpin + Send + 'static,
+    S: AsyncRead + Unpin,
 {
-    pub async fn parse_parts(mut self) -> ParseResult<(http::request::Parts, Bytes, S)> {
-        // create header parts parse buffer with capacity to `config.parsing_headers_max_buf`
-        let mut read_buf = ReadBuf::with_capacity(self.config.parsing_headers_max_buf);
+    pub async fn parse_parts(self) -> ParseResult<(http::request::Parts, Bytes, S)> {
+        let mut buf = BytesMut::new();
+
+        let (parts, stream) = self.parse_parts_with_buf(&mut buf).await?;
+
+        Ok((parts, buf.freeze(), stream))
+    }
+
+    /// Parse http header parts with a caller-provided buffer.
+    ///
+    /// Bytes already in `buf` are parsed before reading from the stream, and on success the bytes
+    /// following the header parts are left in `buf`. Header values and the uri are sliced out of
+    /// the buffer without copying, so passing the same `buf` to the next parser on a keep-alive
+    /// connection reuses its allocation once the previous header parts have been dropped.
+    pub async fn parse_parts_with_buf(
+        mut self,
+        buf: &mut BytesMut,
+    ) -> ParseResult<(http::request::Parts, S)> {
+        // the header parts parse buffer can be filled up to `config.parsing_headers_max_buf` bytes.
+        let mut read_buf =
+            ReadBuf::from_bytes_mut(std::mem::take(buf), self.config.parsing_headers_max_buf);
+
+        loop {
+            self.parse_buffered(&mut read_buf)?;
+
+            if let RequestParseState::Finished = self.state {
+                break;
+            }
 
-        'out: while self.state != RequestParseState::Finished {
             let chunk_mut = read_buf.chunk_mut();
 
             // Checks if the parsing buf is overflowing.
-            if chunk_mut.len() == 0 {
+            if chunk_mut.is_empty() {
                 return Err(ParseError::ParseBufOverflow(
                     self.config.parsing_headers_max_buf,
                 ));
@@ -151,55 +175,44 @@ where
             }
 
             read_buf.advance_mut(read_size);
+        }
+
+        *buf = read_buf.into_bytes_mut(None);
+
+        let (parts, _) = self.builder.unwrap().body(())?.into_parts();
 
-            'inner: while read_buf.chunk().len() > 0 {
-                match self.state {
-                    RequestParseState::Method => {
-                        if !self.parse_method(&mut read_buf)? {
-                            break 'inner;
-                        }
+        Ok((parts, self.stream))
+    }
+
+    /// Parse the bytes already in `read_buf`.
+    fn parse_buffered(&mut self, read_buf: &mut ReadBuf) -> ParseResult<()> {
+        while !read_buf.chunk().is_empty() {
+            match self.state {
+                RequestParseState::Method => {
+                    if !self.parse_method(read_buf)? {
+                        return Ok(());
                     }
-                    RequestParseState::Uri => {
-                        if !self.parse_uri(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                RequestParseState::Uri => {
+                    if !self.parse_uri(read_buf)? {
+                        return Ok(());
                     }
-                    RequestParseState::Version => {
-                        if !self.parse_version(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                RequestParseState::Version => {
+                    if !self.parse_version(read_buf)? {
+                        return Ok(());
                     }
-                    RequestParseState::Headers => {
-                        if !self.parse_header(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                RequestParseState::Headers => {
+                    if !self.parse_header(read_buf)? {
+                        return Ok(());
                     }
-                    RequestParseState::Finished => break 'out,
                 }
-            }
-
-            if let RequestParseState::Finished = self.state {
-                break;
+                RequestParseState::Finished => break,
             }
         }
 
-        let cached = read_buf.into_bytes(None);
-
-        let (parts, _) = self.builder.unwrap().body(())?.into_parts();
-
-        Ok((parts, cached, self.stream))
-    }
-
-    /// Try parse http request header parts and generate [`Request`] object.
-    pub async fn parse(self) -> ParseResult<Request<BodyReader>> {
-        let (parts, cached, stream) = self.parse_parts().await?;
-
-        let stream = Cursor::new(cached).chain(stream);
-
-        let body_reader = BodyReader::parse(&parts.headers, stream).await?;
-
-        // construct [`Request`]
-        Ok(Request::from_parts(parts, body_reader))
+        Ok(())
     }
 
     #[inline]
@@ -240,7 +253,7 @@ where
 
             let buf = read_buf.split_to(len);
 
-            self.set_uri(Uri::from_maybe_shared(buf)?);
+            self.set_uri(Uri::from_maybe_shared(buf.freeze())?);
 
             self.state.next();
 
@@ -318,6 +331,23 @@ where
     }
 }
 
+impl<S> Requester<S>
+where
+    S: AsyncRead + Unpin + Send + 'static,
+{
+    /// Try parse http request header parts and generate [`Request`] object.
+    pub async fn parse(self) -> ParseResult<Request<BodyReader>> {
+        let (parts, cached, stream) = self.parse_parts().await?;
+
+        let stream = Cursor::new(cached).chain(stream);
+
+        let body_reader = BodyReader::parse(&parts.headers, stream).await?;
+
+        // construct [`Request`]
+        Ok(Request::from_parts(parts, body_reader))
+    }
+}
+
 /// Helper function to help parsing stream into [`Request`] instance.
 ///
 /// See [`new_with`](Requester::new) for more information.
@@ -379,17 +409,38 @@ impl<S> Responser<S> {
 
 impl<S> Responser<S>
 where
-    S: AsyncRead + Unpin + Send + 'static,
+    S: AsyncRead + Unpin,
 {
-    pub async fn parse_parts(mut self) -> ParseResult<(Parts, Bytes, S)> {
-        // create header parts parse buffer with capacity to `config.parsing_headers_max_buf`
-        let mut read_buf = ReadBuf::with_capacity(self.config.parsing_headers_max_buf);
+    pub async fn parse_parts(self) -> ParseResult<(Parts, Bytes, S)> {
+        let mut buf = BytesMut::new();
+
+        let (parts, stream) = self.parse_parts_with_buf(&mut buf).await?;
+
+        Ok((parts, buf.freeze(), stream))
+    }
+
+    /// Parse http header parts with a caller-provided buffer.
+    ///
+    /// Bytes already in `buf` are parsed before reading from the stream, and on success the bytes
+    /// following the header parts are left in `buf`. Header values and the uri are sliced out of
+    /// the buffer without copying, so passing the same `buf` to the next parser on a keep-alive
+    /// connection reuses its allocation once the previous header parts have been dropped.
+    pub async fn parse_parts_with_buf(mut self, buf: &mut BytesMut) -> ParseResult<(Parts, S)> {
+        // the header parts parse buffer can be filled up to `config.parsing_headers_max_buf` bytes.
+        let mut read_buf =
+            ReadBuf::from_bytes_mut(std::mem::take(buf), self.config.parsing_headers_max_buf);
+
+        loop {
+            self.parse_buffered(&mut read_buf)?;
+
+            if let ResponseParseState::Finished = self.state {
+                break;
+            }
 
-        'out: while self.state != ResponseParseState::Finished {
             let chunk_mut = read_buf.chunk_mut();
 
             // Checks if the parsing buf is overflowing.
-            if chunk_mut.len() == 0 {
+            if chunk_mut.is_empty() {
                 return Err(ParseError::ParseBufOverflow(
                     self.config.parsing_headers_max_buf,
                 ));
@@ -403,54 +454,44 @@ where
             }
 
             read_buf.advance_mut(read_size);
+        }
 
-            'inner: while read_buf.chunk().len() > 0 {
-                match self.state {
-                    ResponseParseState::Version => {
-                        if !self.parse_version(&mut read_buf)? {
-                            break 'inner;
-                        }
+        *buf = read_buf.into_bytes_mut(None);
+
+        let (parts, _) = self.builder.unwrap().body(())?.into_parts();
+
+        Ok((parts, self.stream))
+    }
+
+    /// Parse the bytes already in `read_buf`.
+    fn parse_buffered(&mut self, read_buf: &mut ReadBuf) -> ParseResult<()> {
+        while !read_buf.chunk().is_empty() {
+            match self.state {
+                ResponseParseState::Version => {
+                    if !self.parse_version(read_buf)? {
+                        return Ok(());
                     }
-                    ResponseParseState::StatusCode => {
-                        if !self.parse_status_code(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                ResponseParseState::StatusCode => {
+                    if !self.parse_status_code(read_buf)? {
+                        return Ok(());
                     }
-                    ResponseParseState::Reason => {
-                        if !self.parse_reason(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                ResponseParseState::Reason => {
+                    if !self.parse_reason(read_buf)? {
+                        return Ok(());
                     }
-                    ResponseParseState::Headers => {
-                        if !self.parse_header(&mut read_buf)? {
-                            break 'inner;
-                        }
+                }
+                ResponseParseState::Headers => {
+                    if !self.parse_header(read_buf)? {
+                        return Ok(());
                     }
-                    ResponseParseState::Finished => break 'out,
                 }
-            }
-
-            if let ResponseParseState::Finished = self.state {
-                break;
+                ResponseParseState::Finished => break,
             }
         }
 
-        let cached = read_buf.into_bytes(None);
-
-        let (parts, _) = self.builder.unwrap().body(())?.into_parts();
-
-        Ok((parts, cached, self.stream))
-    }
-
-    /// Try parse http request header parts and generate [`Request`] object.
-    pub async fn parse(self) -> ParseResult<Response<BodyReader>> {
-        let (parts, cached, stream) = self.parse_parts().await?;
-
-        let stream = Cursor::new(cached).chain(stream);
-
-        let body_reader = BodyReader::parse(&parts.headers, stream).await?;
-
-        Ok(Response::from_parts(parts, body_reader))
+        Ok(())
     }
 
     #[inline]
@@ -561,6 +602,22 @@ where
     }
 }
 
+impl<S> Responser<S>
+where
+    S: AsyncRead + Unpin + Send + 'static,
+{
+    /// Try parse http request header parts and generate [`Request`] object.
+    pub async fn parse(self) -> ParseResult<Response<BodyReader>> {
+        let (parts, cached, stream) = self.parse_parts().await?;
+
+        let stream = Cursor::new(cached).chain(stream);
+
+        let body_reader = BodyReader::parse(&parts.headers, stream).await?;
+
+        Ok(Response::from_parts(parts, body_reader))
+    }
+}
+
 /// Helper function to help parsing stream into [`Response`] instance.
 ///
 /// See [`new_with`](Response::new) for more information.
@@ -727,34 +784,17 @@ fn _skip_newline(buf: &[u8]) -> SkipNewLine {
 
 #[inline]
 fn _skip_newlines(buf: &[u8]) -> SkipNewLine {
-    let mut offset = 0;
-    let mut is_break = false;
-
-    loop {
-        match _skip_newline(&buf[offset..]) {
-            SkipNewLine::Incomplete | SkipNewLine::None => {
-                if is_break {
-                    return SkipNewLine::Break(offset);
-                }
-
-                if offset > 0 {
-                    return SkipNewLine::One(offset);
-                }
-
-                return SkipNewLine::None;
-            }
-            SkipNewLine::One(len) => {
-                if offset > 0 {
-                    is_break = true;
-                }
-
-                offset += len;
-            }
-            SkipNewLine::Break(len) => {
-                is_break = true;
-                offset += len;
-            }
-        }
+    match _skip_newline(buf) {
+        // Only the newline directly following the previous line belongs to the header parts,
+        // the bytes after the header parts break belong to the body.
+        SkipNewLine::One(len) => match _skip_newline(&buf[len..]) {
+            SkipNewLine::One(next) => SkipNewLine::Break(len + next),
+            SkipNewLine::Break(_) => SkipNewLine::Break(len + 1),
+            // keep the first newline in the buffer until we can decide whether it is a break.
+            SkipNewLine::Incomplete => SkipNewLine::Incomplete,
+            SkipNewLine::None => SkipNewLine::One(len),
+        },
+        skip_new_line => skip_new_line,
     }
 }
 
@@ -836,7 +876,8 @@ fn parse_header(read_buf: &mut ReadBuf) -> ParseResult<Option<(HeaderName, Heade
 
     trim_suffix_spaces(&mut buf);
 
-    let header_value = HeaderValue::from_maybe_shared(buf)?;
+    // slice the value out of the shared read buffer instead of copying it.
+    let header_value = HeaderValue::from_maybe_shared(buf.freeze())?;
 
     Ok(Some((header_name, header_value)))
 }
@@ -1201,4 +1242,45 @@ mod tests {
 
         expect_request_empty_method(b" / HTTP/1.1\r\n\r\n").await;
     }
+
+    #[futures_test::test]
+    async fn pipelined_requests_tests() {
+        let first = b"GET /a HTTP/1.1\r\nx-a: hello\r\n\r\n";
+        let second = b"GET /b HTTP/1.1\r\nx-b: world\r\n\r\n";
+
+        let mut stream = Cursor::new([first.as_slice(), second.as_slice()].concat());
+
+        let mut buf = BytesMut::with_capacity(2048);
+
+        let start = buf.as_ptr() as usize;
+        let end = start + buf.capacity();
+
+        let (parts, _) = Requester::new(&mut stream)
+            .parse_parts_with_buf(&mut buf)
+            .await
+            .unwrap();
+
+        assert_eq!(parts.uri, "/a");
+        assert_eq!(parts.headers.get("x-a").unwrap(), "hello");
+
+        // the pipelined request is kept in the buffer.
+        assert_eq!(&buf[..], second.as_slice());
+
+        drop(parts);
+
+        let (parts, _) = Requester::new(&mut stream)
+            .parse_parts_with_buf(&mut buf)
+            .await
+            .unwrap();
+
+        assert_eq!(parts.uri, "/b");
+        assert_eq!(parts.headers.get("x-b").unwrap(), "world");
+        assert!(buf.is_empty());
+
+        // the second parse slices the header value out of the same allocation.
+        let value = parts.headers.get("x-b").unwrap().as_bytes().as_ptr() as usize;
+
+        assert!(value >= start && value < end);
+        assert!(buf.as_ptr() as usize >= start && buf.as_ptr() as usize <= end);
+    }
 }
@@ -2,13 +2,42 @@
 //!
 //!
 
-use std::io::{Error, ErrorKind, Result};
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::SocketAddr,
+};
 
 use futures::{io::WriteHalf, AsyncRead, AsyncReadExt, AsyncWrite, Stream, StreamExt};
 use http::{Request, Response, StatusCode};
 
 use crate::{body::BodyReader, reader::Requester, writer::HttpWriter};
 
+/// A connection accepted by [`HttpServer`] that knows its remote address.
+pub trait PeerAddr {
+    /// Returns the remote address of this connection, or `None` if it is not an ip connection.
+    fn peer_addr(&self) -> Option<SocketAddr>;
+}
+
+impl<S: PeerAddr> PeerAddr for futures_boring::SslStream<S> {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.get_ref().peer_addr()
+    }
+}
+
+#[cfg(feature = "with_rasi")]
+impl PeerAddr for rasi::net::TcpStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.as_raw_ptr().peer_addr().ok()
+    }
+}
+
+#[cfg(all(feature = "with_rasi", unix))]
+impl PeerAddr for rasi::net::unix::UnixStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+}
+
 pub struct HttpServer<I> {
     /// debug information.
     label: Option<String>,
@@ -31,6 +60,30 @@ impl<I> HttpServer<I> {
         I: Stream<Item = std::result::Result<S, E>> + Unpin,
         S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
         E: std::error::Error,
+    {
+        self.accept_priv(|_| None).await
+    }
+
+    /// Accept new incoming http connection like [`accept`](Self::accept), and inserts the remote
+    /// address of the connection, if any, into the request's extensions as a [`SocketAddr`].
+    pub async fn accept_with_peer<S, E>(&mut self) -> Result<(Request<BodyReader>, WriteHalf<S>)>
+    where
+        I: Stream<Item = std::result::Result<S, E>> + Unpin,
+        S: AsyncRead + AsyncWrite + PeerAddr + Send + Unpin + 'static,
+        E: std::error::Error,
+    {
+        self.accept_priv(PeerAddr::peer_addr).await
+    }
+
+    async fn accept_priv<S, E, F>(
+        &mut self,
+        peer_addr: F,
+    ) -> Result<(Request<BodyReader>, WriteHalf<S>)>
+    where
+        I: Stream<Item = std::result::Result<S, E>> + Unpin,
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        E: std::error::Error,
+        F: Fn(&S) -> Option<SocketAddr>,
     {
         loop {
             match self
@@ -40,10 +93,18 @@ impl<I> HttpServer<I> {
                 .ok_or(Error::new(ErrorKind::BrokenPipe, "http server shutdown."))?
             {
                 Ok(stream) => {
+                    let peer_addr = peer_addr(&stream);
+
                     let (read, mut write) = stream.split();
 
                     let request = match Requester::new(read).parse().await {
-                        Ok(request) => request,
+                        Ok(mut request) => {
+                            if let Some(peer_addr) = peer_addr {
+                                request.extensions_mut().insert(peer_addr);
+                            }
+
+                            request
+                        }
                         Err(err) => {
                             log::error!(
                                 "{}, parse request error,{}",
@@ -92,3 +153,25 @@ impl<I> HttpServer<I> {
         }))
     }
 }
+
+#[cfg(feature = "with_rasi")]
+impl HttpServer<rasi::net::TcpListener> {
+    /// Start http server on a tcp listener bound to `laddrs`.
+    pub async fn bind<A: std::net::ToSocketAddrs>(label: Option<&str>, laddrs: A) -> Result<Self> {
+        Ok(Self::on(label, rasi::net::TcpListener::bind(laddrs).await?))
+    }
+}
+
+#[cfg(all(feature = "with_rasi", unix))]
+impl HttpServer<rasi::net::unix::UnixListener> {
+    /// Start http server on a unix domain socket bound to `path`.
+    pub async fn bind_unix<P: AsRef<std::path::Path>>(
+        label: Option<&str>,
+        path: P,
+    ) -> Result<Self> {
+        Ok(Self::on(
+            label,
+            rasi::net::unix::UnixListener::bind(path).await?,
+        ))
+    }
+}
@@ -28,7 +28,11 @@ pub trait HttpWriter: AsyncWrite + Unpin {
                 format!(
                     "{} {} {:?}\r\n",
                     parts.method,
-                    parts.uri.path(),
+                    parts
+                        .uri
+                        .path_and_query()
+                        .map(|path| path.as_str())
+                        .unwrap_or("/"),
                     parts.version
                 )
                 .as_bytes(),
@@ -71,23 +75,23 @@ pub trait HttpWriter: AsyncWrite + Unpin {
             self.write_all(b"\r\n").await?;
 
             if let Some(len) = body.len() {
-                let body = body
-                    .try_next()
-                    .await?
-                    .expect("Ready fixed length body error");
-
-                assert_eq!(body.len(), len);
-
-                self.write_all(&body).await?;
+                self.write_fixed_length_body(len, body).await?;
             } else {
                 while let Some(chunk) = body.try_next().await? {
+                    // an empty chunk is the last-chunk marker, skip it.
+                    if chunk.is_empty() {
+                        continue;
+                    }
+
                     self.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
                         .await?;
 
                     self.write_all(&chunk).await?;
+
+                    self.write_all(b"\r\n").await?;
                 }
 
-                self.write_all(b"0\r\n").await?;
+                self.write_all(b"0\r\n\r\n").await?;
             }
 
             Ok(())
@@ -117,18 +121,17 @@ pub trait HttpWriter: AsyncWrite + Unpin {
                 .await?;
             }
 
-            if let Some(len) = body.len() {
+            if body.len() == Some(0) && parts.headers.contains_key(CONTENT_LENGTH) {
+                // a response without a body, e.g. to a `HEAD` request, whose `Content-Length`
+                // describes the representation instead of the body.
+                self.write_all(b"\r\n").await?;
+            } else if let Some(len) = body.len() {
                 self.write_all(format!("{}: {}\r\n", CONTENT_LENGTH, len).as_bytes())
                     .await?;
 
                 self.write_all(b"\r\n").await?;
 
-                let body = body
-                    .try_next()
-                    .await?
-                    .expect("Ready fixed length body error");
-
-                self.write_all(&body).await?;
+                self.write_fixed_length_body(len, body).await?;
             } else {
                 self.write_all(format!("{}: chunked\r\n", TRANSFER_ENCODING).as_bytes())
                     .await?;
@@ -136,10 +139,17 @@ pub trait HttpWriter: AsyncWrite + Unpin {
                 self.write_all(b"\r\n").await?;
 
                 while let Some(chunk) = body.try_next().await? {
+                    // an empty chunk is the last-chunk marker, skip it.
+                    if chunk.is_empty() {
+                        continue;
+                    }
+
                     self.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
                         .await?;
 
                     self.write_all(&chunk).await?;
+
+                    self.write_all(b"\r\n").await?;
                 }
 
                 self.write_all(b"0\r\n\r\n").await?;
@@ -148,6 +158,39 @@ pub trait HttpWriter: AsyncWrite + Unpin {
             Ok(())
         }
     }
+
+    /// Write a body of `len` bytes, which may be yielded by `body` in several chunks.
+    fn write_fixed_length_body(
+        &mut self,
+        len: usize,
+        mut body: BodyReader,
+    ) -> impl Future<Output = Result<()>> {
+        async move {
+            let mut written = 0;
+
+            while let Some(chunk) = body.try_next().await? {
+                written += chunk.len();
+
+                if written > len {
+                    break;
+                }
+
+                self.write_all(&chunk).await?;
+            }
+
+            if written != len {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Body length mismatch, content_length={}, written={}",
+                        len, written
+                    ),
+                ));
+            }
+
+            Ok(())
+        }
+    }
 }
 
 impl<T: AsyncWrite + Unpin> HttpWriter for T {}
@@ -0,0 +1,235 @@
+use std::{
+    net::SocketAddr,
+    sync::{Once, OnceLock},
+    time::Duration,
+};
+
+use futures::{executor::ThreadPool, Future, TryStreamExt};
+use futures_http::{
+    body::BodyReader,
+    client::rasio::HttpClientOptions,
+    fluent::{Client, ClientError, ResponseExt},
+    server::HttpServer,
+    writer::HttpWriter,
+};
+use http::{Response, StatusCode};
+use rasi::{net::TcpListener, timer::sleep};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+use serde::{Deserialize, Serialize};
+
+fn spawn<Fut>(fut: Fut)
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    static THREAD_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+    let thread_pool =
+        THREAD_POOL.get_or_init(|| ThreadPool::builder().pool_size(10).create().unwrap());
+
+    thread_pool.spawn_ok(fut)
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+    })
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Echo {
+    method: String,
+    content_type: String,
+    authorization: String,
+    body: String,
+}
+
+/// Start a server that echoes the request as json, `/missing` responds with 404
+/// and `/slow` responds after one second.
+async fn spawn_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    spawn(async move {
+        let mut server = HttpServer::on(Some("fluent_test"), listener);
+
+        while let Ok((request, mut write)) = server.accept().await {
+            spawn(async move {
+                let (parts, body) = request.into_parts();
+
+                let header = |name: &str| {
+                    parts
+                        .headers
+                        .get(name)
+                        .map(|value| value.to_str().unwrap().to_owned())
+                        .unwrap_or_default()
+                };
+
+                let body: Vec<Vec<u8>> = body.try_collect().await.unwrap();
+
+                let response = match parts.uri.path() {
+                    "/missing" => Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(BodyReader::from("not found"))
+                        .unwrap(),
+                    path => {
+                        if path == "/slow" {
+                            sleep(Duration::from_secs(1)).await;
+                        }
+
+                        let echo = Echo {
+                            method: parts.method.to_string(),
+                            content_type: header("content-type"),
+                            authorization: header("authorization"),
+                            body: String::from_utf8(body.concat()).unwrap(),
+                        };
+
+                        Response::builder()
+                            .status(StatusCode::OK)
+                            .body(BodyReader::from(serde_json::to_vec(&echo).unwrap()))
+                            .unwrap()
+                    }
+                };
+
+                _ = write.write_response(response).await;
+            });
+        }
+    });
+
+    raddr
+}
+
+#[futures_test::test]
+async fn test_json() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let echo: Echo = client
+        .post(format!("http://{}/echo", raddr))
+        .bearer_auth("token")
+        .json(&["hello", "world"])
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        echo,
+        Echo {
+            method: "POST".to_owned(),
+            content_type: "application/json".to_owned(),
+            authorization: "Bearer token".to_owned(),
+            body: r#"["hello","world"]"#.to_owned(),
+        }
+    );
+}
+
+#[futures_test::test]
+async fn test_form() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::pooled(HttpClientOptions::new(), 4).unwrap();
+
+    for _ in 0..2 {
+        let echo: Echo = client
+            .put(format!("http://{}/echo", raddr))
+            .form(&[("a", "1"), ("b", "x y")])
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert_eq!(echo.method, "PUT");
+        assert_eq!(echo.content_type, "application/x-www-form-urlencoded");
+        assert_eq!(echo.body, "a=1&b=x+y");
+    }
+}
+
+#[futures_test::test]
+async fn test_error_for_status() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let err = client
+        .get(format!("http://{}/missing", raddr))
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+
+    match err {
+        ClientError::Status { body, .. } => assert_eq!(body, b"not found"),
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[futures_test::test]
+async fn test_bytes_limited() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let text = client
+        .delete(format!("http://{}/echo", raddr))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(text.contains(r#""method":"DELETE""#));
+
+    let err = client
+        .get(format!("http://{}/echo", raddr))
+        .send()
+        .await
+        .unwrap()
+        .bytes_limited(16)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, ClientError::BodyTooLarge(16)));
+}
+
+#[futures_test::test]
+async fn test_timeout() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let err = client
+        .get(format!("http://{}/slow", raddr))
+        .timeout(Duration::from_millis(100))
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, ClientError::Timeout(_)));
+}
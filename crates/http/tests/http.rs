@@ -3,6 +3,7 @@ use std::{
     sync::{Once, OnceLock},
 };
 
+use bytes::BytesMut;
 use futures::{executor::ThreadPool, Future, TryStreamExt};
 use futures_boring::{
     ssl::{SslAcceptor, SslFiletype, SslMethod},
@@ -10,7 +11,8 @@ use futures_boring::{
 };
 use futures_http::{
     body::BodyReader,
-    client::rasio::{HttpClient, HttpClientOptions},
+    client::rasio::{HttpClient, HttpClientOptions, HttpClientPool},
+    reader::Requester,
     server::HttpServer,
     writer::HttpWriter,
 };
@@ -147,3 +149,113 @@ async fn test_https() {
 
     assert_eq!(body.try_next().await.unwrap().unwrap(), b"hello world");
 }
+
+#[cfg_attr(docsrs, doc(feature = "with_rasi"))]
+#[futures_test::test]
+async fn test_pool_keep_alive() {
+    init();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    // a keep-alive server, which reports the number of requests received on the connection.
+    spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            spawn(async move {
+                let mut buf = BytesMut::new();
+
+                let mut requests = 0;
+
+                while Requester::new(&mut stream)
+                    .parse_parts_with_buf(&mut buf)
+                    .await
+                    .is_ok()
+                {
+                    requests += 1;
+
+                    let response = Response::builder()
+                        .status(StatusCode::OK)
+                        .header("x-requests", requests)
+                        .body(BodyReader::from("hello world"))
+                        .unwrap();
+
+                    if stream.write_response(response).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let pool = HttpClientPool::new(HttpClientOptions::new(), 4).unwrap();
+
+    for i in 1..=3 {
+        let response = pool
+            .send(
+                Request::get(format!("http://{:?}/hello", raddr))
+                    .body(BodyReader::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-requests").unwrap(),
+            &i.to_string()
+        );
+
+        let body: Vec<Vec<u8>> = response.into_body().try_collect().await.unwrap();
+
+        assert_eq!(body.concat(), b"hello world");
+
+        assert_eq!(pool.idle_len(), 1);
+    }
+}
+
+#[cfg(unix)]
+#[futures_test::test]
+async fn test_unix_socket() {
+    init();
+
+    let path = std::env::temp_dir().join(format!("futures-http-{}.sock", std::process::id()));
+
+    _ = std::fs::remove_file(&path);
+
+    let server = HttpServer::bind_unix(Some("unix_test"), &path)
+        .await
+        .unwrap();
+
+    spawn(async move {
+        let mut incoming = server.into_incoming();
+
+        while let Some((req, mut resp)) = incoming.try_next().await.unwrap() {
+            assert_eq!(req.uri().path(), "/hello");
+            assert_eq!(req.headers().get("host").unwrap(), "localhost");
+
+            resp.write_response(
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .body(BodyReader::from("hello world"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        }
+    });
+
+    let response = Request::get("http://localhost/hello")
+        .body(BodyReader::empty())
+        .unwrap()
+        .send(HttpClientOptions::new().with_unix_socket(&path))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (_, mut body) = response.into_parts();
+
+    assert_eq!(body.try_next().await.unwrap().unwrap(), b"hello world");
+
+    _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,411 @@
+//! Property-based tests for the http packet parser.
+//!
+//! Arbitrary requests/responses are written by [`HttpWriter`] and parsed back by
+//! [`Requester`]/[`Responser`], the serialized packets are also fed to the parser
+//! split at every byte boundary to exercise incremental parsing.
+
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{executor::block_on, io::Cursor, stream, AsyncRead, TryStreamExt};
+use futures_http::{
+    body::BodyReader,
+    reader::{Requester, Responser},
+    writer::HttpWriter,
+};
+use http::{
+    header::{CONTENT_LENGTH, TRANSFER_ENCODING},
+    HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Version,
+};
+use quickcheck::{quickcheck, Arbitrary, Gen, TestResult};
+
+/// A reader that returns the provided segments one by one, one segment per `poll_read` call.
+struct SegmentReader(VecDeque<Vec<u8>>);
+
+impl SegmentReader {
+    /// Split `buf` into two segments at `at`.
+    fn split_at(buf: &[u8], at: usize) -> Self {
+        Self(VecDeque::from([buf[..at].to_vec(), buf[at..].to_vec()]))
+    }
+
+    /// Split `buf` into segments of one byte.
+    fn bytewise(buf: &[u8]) -> Self {
+        Self(buf.iter().map(|b| vec![*b]).collect())
+    }
+}
+
+impl AsyncRead for SegmentReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        while let Some(mut segment) = self.0.pop_front() {
+            if segment.is_empty() {
+                continue;
+            }
+
+            let read_size = segment.len().min(buf.len());
+
+            buf[..read_size].copy_from_slice(&segment[..read_size]);
+
+            if read_size < segment.len() {
+                self.0.push_front(segment.split_off(read_size));
+            }
+
+            return Poll::Ready(Ok(read_size));
+        }
+
+        Poll::Ready(Ok(0))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TestBody {
+    /// Body with `CONTENT_LENGTH` header.
+    Fixed(Vec<u8>),
+    /// Body with `TRANSFER_ENCODING: chunked` header.
+    Chunked(Vec<Vec<u8>>),
+}
+
+impl TestBody {
+    fn content(&self) -> Vec<u8> {
+        match self {
+            TestBody::Fixed(buf) => buf.clone(),
+            TestBody::Chunked(chunks) => chunks.concat(),
+        }
+    }
+
+    fn to_body_reader(&self) -> BodyReader {
+        match self {
+            TestBody::Fixed(buf) => BodyReader::from(buf.clone()),
+            TestBody::Chunked(chunks) => BodyReader::from_stream(stream::iter(
+                chunks.clone().into_iter().map(Ok::<_, io::Error>),
+            )),
+        }
+    }
+}
+
+impl Arbitrary for TestBody {
+    fn arbitrary(g: &mut Gen) -> Self {
+        if bool::arbitrary(g) {
+            TestBody::Fixed(Vec::arbitrary(g))
+        } else {
+            let chunks = usize::arbitrary(g) % 5;
+
+            TestBody::Chunked((0..chunks).map(|_| Vec::arbitrary(g)).collect())
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match self {
+            TestBody::Fixed(buf) => Box::new(buf.shrink().map(TestBody::Fixed)),
+            TestBody::Chunked(chunks) => Box::new(chunks.shrink().map(TestBody::Chunked)),
+        }
+    }
+}
+
+fn gen_string(g: &mut Gen, charset: &[u8], min: usize, max: usize) -> String {
+    let len = min + usize::arbitrary(g) % (max - min + 1);
+
+    (0..len)
+        .map(|_| *g.choose(charset).unwrap() as char)
+        .collect()
+}
+
+const TOKEN_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const PATH_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-._~";
+
+fn gen_headers(g: &mut Gen) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for _ in 0..usize::arbitrary(g) % 8 {
+        let name = gen_string(g, TOKEN_CHARS, 1, 16);
+
+        // the parser trims the leading/trailing spaces of header values.
+        let value = format!(
+            "{}{}",
+            gen_string(g, &(b'!'..=b'~').collect::<Vec<_>>(), 1, 16),
+            gen_string(g, &(b' '..=b'~').collect::<Vec<_>>(), 0, 16).trim_end()
+        );
+
+        let name = HeaderName::from_bytes(name.as_bytes()).unwrap();
+
+        if name == CONTENT_LENGTH || name == TRANSFER_ENCODING || name == http::header::HOST {
+            continue;
+        }
+
+        headers.append(name, HeaderValue::from_str(&value).unwrap());
+    }
+
+    headers
+}
+
+fn gen_version(g: &mut Gen) -> Version {
+    *g.choose(&[Version::HTTP_10, Version::HTTP_11]).unwrap()
+}
+
+#[derive(Debug, Clone)]
+struct TestRequest {
+    method: Method,
+    uri: String,
+    version: Version,
+    headers: HeaderMap,
+    body: TestBody,
+}
+
+impl Arbitrary for TestRequest {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let method = if bool::arbitrary(g) {
+            g.choose(&[
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PATCH,
+            ])
+            .unwrap()
+            .clone()
+        } else {
+            Method::from_bytes(gen_string(g, b"ABCDEFGHIJKLMNOPQRSTUVWXYZ", 1, 10).as_bytes())
+                .unwrap()
+        };
+
+        let mut uri = String::new();
+
+        for _ in 0..usize::arbitrary(g) % 4 {
+            uri.push('/');
+            uri.push_str(&gen_string(g, PATH_CHARS, 0, 8));
+        }
+
+        if uri.is_empty() {
+            uri.push('/');
+        }
+
+        if bool::arbitrary(g) {
+            uri.push_str(&format!(
+                "?{}={}",
+                gen_string(g, PATH_CHARS, 1, 8),
+                gen_string(g, PATH_CHARS, 0, 8)
+            ));
+        }
+
+        Self {
+            method,
+            uri,
+            version: gen_version(g),
+            headers: gen_headers(g),
+            body: TestBody::arbitrary(g),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let this = self.clone();
+
+        Box::new(self.body.shrink().map(move |body| TestRequest {
+            body,
+            ..this.clone()
+        }))
+    }
+}
+
+impl TestRequest {
+    fn to_request(&self) -> Request<BodyReader> {
+        let mut request = Request::builder()
+            .method(self.method.clone())
+            .uri(self.uri.as_str())
+            .version(self.version)
+            .body(self.body.to_body_reader())
+            .unwrap();
+
+        *request.headers_mut() = self.headers.clone();
+
+        request
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Cursor::new(vec![]);
+
+        block_on(buf.write_request(self.to_request())).unwrap();
+
+        buf.into_inner()
+    }
+
+    fn check<R>(&self, read: R) -> bool
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        block_on(async {
+            let request = Requester::new(read).parse().await.unwrap();
+
+            let (mut parts, body) = request.into_parts();
+
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.remove(TRANSFER_ENCODING);
+
+            let body: Vec<Vec<u8>> = body.try_collect().await.unwrap();
+
+            parts.method == self.method
+                && parts.uri == self.uri.as_str()
+                && parts.version == self.version
+                && parts.headers == self.headers
+                && body.concat() == self.body.content()
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TestResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: TestBody,
+}
+
+impl Arbitrary for TestResponse {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            status: StatusCode::from_u16(100 + u16::arbitrary(g) % 500).unwrap(),
+            version: gen_version(g),
+            headers: gen_headers(g),
+            body: TestBody::arbitrary(g),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let this = self.clone();
+
+        Box::new(self.body.shrink().map(move |body| TestResponse {
+            body,
+            ..this.clone()
+        }))
+    }
+}
+
+impl TestResponse {
+    fn to_response(&self) -> Response<BodyReader> {
+        let mut response = Response::builder()
+            .status(self.status)
+            .version(self.version)
+            .body(self.body.to_body_reader())
+            .unwrap();
+
+        *response.headers_mut() = self.headers.clone();
+
+        response
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Cursor::new(vec![]);
+
+        block_on(buf.write_response(self.to_response())).unwrap();
+
+        buf.into_inner()
+    }
+
+    fn check<R>(&self, read: R) -> bool
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        block_on(async {
+            let response = Responser::new(read).parse().await.unwrap();
+
+            let (mut parts, body) = response.into_parts();
+
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.remove(TRANSFER_ENCODING);
+
+            let body: Vec<Vec<u8>> = body.try_collect().await.unwrap();
+
+            parts.status == self.status
+                && parts.version == self.version
+                && parts.headers == self.headers
+                && body.concat() == self.body.content()
+        })
+    }
+}
+
+/// Parse `buf` as request and response and drain the body, returns nothing but must not panic.
+fn parse_all(buf: Vec<u8>) {
+    block_on(async {
+        if let Ok(request) = Requester::new(Cursor::new(buf.clone())).parse().await {
+            let mut body = request.into_body();
+            while let Ok(Some(_)) = body.try_next().await {}
+        }
+
+        if let Ok(response) = Responser::new(Cursor::new(buf)).parse().await {
+            let mut body = response.into_body();
+            while let Ok(Some(_)) = body.try_next().await {}
+        }
+    })
+}
+
+quickcheck! {
+    fn prop_request_roundtrip(request: TestRequest) -> bool {
+        request.check(Cursor::new(request.serialize()))
+    }
+
+    fn prop_request_incremental(request: TestRequest) -> bool {
+        let buf = request.serialize();
+
+        (1..buf.len()).all(|at| request.check(SegmentReader::split_at(&buf, at)))
+            && request.check(SegmentReader::bytewise(&buf))
+    }
+
+    fn prop_response_roundtrip(response: TestResponse) -> bool {
+        response.check(Cursor::new(response.serialize()))
+    }
+
+    fn prop_response_incremental(response: TestResponse) -> bool {
+        let buf = response.serialize();
+
+        (1..buf.len()).all(|at| response.check(SegmentReader::split_at(&buf, at)))
+            && response.check(SegmentReader::bytewise(&buf))
+    }
+
+    fn prop_invalid_input_no_panic(buf: Vec<u8>) -> bool {
+        parse_all(buf);
+        true
+    }
+
+    fn prop_mutated_request_no_panic(request: TestRequest, offset: usize, value: u8) -> TestResult {
+        let mut buf = request.serialize();
+
+        if buf.is_empty() {
+            return TestResult::discard();
+        }
+
+        let offset = offset % buf.len();
+
+        buf[offset] = value;
+
+        parse_all(buf.clone());
+        parse_all(buf[..offset].to_vec());
+
+        TestResult::passed()
+    }
+
+    fn prop_mutated_response_no_panic(response: TestResponse, offset: usize, value: u8) -> TestResult {
+        let mut buf = response.serialize();
+
+        if buf.is_empty() {
+            return TestResult::discard();
+        }
+
+        let offset = offset % buf.len();
+
+        buf[offset] = value;
+
+        parse_all(buf.clone());
+        parse_all(buf[..offset].to_vec());
+
+        TestResult::passed()
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    net::SocketAddr,
+    sync::{Once, OnceLock},
+};
+
+use futures::{executor::ThreadPool, stream, Future, TryStreamExt};
+use futures_http::{
+    body::BodyReader,
+    client::rasio::{HttpClient, HttpClientOptions},
+    proxy::{Balance, ReverseProxy},
+    server::HttpServer,
+    writer::HttpWriter,
+};
+use http::{header::HOST, Method, Request, Response, StatusCode};
+use rasi::{net::TcpListener, task::register_futures_spawn};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+
+fn spawn<Fut>(fut: Fut)
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    static THREAD_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+    let thread_pool =
+        THREAD_POOL.get_or_init(|| ThreadPool::builder().pool_size(10).create().unwrap());
+
+    thread_pool.spawn_ok(fut)
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+        register_futures_spawn(10);
+    })
+}
+
+/// Start an upstream server that echoes the request body and the forwarding headers.
+async fn spawn_upstream(name: &'static str) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    spawn(async move {
+        let mut server = HttpServer::on(Some(name), listener);
+
+        while let Ok((request, mut write)) = server.accept().await {
+            spawn(async move {
+                let (parts, body) = request.into_parts();
+
+                let echo = |header: &str| {
+                    parts
+                        .headers
+                        .get(header)
+                        .map(|value| value.to_str().unwrap().to_owned())
+                        .unwrap_or_default()
+                };
+
+                let hop_by_hop = ["connection", "keep-alive", "x-hop"]
+                    .iter()
+                    .any(|name| parts.headers.contains_key(*name));
+
+                // responses without a body keep the `Content-Length` of the representation.
+                if parts.method == Method::HEAD || parts.uri.path().ends_with("/not-modified") {
+                    let status = if parts.method == Method::HEAD {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::NOT_MODIFIED
+                    };
+
+                    let response = Response::builder()
+                        .status(status)
+                        .header("content-length", "5")
+                        .body(BodyReader::empty())
+                        .unwrap();
+
+                    _ = write.write_response(response).await;
+
+                    return;
+                }
+
+                let response = Response::builder()
+                    .status(StatusCode::OK)
+                    .header("connection", "close")
+                    .header("x-upstream", name)
+                    .header("x-echo-path", parts.uri.to_string())
+                    .header("x-echo-host", echo("host"))
+                    .header("x-echo-xff", echo("x-forwarded-for"))
+                    .header("x-echo-forwarded", echo("forwarded"))
+                    .header("x-echo-hop", hop_by_hop.to_string())
+                    .body(BodyReader::from_stream(body))
+                    .unwrap();
+
+                _ = write.write_response(response).await;
+            });
+        }
+    });
+
+    raddr
+}
+
+/// Start an upstream server that closes connections without responding.
+async fn spawn_dead_upstream() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            drop(stream);
+        }
+    });
+
+    raddr
+}
+
+fn get(path: &str) -> Request<BodyReader> {
+    Request::get(path).body(BodyReader::empty()).unwrap()
+}
+
+fn header<T>(response: &Response<T>, name: &str) -> String {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap().to_owned())
+        .unwrap_or_default()
+}
+
+#[futures_test::test]
+async fn test_forward_headers() {
+    init();
+
+    let a = spawn_upstream("a").await;
+    let b = spawn_upstream("b").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}/api", a), format!("http://{}/api", b)])
+        .create()
+        .unwrap();
+
+    let peer_addr: SocketAddr = "127.0.0.2:1234".parse().unwrap();
+
+    let mut upstreams = vec![];
+
+    for _ in 0..4 {
+        let request = Request::get("/hello?a=1")
+            .header(HOST, "gateway.local")
+            .header("connection", "keep-alive, x-hop")
+            .header("keep-alive", "timeout=5")
+            .header("x-hop", "1")
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(BodyReader::empty())
+            .unwrap();
+
+        let response = proxy.forward(request, Some(peer_addr)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "connection"), "");
+
+        let upstream = header(&response, "x-upstream");
+
+        let raddr = if upstream == "a" { a } else { b };
+
+        assert_eq!(header(&response, "x-echo-path"), "/api/hello?a=1");
+        assert_eq!(header(&response, "x-echo-host"), raddr.to_string());
+        assert_eq!(header(&response, "x-echo-xff"), "10.0.0.1, 127.0.0.2");
+        assert_eq!(
+            header(&response, "x-echo-forwarded"),
+            "for=127.0.0.2;host=\"gateway.local\";proto=http"
+        );
+        assert_eq!(header(&response, "x-echo-hop"), "false");
+
+        upstreams.push(upstream);
+    }
+
+    assert_eq!(upstreams, ["a", "b", "a", "b"]);
+}
+
+#[futures_test::test]
+async fn test_bodyless_content_length() {
+    init();
+
+    let a = spawn_upstream("a").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}", a)])
+        .create()
+        .unwrap();
+
+    let request = Request::head("/hello").body(BodyReader::empty()).unwrap();
+
+    let response = proxy.forward(request, None).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "content-length"), "5");
+
+    let response = proxy.forward(get("/not-modified"), None).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(header(&response, "content-length"), "5");
+}
+
+#[futures_test::test]
+async fn test_preserve_host() {
+    init();
+
+    let a = spawn_upstream("a").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}", a)])
+        .preserve_host(true)
+        .create()
+        .unwrap();
+
+    let request = Request::get("/")
+        .header(HOST, "gateway.local")
+        .body(BodyReader::empty())
+        .unwrap();
+
+    let response = proxy.forward(request, None).await.unwrap();
+
+    assert_eq!(header(&response, "x-echo-host"), "gateway.local");
+    assert_eq!(header(&response, "x-echo-xff"), "");
+}
+
+#[futures_test::test]
+async fn test_least_connections() {
+    init();
+
+    let a = spawn_upstream("a").await;
+    let b = spawn_upstream("b").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}", a), format!("http://{}", b)])
+        .balance(Balance::LeastConnections)
+        .create()
+        .unwrap();
+
+    // keep the first request in flight by holding its response body.
+    let first = proxy.forward(get("/"), None).await.unwrap();
+
+    assert_eq!(header(&first, "x-upstream"), "a");
+
+    for _ in 0..2 {
+        let response = proxy.forward(get("/"), None).await.unwrap();
+
+        assert_eq!(header(&response, "x-upstream"), "b");
+
+        let _: Vec<Vec<u8>> = response.into_body().try_collect().await.unwrap();
+    }
+
+    drop(first);
+
+    let mut upstreams = vec![];
+
+    for _ in 0..2 {
+        let response = proxy.forward(get("/"), None).await.unwrap();
+
+        upstreams.push(header(&response, "x-upstream"));
+    }
+
+    upstreams.sort();
+
+    assert_eq!(upstreams, ["a", "b"]);
+}
+
+#[futures_test::test]
+async fn test_retry_idempotent() {
+    init();
+
+    let dead = spawn_dead_upstream().await;
+    let live = spawn_upstream("live").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}", dead), format!("http://{}", live)])
+        .max_fails(1)
+        .create()
+        .unwrap();
+
+    // the request to the dead upstream is retried on the live one.
+    let response = proxy
+        .forward(
+            Request::put("/").body(BodyReader::from("hello")).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(header(&response, "x-upstream"), "live");
+
+    let body: Vec<Vec<u8>> = response.into_body().try_collect().await.unwrap();
+
+    assert_eq!(body.concat(), b"hello");
+
+    // the dead upstream is skipped after `max_fails` failures.
+    for _ in 0..4 {
+        let response = proxy
+            .forward(
+                Request::post("/").body(BodyReader::from("hello")).unwrap(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(header(&response, "x-upstream"), "live");
+    }
+
+    let proxy = ReverseProxy::builder([format!("http://{}", dead), format!("http://{}", live)])
+        .create()
+        .unwrap();
+
+    // non-idempotent requests are never retried.
+    let response = proxy
+        .handle(
+            Request::post("/").body(BodyReader::from("hello")).unwrap(),
+            None,
+        )
+        .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[futures_test::test]
+async fn test_serve_streaming() {
+    init();
+
+    let a = spawn_upstream("a").await;
+
+    let proxy = ReverseProxy::builder([format!("http://{}", a)])
+        .create()
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    spawn(async move {
+        _ = proxy
+            .serve(HttpServer::on(Some("proxy_test"), listener))
+            .await;
+    });
+
+    let chunks = (0..16)
+        .map(|i| vec![i as u8; 1024 * (i + 1)])
+        .collect::<Vec<_>>();
+
+    let request = Request::post(format!("http://{}/stream", raddr))
+        .body(BodyReader::from_stream(stream::iter(
+            chunks.clone().into_iter().map(Ok),
+        )))
+        .unwrap();
+
+    let response = request.send(HttpClientOptions::new()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "x-echo-path"), "/stream");
+    assert_eq!(header(&response, "x-echo-xff"), "127.0.0.1");
+    assert!(header(&response, "x-echo-forwarded").starts_with("for=127.0.0.1;"));
+
+    let body: Vec<Vec<u8>> = response.into_body().try_collect().await.unwrap();
+
+    assert_eq!(body.concat(), chunks.concat());
+}
@@ -0,0 +1,607 @@
+//! Procedural macros of the `futures-jsonrpcv2` crate, which are re-exported with its `macros` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, meta::ParseNestedMeta, parse_macro_input, parse_quote, spanned::Spanned,
+    Attribute, Expr, ExprLit, FnArg, GenericArgument, Ident, ItemTrait, Lit, LitStr, Meta,
+    MetaNameValue, Pat, PathArguments, ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+/// Generates a typed jsonrpc client and a server registration function from a trait.
+///
+/// Each method of the trait must be an `async fn` with a `&self` receiver, and returns
+/// `Result<T, E>` where `E: Into<Error<String, Value>>`, e.g, [`HandlerResult<T>`].
+/// For a trait `Calculator`, this macro generates:
+///
+/// - `CalculatorClient`, a wrapper of `JsonRpcClient` with a typed method for each trait method,
+///   which returns `JsonRpcResult<T>`.
+/// - `register_calculator(server, service)`, which registers the methods of the `service`
+///   to a `JsonRpcServer` and returns it.
+///
+/// The params are sent as an array by default, use `#[jsonrpc(params = "named")]` on the trait
+/// or methods to send them as an object keyed by the argument names.
+///
+/// The method attribute `#[jsonrpc(name = "...")]` overrides the method name, which defaults to
+/// the method ident, and `#[jsonrpc(notification)]` marks a method without response,
+/// which must return nothing.
+///
+/// With the trait option `#[jsonrpc(openrpc)]`, the argument and result types must implement
+/// `JsonSchema`, the client gets an associated function `openrpc_methods()` describing the methods,
+/// which are also registered to the server for the `rpc.discover` method.
+///
+/// ```ignore
+/// use futures_jsonrpcv2::{jsonrpc, server::HandlerResult};
+///
+/// #[jsonrpc]
+/// pub trait Calculator {
+///     async fn add(&self, a: i32, b: i32) -> HandlerResult<i32>;
+///
+///     #[jsonrpc(name = "math_sub", params = "named")]
+///     async fn sub(&self, a: i32, b: i32) -> HandlerResult<i32>;
+///
+///     #[jsonrpc(notification)]
+///     async fn log(&self, message: String);
+/// }
+/// ```
+///
+/// [`HandlerResult<T>`]: https://docs.rs/futures-jsonrpcv2/latest/futures_jsonrpcv2/server/type.HandlerResult.html
+#[proc_macro_attribute]
+pub fn jsonrpc(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = TraitOptions::default();
+
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+
+    parse_macro_input!(attr with parser);
+
+    let item = parse_macro_input!(item as ItemTrait);
+
+    expand(options, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The way of passing params of a method.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ParamsStyle {
+    /// By-position, an array of arguments.
+    #[default]
+    Positional,
+    /// By-name, an object keyed by the argument names.
+    Named,
+}
+
+impl ParamsStyle {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let style: LitStr = meta.value()?.parse()?;
+
+        match style.value().as_str() {
+            "positional" => Ok(ParamsStyle::Positional),
+            "named" => Ok(ParamsStyle::Named),
+            _ => Err(syn::Error::new(
+                style.span(),
+                "expected `positional` or `named`",
+            )),
+        }
+    }
+}
+
+/// The options of the `#[jsonrpc(...)]` attribute on traits.
+#[derive(Default)]
+struct TraitOptions {
+    params: ParamsStyle,
+    openrpc: bool,
+}
+
+impl TraitOptions {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("params") {
+            self.params = ParamsStyle::parse(&meta)?;
+            Ok(())
+        } else if meta.path.is_ident("openrpc") {
+            self.openrpc = true;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported jsonrpc trait option"))
+        }
+    }
+}
+
+/// A jsonrpc method parsed from a trait method.
+struct Method {
+    ident: Ident,
+    /// The jsonrpc method name.
+    name: String,
+    params: ParamsStyle,
+    notification: bool,
+    args: Vec<(Ident, Type)>,
+    /// The type of the success result, is `None` for notifications.
+    result: Option<Type>,
+    docs: Vec<Attribute>,
+}
+
+impl Method {
+    /// Parse the jsonrpc method from `item`, and strips the `#[jsonrpc(...)]` attributes.
+    fn parse(options: &TraitOptions, item: &mut TraitItemFn) -> syn::Result<Self> {
+        let mut name = None;
+        let mut params = options.params;
+        let mut notification = false;
+
+        let mut attrs = vec![];
+
+        for attr in item.attrs.drain(..) {
+            if !attr.path().is_ident("jsonrpc") {
+                attrs.push(attr);
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("params") {
+                    params = ParamsStyle::parse(&meta)?;
+                    Ok(())
+                } else if meta.path.is_ident("notification") {
+                    notification = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported jsonrpc method option"))
+                }
+            })?;
+        }
+
+        item.attrs = attrs;
+
+        let sig = &item.sig;
+
+        if sig.asyncness.is_none() {
+            return Err(syn::Error::new(
+                sig.fn_token.span(),
+                "jsonrpc methods must be `async fn`",
+            ));
+        }
+
+        if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+            return Err(syn::Error::new(
+                sig.generics.span(),
+                "jsonrpc methods can't be generic",
+            ));
+        }
+
+        let mut inputs = sig.inputs.iter();
+
+        match inputs.next() {
+            Some(FnArg::Receiver(receiver))
+                if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+            _ => {
+                return Err(syn::Error::new(
+                    sig.ident.span(),
+                    "jsonrpc methods must have a `&self` receiver",
+                ))
+            }
+        }
+
+        let mut args = vec![];
+
+        for input in inputs {
+            let FnArg::Typed(arg) = input else {
+                return Err(syn::Error::new(input.span(), "unexpected receiver"));
+            };
+
+            let Pat::Ident(pat) = arg.pat.as_ref() else {
+                return Err(syn::Error::new(
+                    arg.pat.span(),
+                    "the arguments of jsonrpc methods must be identifiers",
+                ));
+            };
+
+            args.push((pat.ident.clone(), arg.ty.as_ref().clone()));
+        }
+
+        let result = match (&sig.output, notification) {
+            (ReturnType::Default, true) => None,
+            (ReturnType::Type(_, ty), true) if is_unit(ty) => None,
+            (output, true) => {
+                return Err(syn::Error::new(
+                    output.span(),
+                    "jsonrpc notifications must return nothing",
+                ))
+            }
+            (ReturnType::Type(_, ty), false) if result_type(ty).is_some() => {
+                result_type(ty).cloned()
+            }
+            (output, false) => {
+                return Err(syn::Error::new(
+                    output.span(),
+                    "jsonrpc methods must return a `Result<T, E>`, e.g, `HandlerResult<T>`",
+                ))
+            }
+        };
+
+        Ok(Method {
+            name: name.unwrap_or_else(|| sig.ident.unraw().to_string()),
+            ident: sig.ident.clone(),
+            params,
+            notification,
+            args,
+            result,
+            docs: item
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Generates the typed method of the client.
+    fn client_method(&self) -> TokenStream2 {
+        let Method {
+            ident,
+            name,
+            args,
+            docs,
+            ..
+        } = self;
+
+        let arg_idents = args.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+        let arg_types = args.iter().map(|(_, ty)| ty);
+
+        let params = match self.params {
+            ParamsStyle::Positional => quote!((#(#arg_idents,)*)),
+            ParamsStyle::Named => {
+                let keys = arg_idents.iter().map(|ident| ident.unraw().to_string());
+
+                quote! {{
+                    let mut __params = ::futures_jsonrpcv2::__private::serde_json::Map::new();
+
+                    #(
+                        __params.insert(
+                            #keys.to_owned(),
+                            ::futures_jsonrpcv2::__private::serde_json::to_value(&#arg_idents)?,
+                        );
+                    )*
+
+                    ::futures_jsonrpcv2::__private::serde_json::Value::Object(__params)
+                }}
+            }
+        };
+
+        let (result, call) = match &self.result {
+            Some(result) => (result.clone(), quote!(call)),
+            None => (parse_quote!(()), quote!(notify)),
+        };
+
+        quote! {
+            #(#docs)*
+            pub async fn #ident(&self, #(#arg_idents: #arg_types),*) -> ::futures_jsonrpcv2::JsonRpcResult<#result> {
+                self.0.#call(#name, #params).await
+            }
+        }
+    }
+
+    /// Generates the OpenRPC description of the method.
+    fn describe(&self) -> TokenStream2 {
+        let name = &self.name;
+
+        let mut method = quote!(::futures_jsonrpcv2::openrpc::Method::new(#name));
+
+        let description = self
+            .docs
+            .iter()
+            .filter_map(|attr| match &attr.meta {
+                Meta::NameValue(MetaNameValue {
+                    value:
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(doc), ..
+                        }),
+                    ..
+                }) => Some(doc.value()),
+                _ => None,
+            })
+            .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !description.trim().is_empty() {
+            let description = description.trim();
+            method = quote!(#method.description(#description));
+        }
+
+        if !self.args.is_empty() {
+            method = match self.params {
+                ParamsStyle::Positional => quote! {
+                    #method.param_structure(::futures_jsonrpcv2::openrpc::ParamStructure::ByPosition)
+                },
+                ParamsStyle::Named => quote! {
+                    #method.param_structure(::futures_jsonrpcv2::openrpc::ParamStructure::ByName)
+                },
+            };
+        }
+
+        for (ident, ty) in &self.args {
+            let key = ident.unraw().to_string();
+
+            // the missing fields of `Option` are deserialized as `None`, which is by-name only.
+            method = if self.params == ParamsStyle::Named && is_option(ty) {
+                quote!(#method.optional_param::<#ty, _>(#key))
+            } else {
+                quote!(#method.param::<#ty, _>(#key))
+            };
+        }
+
+        if let Some(result) = &self.result {
+            method = quote!(#method.result::<#result, _>("result"));
+        }
+
+        method
+    }
+
+    /// Generates the statement to register the method handler to `server`.
+    fn register(&self) -> TokenStream2 {
+        let Method {
+            ident, name, args, ..
+        } = self;
+
+        let arg_idents = args.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+        let arg_types = args.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+
+        let call = if self.notification {
+            quote! {
+                service.#ident(#(#arg_idents),*).await;
+
+                ::futures_jsonrpcv2::server::HandlerResult::<()>::Ok(())
+            }
+        } else {
+            quote! {
+                let result: ::futures_jsonrpcv2::server::HandlerResult<_> = service
+                    .#ident(#(#arg_idents),*)
+                    .await
+                    .map_err(::std::convert::Into::into);
+
+                result
+            }
+        };
+
+        let handler = |pat: TokenStream2| {
+            quote! {
+                move |#pat| {
+                    let service = service.clone();
+
+                    async move { #call }
+                }
+            }
+        };
+
+        // the params of methods without arguments may be null, empty array or empty object.
+        if args.is_empty() {
+            let handler = handler(quote!(_: ::futures_jsonrpcv2::__private::serde_json::Value));
+
+            return quote! {
+                let server = {
+                    let service = service.clone();
+
+                    server.handle(#name, #handler)
+                };
+            };
+        }
+
+        match self.params {
+            ParamsStyle::Positional => {
+                let handler = handler(quote!((#(#arg_idents,)*): (#(#arg_types,)*)));
+
+                quote! {
+                    let server = {
+                        let service = service.clone();
+
+                        server.handle(#name, #handler)
+                    };
+                }
+            }
+            ParamsStyle::Named => {
+                let handler = handler(quote!(Params { #(#arg_idents),* }: Params));
+
+                quote! {
+                    let server = {
+                        #[derive(::futures_jsonrpcv2::__private::serde::Deserialize)]
+                        #[serde(crate = "::futures_jsonrpcv2::__private::serde")]
+                        struct Params {
+                            #(#arg_idents: #arg_types),*
+                        }
+
+                        let service = service.clone();
+
+                        server.handle(#name, #handler)
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn expand(options: TraitOptions, mut item: ItemTrait) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() || item.generics.where_clause.is_some() {
+        return Err(syn::Error::new(
+            item.generics.span(),
+            "jsonrpc traits can't be generic",
+        ));
+    }
+
+    let mut methods = vec![];
+
+    for trait_item in item.items.iter_mut() {
+        let TraitItem::Fn(trait_fn) = trait_item else {
+            return Err(syn::Error::new(
+                trait_item.span(),
+                "jsonrpc traits can only contain methods",
+            ));
+        };
+
+        let method = Method::parse(&options, trait_fn)?;
+
+        // desugar into `fn -> impl Future + Send`, so the handlers can be spawned.
+        let output = match &method.result {
+            Some(_) => match &trait_fn.sig.output {
+                ReturnType::Type(_, ty) => ty.as_ref().clone(),
+                ReturnType::Default => unreachable!("checked by Method::parse"),
+            },
+            None => parse_quote!(()),
+        };
+
+        trait_fn.sig.asyncness = None;
+        trait_fn.sig.output = parse_quote!(
+            -> impl ::std::future::Future<Output = #output> + ::std::marker::Send
+        );
+
+        if let Some(block) = trait_fn.default.take() {
+            trait_fn.default = Some(parse_quote!({ async move #block }));
+        }
+
+        methods.push(method);
+    }
+
+    let vis = &item.vis;
+    let trait_ident = &item.ident;
+
+    let client_ident = format_ident!("{}Client", trait_ident);
+    let register_ident = Ident::new(
+        &format!("register_{}", snake_case(&trait_ident.unraw().to_string())),
+        Span::call_site(),
+    );
+
+    let client_doc = format!("The typed jsonrpc client of [`{}`].", trait_ident);
+    let register_doc = format!(
+        "Register the methods of [`{}`] implemented by `service` to `server`.",
+        trait_ident
+    );
+
+    let client_methods = methods.iter().map(Method::client_method);
+    let registers = methods.iter().map(Method::register);
+
+    let (openrpc_methods, describe) = if options.openrpc {
+        let descriptions = methods.iter().map(Method::describe);
+
+        (
+            quote! {
+                /// Returns the OpenRPC descriptions of the methods of this client stub.
+                pub fn openrpc_methods() -> ::std::vec::Vec<::futures_jsonrpcv2::openrpc::Method> {
+                    ::std::vec![#(#descriptions),*]
+                }
+            },
+            quote! {
+                let server = #client_ident::openrpc_methods()
+                    .into_iter()
+                    .fold(server, |server, method| server.describe(method));
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    Ok(quote! {
+        #item
+
+        #[doc = #client_doc]
+        #vis struct #client_ident(::futures_jsonrpcv2::client::JsonRpcClient);
+
+        impl #client_ident {
+            /// Create a typed client over `client`.
+            pub fn new(client: ::futures_jsonrpcv2::client::JsonRpcClient) -> Self {
+                Self(client)
+            }
+
+            /// Get the inner `JsonRpcClient` instance.
+            pub fn inner(&self) -> &::futures_jsonrpcv2::client::JsonRpcClient {
+                &self.0
+            }
+
+            #openrpc_methods
+
+            #(#client_methods)*
+        }
+
+        impl ::std::convert::From<::futures_jsonrpcv2::client::JsonRpcClient> for #client_ident {
+            fn from(client: ::futures_jsonrpcv2::client::JsonRpcClient) -> Self {
+                Self(client)
+            }
+        }
+
+        #[doc = #register_doc]
+        #vis fn #register_ident<S>(
+            server: ::futures_jsonrpcv2::server::JsonRpcServer,
+            service: S,
+        ) -> ::futures_jsonrpcv2::server::JsonRpcServer
+        where
+            S: #trait_ident + ::std::marker::Send + ::std::marker::Sync + 'static,
+        {
+            let service = ::std::sync::Arc::new(service);
+
+            #(#registers)*
+
+            #describe
+
+            server
+        }
+    })
+}
+
+fn is_option(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "Option"))
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+/// Returns `T` of a `Result<T, E>`-like type.
+fn result_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    let PathArguments::AngleBracketed(args) = &path.path.segments.last()?.arguments else {
+        return None;
+    };
+
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// Converts `UpperCamelCase` to `snake_case`, e.g, `HTTPService` to `http_service`.
+fn snake_case(ident: &str) -> String {
+    let chars = ident.chars().collect::<Vec<_>>();
+
+    let mut snake = String::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("Calculator"), "calculator");
+        assert_eq!(snake_case("EthApi"), "eth_api");
+        assert_eq!(snake_case("HTTPService"), "http_service");
+        assert_eq!(snake_case("Web3Api"), "web3_api");
+    }
+}
@@ -1,22 +1,34 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     io,
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
 };
 
+#[cfg(feature = "with_rasi")]
+use std::time::Duration;
+
 use futures::{lock::Mutex, Future, Sink, SinkExt, Stream, StreamExt};
 
 use futures_map::KeyWaitMap;
 
-use crate::{Request, Response, Version};
+use crate::{
+    codec::{Codec, JsonCodec},
+    Error, Id, JsonRpcError, JsonRpcResult, Packet, Request, Response, Version,
+};
 
+/// A helper extension of packet sinks to send a jsonrpc request.
+///
+/// The request is always encoded as JSON text, regardless of the [`Codec`] of any client,
+/// the packets of a client with a custom codec should be written as is,
+/// see [`JsonRpcClientState::send`].
 pub trait JsonRpcClientSender<E>: Sink<Vec<u8>, Error = E> + Unpin
 where
     E: ToString,
 {
+    /// Encodes `request` as JSON text and sends it.
     fn send_request<S, P, R, D>(
         &mut self,
         request: Request<S, P>,
@@ -44,7 +56,13 @@ where
 {
 }
 
+/// A helper extension of packet streams to receive a jsonrpc response.
+///
+/// The response is always decoded from JSON text, regardless of the [`Codec`] of any client,
+/// the packets for a client with a custom codec should be passed as is,
+/// see [`JsonRpcClientState::recv`].
 pub trait JsonRpcClientReceiver: Stream<Item = Vec<u8>> + Unpin {
+    /// Receives the next packet and decodes it from JSON text as a response.
     fn next_response<R, D>(&mut self) -> impl Future<Output = io::Result<Response<String, R, D>>>
     where
         for<'a> R: serde::Deserialize<'a>,
@@ -65,11 +83,43 @@ impl<T> JsonRpcClientReceiver for T where T: Stream<Item = Vec<u8>> + Unpin {}
 
 type InnerResponse = Response<String, serde_json::Value, serde_json::Value>;
 
+/// A request or notification initiated by the peer.
+pub type IncomingRequest = Request<String, serde_json::Value>;
+
+/// An error object replied by the peer with a `null` id, e.g, the peer failed to parse a packet.
+pub type ConnectionError = Error<String, serde_json::Value>;
+
+/// A generator of call ids, the generated ids must be unique among the pending calls.
+pub trait IdGenerator: Send + Sync {
+    /// Returns the id of the next call.
+    fn next_id(&self) -> Id;
+}
+
+impl<F> IdGenerator for F
+where
+    F: Fn() -> Id + Send + Sync,
+{
+    fn next_id(&self) -> Id {
+        self()
+    }
+}
+
+/// The default [`IdGenerator`], which generates sequential numbers starting from 0.
+#[derive(Debug, Default)]
+pub struct SequentialIds(AtomicU64);
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> Id {
+        Id::from(self.0.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
 #[derive(Default)]
 struct RawJsonRpcClient {
     max_send_queue_size: usize,
     send_queue: VecDeque<(usize, Vec<u8>)>,
-    received_resps: HashMap<usize, InnerResponse>,
+    incoming: VecDeque<IncomingRequest>,
+    errors: VecDeque<ConnectionError>,
 }
 
 impl RawJsonRpcClient {
@@ -93,22 +143,131 @@ impl RawJsonRpcClient {
     fn send_one(&mut self) -> Option<(usize, Vec<u8>)> {
         self.send_queue.pop_front()
     }
+
+    fn cache_incoming(&mut self, request: IncomingRequest) {
+        if self.incoming.len() == self.max_send_queue_size {
+            if let Some(dropped) = self.incoming.pop_front() {
+                log::warn!("incoming queue is full, drop {}", dropped.method);
+            }
+        }
+
+        self.incoming.push_back(request);
+    }
+
+    fn cache_error(&mut self, error: ConnectionError) {
+        if self.errors.len() == self.max_send_queue_size {
+            if let Some(dropped) = self.errors.pop_front() {
+                log::warn!("connection error queue is full, drop {}", dropped);
+            }
+        }
+
+        self.errors.push_back(error);
+    }
+}
+
+/// The state of a call waiting for its response.
+enum CallState {
+    Waiting,
+    Ready(InnerResponse),
+    Failed(io::ErrorKind, String),
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+/// A call waiting for its response.
+struct PendingCall {
+    /// The sequence of the packet carrying this call in the send queue.
+    seq: usize,
+    state: CallState,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 enum JsonRpcClientEvent {
     Send,
     Forward,
-    Response(usize),
+    Incoming,
+    ConnectionError,
+    Response(Id),
 }
 
 struct RawJsonRpcClientState {
     is_closed: AtomicBool,
-    next_id: AtomicUsize,
+    /// The sequence of packets in the send queue, which is independent of the call ids.
+    next_seq: AtomicUsize,
+    id_generator: Box<dyn IdGenerator>,
+    codec: Arc<dyn Codec>,
     raw: Mutex<RawJsonRpcClient>,
+    /// The calls waiting for responses, which is locked in [`Drop`] of [`PendingCallGuard`].
+    pending_calls: std::sync::Mutex<HashMap<Id, PendingCall>>,
     wait_map: KeyWaitMap<JsonRpcClientEvent, ()>,
 }
 
+/// Removes the pending call and its response when the call future is dropped.
+struct PendingCallGuard {
+    client: JsonRpcClientState,
+    id: Id,
+}
+
+impl Drop for PendingCallGuard {
+    fn drop(&mut self) {
+        self.client.0.pending_calls.lock().unwrap().remove(&self.id);
+
+        self.client
+            .0
+            .wait_map
+            .remove(&JsonRpcClientEvent::Response(self.id.clone()));
+    }
+}
+
+/// A builder to create a jsonrpc client with a custom id generator or codec.
+pub struct JsonRpcClientBuilder {
+    max_send_queue_size: usize,
+    id_generator: Box<dyn IdGenerator>,
+    codec: Arc<dyn Codec>,
+}
+
+impl JsonRpcClientBuilder {
+    /// Create a new builder with provided send cache channel length.
+    pub fn new(max_send_queue_size: usize) -> Self {
+        Self {
+            max_send_queue_size,
+            id_generator: Box::new(SequentialIds::default()),
+            codec: Arc::new(JsonCodec),
+        }
+    }
+
+    /// Set the generator of call ids, e.g, UUIDs. Defaults to [`SequentialIds`].
+    pub fn id_generator<G: IdGenerator + 'static>(mut self, id_generator: G) -> Self {
+        self.id_generator = Box::new(id_generator);
+        self
+    }
+
+    /// Set the codec of packets. Defaults to [`JsonCodec`].
+    pub fn codec<C: Codec + 'static>(self, codec: C) -> Self {
+        self.shared_codec(Arc::new(codec))
+    }
+
+    pub(crate) fn shared_codec(mut self, codec: Arc<dyn Codec>) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Consume builder and create a new `JsonRpcClient` instance.
+    pub fn create(self) -> JsonRpcClient {
+        JsonRpcClient(self.create_state())
+    }
+
+    pub(crate) fn create_state(self) -> JsonRpcClientState {
+        JsonRpcClientState(Arc::new(RawJsonRpcClientState {
+            is_closed: Default::default(),
+            next_seq: Default::default(),
+            id_generator: self.id_generator,
+            codec: self.codec,
+            raw: Mutex::new(RawJsonRpcClient::new(self.max_send_queue_size)),
+            pending_calls: Default::default(),
+            wait_map: KeyWaitMap::new(),
+        }))
+    }
+}
+
 /// The jsonrpc client without [`Drop`] support.
 #[derive(Clone)]
 pub struct JsonRpcClientState(Arc<RawJsonRpcClientState>);
@@ -116,45 +275,195 @@ pub struct JsonRpcClientState(Arc<RawJsonRpcClientState>);
 impl JsonRpcClientState {
     /// Create a new `JsonRpcClient` with provided send cache channel length.
     pub fn new(max_send_queue_size: usize) -> Self {
-        Self(Arc::new(RawJsonRpcClientState {
-            is_closed: Default::default(),
-            next_id: Default::default(),
-            raw: Mutex::new(RawJsonRpcClient::new(max_send_queue_size)),
-            wait_map: KeyWaitMap::new(),
-        }))
+        JsonRpcClientBuilder::new(max_send_queue_size).create_state()
+    }
+
+    /// Create a new `JsonRpcClient` with provided send cache channel length,
+    /// the ids of calls are generated by `id_generator`, e.g, UUIDs.
+    pub fn with_id_generator<G>(max_send_queue_size: usize, id_generator: G) -> Self
+    where
+        G: IdGenerator + 'static,
+    {
+        JsonRpcClientBuilder::new(max_send_queue_size)
+            .id_generator(id_generator)
+            .create_state()
     }
 
     /// Invoke a jsonrpc v2.0 call and waiting for response.
-    pub async fn call<M, P, R>(&self, method: M, params: P) -> std::io::Result<R>
+    ///
+    /// The error object replied by the peer is returned as [`JsonRpcError::Rpc`].
+    pub async fn call<M, P, R>(&self, method: M, params: P) -> JsonRpcResult<R>
     where
         M: AsRef<str>,
         P: serde::Serialize,
         for<'a> R: serde::Deserialize<'a>,
     {
-        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        let id = self.0.id_generator.next_id();
 
         let request = Request {
-            id: Some(id),
+            id: Some(id.clone()),
             jsonrpc: Version::default(),
             method: method.as_ref(),
             params,
         };
 
-        let packet = serde_json::to_vec(&request)?;
+        let packet = self.encode(&request)?;
+
+        let seq = self.next_seq();
 
-        let mut send_data = Some((id, packet));
+        let _guard = self.register_call(id.clone(), seq);
+
+        self.send_packet(seq, packet).await?;
+
+        self.wait_response(id).await
+    }
+
+    /// Invoke a jsonrpc v2.0 call and waiting for response at most `timeout`.
+    ///
+    /// Returns [`JsonRpcError::Timeout`] if the deadline is reached, the late response is dropped.
+    #[cfg(feature = "with_rasi")]
+    pub async fn call_with_timeout<M, P, R>(
+        &self,
+        method: M,
+        params: P,
+        timeout: Duration,
+    ) -> JsonRpcResult<R>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        use rasi::timer::TimeoutExt;
+
+        self.call(method, params)
+            .timeout(timeout)
+            .await
+            .ok_or(JsonRpcError::Timeout)?
+    }
+
+    /// Send a jsonrpc v2.0 notification, which has no response.
+    ///
+    /// Returns after the notification is pushed into the send queue.
+    pub async fn notify<M, P>(&self, method: M, params: P) -> JsonRpcResult<()>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+    {
+        let request = Request {
+            id: None,
+            jsonrpc: Version,
+            method: method.as_ref(),
+            params,
+        };
+
+        let packet = self.encode(&request)?;
+
+        self.send_packet(self.next_seq(), packet).await
+    }
+
+    /// Returns a stream of requests and notifications initiated by the peer,
+    /// e.g, subscription pushes. The stream ends when the client is closed.
+    ///
+    /// At most `max_send_queue_size` unhandled incoming messages are cached, the oldest one
+    /// is dropped when the cache is full. Only one stream can be polled at a time.
+    pub fn incoming(&self) -> impl Stream<Item = IncomingRequest> + Send + Unpin {
+        Box::pin(futures::stream::unfold(self.clone(), |client| async move {
+            let request = client.next_incoming().await?;
+
+            Some((request, client))
+        }))
+    }
+
+    async fn next_incoming(&self) -> Option<IncomingRequest> {
+        loop {
+            let mut raw = self.0.raw.lock().await;
+
+            if let Some(request) = raw.incoming.pop_front() {
+                return Some(request);
+            }
 
-        while let Some((id, data)) = send_data {
             if self.is_closed() {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::BrokenPipe,
-                    "JsonRpcClient is closed",
-                ));
+                return None;
             }
 
+            self.0
+                .wait_map
+                .wait(&JsonRpcClientEvent::Incoming, raw)
+                .await;
+        }
+    }
+
+    /// Returns a stream of error objects replied by the peer with a `null` id,
+    /// which can't be delivered to any call, e.g, the peer failed to parse a packet.
+    ///
+    /// The error objects are cached like the [`incoming`](Self::incoming) stream.
+    pub fn errors(&self) -> impl Stream<Item = ConnectionError> + Send + Unpin {
+        Box::pin(futures::stream::unfold(self.clone(), |client| async move {
+            let error = client.next_error().await?;
+
+            Some((error, client))
+        }))
+    }
+
+    async fn next_error(&self) -> Option<ConnectionError> {
+        loop {
             let mut raw = self.0.raw.lock().await;
 
-            send_data = raw.cache_send(id, data);
+            if let Some(error) = raw.errors.pop_front() {
+                return Some(error);
+            }
+
+            if self.is_closed() {
+                return None;
+            }
+
+            self.0
+                .wait_map
+                .wait(&JsonRpcClientEvent::ConnectionError, raw)
+                .await;
+        }
+    }
+
+    /// Create a new [`JsonRpcBatch`] to send several calls in one packet.
+    pub fn batch(&self) -> JsonRpcBatch {
+        JsonRpcBatch {
+            seq: self.next_seq(),
+            client: self.clone(),
+            ids: vec![],
+            requests: vec![],
+            sent: false,
+        }
+    }
+
+    /// Push a packet into the send queue regardless of the queue size, e.g, the replies of peer's calls.
+    pub(crate) async fn push_packet(&self, packet: Vec<u8>) {
+        let seq = self.next_seq();
+
+        self.0.raw.lock().await.send_queue.push_back((seq, packet));
+
+        self.0.wait_map.insert(JsonRpcClientEvent::Forward, ());
+    }
+
+    fn encode<T: serde::Serialize>(&self, value: &T) -> JsonRpcResult<Vec<u8>> {
+        Ok(self.0.codec.encode(&serde_json::to_value(value)?)?)
+    }
+
+    fn next_seq(&self) -> usize {
+        self.0.next_seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Push a packet into the send queue, waiting if the queue is full.
+    async fn send_packet(&self, seq: usize, packet: Vec<u8>) -> JsonRpcResult<()> {
+        let mut send_data = Some((seq, packet));
+
+        while let Some((seq, data)) = send_data {
+            if self.is_closed() {
+                return Err(JsonRpcError::Closed);
+            }
+
+            let mut raw = self.0.raw.lock().await;
+
+            send_data = raw.cache_send(seq, data);
 
             if send_data.is_some() {
                 self.0.wait_map.wait(&JsonRpcClientEvent::Send, raw).await;
@@ -163,37 +472,143 @@ impl JsonRpcClientState {
             }
         }
 
-        if let Some(_) = self
+        Ok(())
+    }
+
+    /// Register a pending call `id` sent by the packet `seq`,
+    /// the returned guard must be held until the call is finished.
+    fn register_call(&self, id: Id, seq: usize) -> PendingCallGuard {
+        self.0.pending_calls.lock().unwrap().insert(
+            id.clone(),
+            PendingCall {
+                seq,
+                state: CallState::Waiting,
+            },
+        );
+
+        PendingCallGuard {
+            client: self.clone(),
+            id,
+        }
+    }
+
+    /// Waiting for the response of call `id`.
+    async fn wait_response<R>(&self, id: Id) -> JsonRpcResult<R>
+    where
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        let waited = self
             .0
             .wait_map
-            .wait(&JsonRpcClientEvent::Response(id), ())
-            .await
-        {
-            if self.is_closed() {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::BrokenPipe,
-                    "JsonRpcClient is closed",
-                ));
+            .wait(&JsonRpcClientEvent::Response(id.clone()), ())
+            .await;
+
+        if self.is_closed() {
+            return Err(JsonRpcError::Closed);
+        }
+
+        if waited.is_none() {
+            return Err(JsonRpcError::Canceled);
+        }
+
+        let call = self.0.pending_calls.lock().unwrap().remove(&id);
+
+        match call.map(|call| call.state) {
+            Some(CallState::Ready(resp)) => {
+                if let Some(err) = resp.error {
+                    return Err(JsonRpcError::Rpc(err));
+                }
+
+                Ok(serde_json::from_value(serde_json::to_value(resp.result)?)?)
             }
+            Some(CallState::Failed(kind, message)) => Err(io::Error::new(kind, message).into()),
+            _ => Err(JsonRpcError::Canceled),
+        }
+    }
 
-            let mut raw = self.0.raw.lock().await;
+    /// Fails all pending calls with an error of `kind`, e.g, when the transport is broken.
+    ///
+    /// The calls not yet sent are removed from the send queue, so they are never sent to the peer.
+    /// The error of [`TimedOut`](io::ErrorKind::TimedOut) is returned as [`JsonRpcError::Timeout`],
+    /// others are returned as [`JsonRpcError::Transport`].
+    pub async fn fail_pending<E: ToString>(&self, kind: io::ErrorKind, error: E) {
+        self.fail_calls_if(|_| true, kind, error).await
+    }
+
+    /// Fails the pending calls of `ids` with an error of `kind`,
+    /// see [`fail_pending`](Self::fail_pending) for more details.
+    ///
+    /// A batch not yet sent is removed from the send queue as a whole,
+    /// so the other calls of that batch are failed too.
+    pub async fn fail_calls<I, E>(&self, ids: I, kind: io::ErrorKind, error: E)
+    where
+        I: IntoIterator<Item = Id>,
+        E: ToString,
+    {
+        let ids = ids.into_iter().collect::<HashSet<_>>();
+
+        self.fail_calls_if(|id| ids.contains(id), kind, error).await
+    }
+
+    async fn fail_calls_if<F, E>(&self, filter: F, kind: io::ErrorKind, error: E)
+    where
+        F: Fn(&Id) -> bool,
+        E: ToString,
+    {
+        let message = error.to_string();
+
+        let mut raw = self.0.raw.lock().await;
+
+        let mut pending_calls = self.0.pending_calls.lock().unwrap();
 
-            let resp = raw
-                .received_resps
-                .remove(&id)
-                .expect("consistency guarantee");
+        let mut failed = vec![];
+        let mut failed_seqs = HashSet::new();
 
-            if let Some(err) = resp.error {
-                return Err(io::Error::new(io::ErrorKind::Other, err));
+        for (id, call) in pending_calls.iter_mut() {
+            if matches!(call.state, CallState::Waiting) && filter(id) {
+                call.state = CallState::Failed(kind, message.clone());
+                failed.push(id.clone());
+                failed_seqs.insert(call.seq);
             }
+        }
+
+        let len = raw.send_queue.len();
+
+        let mut removed_seqs = HashSet::new();
+
+        raw.send_queue.retain(|(seq, _)| {
+            if failed_seqs.contains(seq) {
+                removed_seqs.insert(*seq);
+                false
+            } else {
+                true
+            }
+        });
 
-            Ok(serde_json::from_value(serde_json::to_value(resp.result)?)?)
-        } else {
-            Err(io::Error::new(io::ErrorKind::Other, "jsonrpc canceled."))
+        if raw.send_queue.len() < len {
+            self.0.wait_map.insert(JsonRpcClientEvent::Send, ());
         }
+
+        // the calls sharing a removed packet are never sent either.
+        for (id, call) in pending_calls.iter_mut() {
+            if matches!(call.state, CallState::Waiting) && removed_seqs.contains(&call.seq) {
+                call.state = CallState::Failed(kind, message.clone());
+                failed.push(id.clone());
+            }
+        }
+
+        drop(pending_calls);
+
+        drop(raw);
+
+        self.0.wait_map.batch_insert(
+            failed
+                .into_iter()
+                .map(|id| (JsonRpcClientEvent::Response(id), ())),
+        );
     }
 
-    /// Writes a single jsonrpc packet to be sent to the peer.
+    /// Writes a single jsonrpc packet to be sent to the peer, returns the packet sequence and data.
     pub async fn send(&self) -> std::io::Result<(usize, Vec<u8>)> {
         loop {
             let mut raw = self.0.raw.lock().await;
@@ -219,6 +634,10 @@ impl JsonRpcClientState {
     }
 
     /// Processes jsonrpc packet received from the peer.
+    ///
+    /// Responses are delivered to the pending calls,
+    /// requests and notifications are delivered to the [`incoming`](Self::incoming) stream,
+    /// error objects with a `null` id are delivered to the [`errors`](Self::errors) stream.
     pub async fn recv<V: AsRef<[u8]>>(&self, packet: V) -> std::io::Result<()> {
         if self.is_closed() {
             return Err(std::io::Error::new(
@@ -227,16 +646,76 @@ impl JsonRpcClientState {
             ));
         }
 
-        let resp: Response<String, serde_json::Value, serde_json::Value> =
-            serde_json::from_slice(packet.as_ref())?;
+        let packet: Packet<serde_json::Value> =
+            serde_json::from_value(self.0.codec.decode(packet.as_ref())?)?;
+
+        self.recv_objects(packet.into_vec()).await
+    }
 
+    /// Processes jsonrpc objects received from the peer.
+    pub(crate) async fn recv_objects(
+        &self,
+        objects: Vec<serde_json::Value>,
+    ) -> std::io::Result<()> {
         let mut raw = self.0.raw.lock().await;
 
-        let id = resp.id;
+        let mut events = vec![];
 
-        raw.received_resps.insert(resp.id, resp);
+        for object in objects {
+            // requests and notifications from peer have a method member.
+            if object.get("method").is_some() {
+                match serde_json::from_value(object) {
+                    Ok(incoming) => {
+                        raw.cache_incoming(incoming);
 
-        self.0.wait_map.insert(JsonRpcClientEvent::Response(id), ());
+                        events.push((JsonRpcClientEvent::Incoming, ()));
+                    }
+                    // the other objects of this batch are still delivered.
+                    Err(err) => log::error!("drop the invalid object from peer, {}", err),
+                }
+
+                continue;
+            }
+
+            let resp: InnerResponse = match serde_json::from_value(object) {
+                Ok(resp) => resp,
+                Err(err) => {
+                    log::error!("drop the invalid object from peer, {}", err);
+                    continue;
+                }
+            };
+
+            if resp.id == Id::Null {
+                match resp.error {
+                    Some(error) => {
+                        log::error!("connection error replied by the peer, {}", error);
+
+                        raw.cache_error(error);
+
+                        events.push((JsonRpcClientEvent::ConnectionError, ()));
+                    }
+                    None => log::warn!("drop the response with a null id"),
+                }
+
+                continue;
+            }
+
+            let mut pending_calls = self.0.pending_calls.lock().unwrap();
+
+            match pending_calls.get_mut(&resp.id) {
+                Some(call) if matches!(call.state, CallState::Waiting) => {
+                    events.push((JsonRpcClientEvent::Response(resp.id.clone()), ()));
+                    call.state = CallState::Ready(resp);
+                }
+                // the call is finished, e.g, timeout or the call future is dropped.
+                _ => log::warn!(
+                    "drop the response of a finished or unknown call {}",
+                    resp.id
+                ),
+            }
+        }
+
+        self.0.wait_map.batch_insert(events);
 
         Ok(())
     }
@@ -244,6 +723,7 @@ impl JsonRpcClientState {
     /// Close the jsonrpc client.
     pub fn close(&self) {
         self.0.is_closed.store(true, Ordering::SeqCst);
+        self.0.wait_map.cancel_all();
     }
 
     /// Returns true if this client is already closed.
@@ -272,8 +752,19 @@ impl JsonRpcClient {
     pub fn new(max_send_queue_size: usize) -> Self {
         Self(JsonRpcClientState::new(max_send_queue_size))
     }
+
+    /// Create a new `JsonRpcClient` with provided send cache channel length and call id generator.
+    pub fn with_id_generator<G>(max_send_queue_size: usize, id_generator: G) -> Self
+    where
+        G: IdGenerator + 'static,
+    {
+        Self(JsonRpcClientState::with_id_generator(
+            max_send_queue_size,
+            id_generator,
+        ))
+    }
     /// Invoke a jsonrpc v2.0 call and waiting for response.
-    pub async fn call<M, P, R>(&self, method: M, params: P) -> std::io::Result<R>
+    pub async fn call<M, P, R>(&self, method: M, params: P) -> JsonRpcResult<R>
     where
         M: AsRef<str>,
         P: serde::Serialize,
@@ -282,12 +773,161 @@ impl JsonRpcClient {
         self.0.call(method, params).await
     }
 
+    /// Invoke a jsonrpc v2.0 call and waiting for response at most `timeout`,
+    /// see [`JsonRpcClientState::call_with_timeout`] for more details.
+    #[cfg(feature = "with_rasi")]
+    pub async fn call_with_timeout<M, P, R>(
+        &self,
+        method: M,
+        params: P,
+        timeout: Duration,
+    ) -> JsonRpcResult<R>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        self.0.call_with_timeout(method, params, timeout).await
+    }
+
+    /// Send a jsonrpc v2.0 notification, which has no response.
+    pub async fn notify<M, P>(&self, method: M, params: P) -> JsonRpcResult<()>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+    {
+        self.0.notify(method, params).await
+    }
+
+    /// Returns a stream of requests and notifications initiated by the peer,
+    /// see [`JsonRpcClientState::incoming`] for more details.
+    pub fn incoming(&self) -> impl Stream<Item = IncomingRequest> + Send + Unpin {
+        self.0.incoming()
+    }
+
+    /// Returns a stream of error objects replied by the peer with a `null` id,
+    /// see [`JsonRpcClientState::errors`] for more details.
+    pub fn errors(&self) -> impl Stream<Item = ConnectionError> + Send + Unpin {
+        self.0.errors()
+    }
+
+    /// Create a new [`JsonRpcBatch`] to send several calls in one packet.
+    pub fn batch(&self) -> JsonRpcBatch {
+        self.0.batch()
+    }
+
     /// Get the inner [`JsonRpcClientState`] instance.
     pub fn to_state(&self) -> JsonRpcClientState {
         self.0.clone()
     }
 }
 
+/// A batch of jsonrpc calls, which are sent to the peer in one packet.
+///
+/// Each call returns a future that resolves independently once its response is received.
+pub struct JsonRpcBatch {
+    client: JsonRpcClientState,
+    /// The sequence of the batch packet in the send queue.
+    seq: usize,
+    ids: Vec<Id>,
+    requests: Vec<serde_json::Value>,
+    /// Whether the batch packet is pushed into the send queue.
+    sent: bool,
+}
+
+impl Drop for JsonRpcBatch {
+    fn drop(&mut self) {
+        if self.sent {
+            return;
+        }
+
+        // cancels the calls of the unsent batch.
+        let mut pending_calls = self.client.0.pending_calls.lock().unwrap();
+
+        for id in &self.ids {
+            pending_calls.remove(id);
+        }
+
+        drop(pending_calls);
+
+        self.client.0.wait_map.batch_insert(
+            self.ids
+                .drain(..)
+                .map(|id| (JsonRpcClientEvent::Response(id), ())),
+        );
+    }
+}
+
+impl JsonRpcBatch {
+    /// Add a jsonrpc v2.0 call to this batch, and returns a future waiting for its response.
+    ///
+    /// The returned future never resolves before this batch is [`sent`](Self::send),
+    /// and returns [`JsonRpcError::Canceled`] if this batch is dropped or failed to send.
+    pub fn call<M, P, R>(
+        &mut self,
+        method: M,
+        params: P,
+    ) -> JsonRpcResult<impl Future<Output = JsonRpcResult<R>>>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        let id = self.client.0.id_generator.next_id();
+
+        let request = Request {
+            id: Some(id.clone()),
+            jsonrpc: Version,
+            method: method.as_ref(),
+            params,
+        };
+
+        self.requests.push(serde_json::to_value(&request)?);
+        self.ids.push(id.clone());
+
+        let client = self.client.clone();
+
+        let guard = client.register_call(id.clone(), self.seq);
+
+        Ok(async move {
+            let _guard = guard;
+
+            client.wait_response(id).await
+        })
+    }
+
+    /// Returns the number of calls in this batch.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if this batch has no calls.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Consume self and push the batch packet into the send queue, an empty batch is not sent.
+    pub async fn send(mut self) -> JsonRpcResult<()> {
+        if self.ids.is_empty() {
+            return Ok(());
+        }
+
+        let requests = std::mem::take(&mut self.requests);
+
+        let packet = self
+            .client
+            .0
+            .codec
+            .encode(&serde_json::Value::Array(requests))?;
+
+        self.client.send_packet(self.seq, packet).await?;
+
+        self.sent = true;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -296,7 +936,7 @@ mod tests {
     use futures::poll;
     use serde_json::json;
 
-    use crate::{Error, ErrorCode};
+    use crate::ErrorCode;
 
     use super::*;
 
@@ -321,7 +961,7 @@ mod tests {
 
         let mut call = Box::pin(call_client.call("echo", ("hello", 1)));
 
-        let poll_result: Poll<io::Result<()>> = poll!(&mut call);
+        let poll_result: Poll<JsonRpcResult<()>> = poll!(&mut call);
 
         assert!(poll_result.is_pending());
 
@@ -341,7 +981,7 @@ mod tests {
             .await
             .unwrap();
 
-        let poll_result: Poll<io::Result<()>> = poll!(&mut call);
+        let poll_result: Poll<JsonRpcResult<()>> = poll!(&mut call);
 
         assert!(matches!(poll_result, Poll::Ready(Ok(()))));
 
@@ -349,7 +989,7 @@ mod tests {
 
         let mut call = Box::pin(call_client.call("echo", ("hello", 1)));
 
-        let poll_result: Poll<io::Result<i32>> = poll!(&mut call);
+        let poll_result: Poll<JsonRpcResult<i32>> = poll!(&mut call);
 
         assert!(poll_result.is_pending());
 
@@ -371,7 +1011,7 @@ mod tests {
 
         let mut call = Box::pin(call_client.call("echo", ("hello", 1)));
 
-        let poll_result: Poll<io::Result<i32>> = poll!(&mut call);
+        let poll_result: Poll<JsonRpcResult<i32>> = poll!(&mut call);
 
         assert!(poll_result.is_pending());
 
@@ -393,4 +1033,361 @@ mod tests {
 
         assert!(matches!(poll_result, Poll::Ready(Err(_))));
     }
+
+    #[futures_test::test]
+    async fn test_rpc_error() {
+        let client = JsonRpcClient::default();
+
+        let mut call = Box::pin(client.call::<_, _, i32>("eth_call", ()));
+
+        assert!(poll!(&mut call).is_pending());
+
+        let client = client.to_state();
+
+        // an application-defined error code out of the reserved range.
+        client
+            .recv(
+                json!({
+                    "id":0,"jsonrpc":"2.0","error": {
+                        "code": 3,
+                        "message": "execution reverted",
+                        "data": "0x08c379a0"
+                    }
+                })
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        let err = call.await.unwrap_err();
+
+        assert_eq!(err.code(), Some(&ErrorCode::Unknown(3)));
+        assert_eq!(err.code().unwrap().code(), 3);
+        assert_eq!(err.data(), Some(&json!("0x08c379a0")));
+        assert_eq!(err.rpc_error().unwrap().message, "execution reverted");
+    }
+
+    #[futures_test::test]
+    async fn test_batch() {
+        let client = JsonRpcClient::default();
+
+        let mut batch = client.batch();
+
+        let mut first = Box::pin(batch.call::<_, _, i32>("echo", (1,)).unwrap());
+        let mut second = Box::pin(batch.call::<_, _, String>("echo", ("hello",)).unwrap());
+
+        assert_eq!(batch.len(), 2);
+
+        batch.send().await.unwrap();
+
+        let client = client.to_state();
+
+        let (_, buf) = client.send().await.unwrap();
+
+        let json = json!([
+            {"id":0,"jsonrpc":"2.0","method":"echo","params":[1]},
+            {"id":1,"jsonrpc":"2.0","method":"echo","params":["hello"]}
+        ])
+        .to_string();
+
+        assert_eq!(json.as_bytes(), buf);
+
+        assert!(poll!(&mut first).is_pending());
+        assert!(poll!(&mut second).is_pending());
+
+        // responses of a batch can be returned in any order.
+        client
+            .recv(
+                json!([
+                    {"id":1,"jsonrpc":"2.0","result":"hello"},
+                    {"id":0,"jsonrpc":"2.0","result":1}
+                ])
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(second.await.unwrap(), "hello");
+        assert_eq!(first.await.unwrap(), 1);
+
+        assert!(client.batch().send().await.is_ok());
+    }
+
+    #[futures_test::test]
+    async fn test_batch_drop_unsent() {
+        let client = JsonRpcClient::default();
+
+        let mut batch = client.batch();
+
+        let call = batch.call::<_, _, i32>("echo", (1,)).unwrap();
+
+        drop(batch);
+
+        assert!(matches!(call.await.unwrap_err(), JsonRpcError::Canceled));
+
+        assert!(client.0 .0.pending_calls.lock().unwrap().is_empty());
+    }
+
+    #[futures_test::test]
+    async fn test_batch_invalid_object() {
+        let client = JsonRpcClient::default();
+
+        let mut call = Box::pin(client.call::<_, _, i32>("echo", (1,)));
+
+        assert!(poll!(&mut call).is_pending());
+
+        let state = client.to_state();
+
+        _ = state.send().await.unwrap();
+
+        // the invalid objects are dropped, the valid response is still delivered.
+        state
+            .recv(
+                json!([
+                    {"id":0,"jsonrpc":"2.0","result":1},
+                    42,
+                    {"jsonrpc":"2.0","method":1}
+                ])
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(call.await.unwrap(), 1);
+    }
+
+    #[futures_test::test]
+    async fn test_notify_and_incoming() {
+        let client = JsonRpcClient::default();
+
+        client.notify("log", ("hello",)).await.unwrap();
+
+        let state = client.to_state();
+
+        let (_, buf) = state.send().await.unwrap();
+
+        let json = json!({"jsonrpc":"2.0","method":"log","params":["hello"]}).to_string();
+
+        assert_eq!(json.as_bytes(), buf);
+
+        let mut call = Box::pin(state.call::<_, _, String>("eth_subscribe", ("newHeads",)));
+
+        assert!(poll!(&mut call).is_pending());
+
+        _ = state.send().await.unwrap();
+
+        let mut incoming = client.incoming();
+
+        assert!(poll!(incoming.next()).is_pending());
+
+        // a subscription push in the same batch as the response.
+        state
+            .recv(
+                json!([
+                    {"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x1","result":1}},
+                    {"id":0,"jsonrpc":"2.0","result":"0x1"}
+                ])
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(call.await.unwrap(), "0x1");
+
+        let request = incoming.next().await.unwrap();
+
+        assert_eq!(request.id, None);
+        assert_eq!(request.method, "eth_subscription");
+        assert_eq!(request.params, json!({"subscription":"0x1","result":1}));
+
+        // a request initiated by the peer.
+        state
+            .recv(json!({"id":0,"jsonrpc":"2.0","method":"ping","params":[]}).to_string())
+            .await
+            .unwrap();
+
+        let request = incoming.next().await.unwrap();
+
+        assert_eq!(request.id, Some(Id::from(0)));
+        assert_eq!(request.method, "ping");
+
+        drop(client);
+
+        assert!(incoming.next().await.is_none());
+    }
+
+    #[futures_test::test]
+    async fn test_drop_call() {
+        let client = JsonRpcClient::default();
+
+        let client = client.to_state();
+
+        let mut call = Box::pin(client.call::<_, _, i32>("echo", (1,)));
+
+        assert!(poll!(&mut call).is_pending());
+
+        _ = client.send().await.unwrap();
+
+        assert_eq!(client.0.pending_calls.lock().unwrap().len(), 1);
+
+        drop(call);
+
+        assert!(client.0.pending_calls.lock().unwrap().is_empty());
+
+        // the late response is dropped.
+        client
+            .recv(json!({"id":0,"jsonrpc":"2.0","result":1}).to_string())
+            .await
+            .unwrap();
+
+        assert!(client.0.pending_calls.lock().unwrap().is_empty());
+    }
+
+    #[futures_test::test]
+    async fn test_fail_pending() {
+        let client = JsonRpcClient::default();
+
+        let client = client.to_state();
+
+        let mut sent = Box::pin(client.call::<_, _, i32>("echo", (1,)));
+
+        assert!(poll!(&mut sent).is_pending());
+
+        _ = client.send().await.unwrap();
+
+        let mut queued = Box::pin(client.call::<_, _, i32>("echo", (2,)));
+
+        assert!(poll!(&mut queued).is_pending());
+
+        client.notify("log", ("hello",)).await.unwrap();
+
+        client
+            .fail_pending(io::ErrorKind::ConnectionReset, "transport is broken")
+            .await;
+
+        match sent.await.unwrap_err() {
+            JsonRpcError::Transport(err) => {
+                assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+                assert_eq!(err.to_string(), "transport is broken");
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        assert!(matches!(
+            queued.await.unwrap_err(),
+            JsonRpcError::Transport(_)
+        ));
+
+        // only the notification is left in the send queue.
+        let (_, buf) = client.send().await.unwrap();
+
+        let json = json!({"jsonrpc":"2.0","method":"log","params":["hello"]}).to_string();
+
+        assert_eq!(json.as_bytes(), buf);
+    }
+
+    #[futures_test::test]
+    async fn test_fail_batch_subset() {
+        let client = JsonRpcClient::default();
+
+        let mut batch = client.batch();
+
+        let first = batch.call::<_, _, i32>("echo", (1,)).unwrap();
+        let second = batch.call::<_, _, i32>("echo", (2,)).unwrap();
+
+        batch.send().await.unwrap();
+
+        let mut single = Box::pin(client.call::<_, _, i32>("echo", (3,)));
+
+        assert!(poll!(&mut single).is_pending());
+
+        let client = client.to_state();
+
+        // fails only the first call of the queued batch.
+        client
+            .fail_calls([Id::from(0)], io::ErrorKind::TimedOut, "timeout")
+            .await;
+
+        assert!(matches!(first.await.unwrap_err(), JsonRpcError::Timeout));
+
+        // the batch packet is removed from the send queue, so the second call is failed too.
+        assert!(matches!(second.await.unwrap_err(), JsonRpcError::Timeout));
+
+        // only the single call is left in the send queue.
+        let (_, buf) = client.send().await.unwrap();
+
+        let json = json!({"id":2,"jsonrpc":"2.0","method":"echo","params":[3]}).to_string();
+
+        assert_eq!(json.as_bytes(), buf);
+
+        assert!(poll!(&mut single).is_pending());
+    }
+
+    #[futures_test::test]
+    async fn test_id_generator() {
+        let next = AtomicUsize::new(0);
+
+        let client = JsonRpcClient::with_id_generator(10, move || {
+            Id::String(format!("req-{}", next.fetch_add(1, Ordering::Relaxed)))
+        });
+
+        let mut call = Box::pin(client.call::<_, _, i32>("echo", (1,)));
+
+        assert!(poll!(&mut call).is_pending());
+
+        let state = client.to_state();
+
+        let (_, buf) = state.send().await.unwrap();
+
+        let json = json!({"id":"req-0","jsonrpc":"2.0","method":"echo","params":[1]}).to_string();
+
+        assert_eq!(json.as_bytes(), buf);
+
+        state
+            .recv(json!({"id":"req-0","jsonrpc":"2.0","result":1}).to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(call.await.unwrap(), 1);
+    }
+
+    #[futures_test::test]
+    async fn test_null_id_error() {
+        let client = JsonRpcClient::default();
+
+        let mut call = Box::pin(client.call::<_, _, i32>("echo", (1,)));
+
+        assert!(poll!(&mut call).is_pending());
+
+        let state = client.to_state();
+
+        _ = state.send().await.unwrap();
+
+        let mut errors = client.errors();
+
+        assert!(poll!(errors.next()).is_pending());
+
+        state
+            .recv(
+                json!({
+                    "id":null,"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"}
+                })
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        let error = errors.next().await.unwrap();
+
+        assert_eq!(error.code, ErrorCode::ParseError);
+        assert_eq!(error.message, "Parse error");
+
+        // the pending call is not affected.
+        assert!(poll!(&mut call).is_pending());
+
+        drop(call);
+        drop(client);
+
+        assert!(errors.next().await.is_none());
+    }
 }
@@ -0,0 +1,149 @@
+//! The serialization formats of jsonrpc packets.
+//!
+//! The client and server state machines process packets through the JSON data model,
+//! a [`Codec`] converts the packets between bytes and [`serde_json::Value`], so the same
+//! state machines can run jsonrpc semantics over binary formats, e.g, CBOR or MessagePack.
+//!
+//! Since the values are converted through the JSON data model, the binary strings of the binary
+//! formats are not supported.
+//! The binary packets should be transported by a binary-safe framing,
+//! e.g, [`Framing::LengthPrefixed`](crate::framed::Framing::LengthPrefixed).
+
+use std::io;
+
+use serde_json::Value;
+
+/// The serialization format of jsonrpc packets.
+pub trait Codec: Send + Sync {
+    /// Encodes a jsonrpc object or a batch of objects into a packet.
+    fn encode(&self, value: &Value) -> io::Result<Vec<u8>>;
+
+    /// Decodes a packet into a jsonrpc object or a batch of objects.
+    fn decode(&self, packet: &[u8]) -> io::Result<Value>;
+
+    /// Returns the media type of the packets, e.g, the `Content-Type` of http transports.
+    fn media_type(&self) -> &'static str;
+}
+
+/// The default codec, which encodes packets as JSON text.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode(&self, value: &Value) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode(&self, packet: &[u8]) -> io::Result<Value> {
+        Ok(serde_json::from_slice(packet)?)
+    }
+
+    fn media_type(&self) -> &'static str {
+        "application/json"
+    }
+}
+
+/// The codec encodes packets as [CBOR](https://www.rfc-editor.org/rfc/rfc8949.html).
+#[cfg(feature = "cbor")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    fn encode(&self, value: &Value) -> io::Result<Vec<u8>> {
+        let mut packet = vec![];
+
+        ciborium::into_writer(value, &mut packet)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        Ok(packet)
+    }
+
+    fn decode(&self, packet: &[u8]) -> io::Result<Value> {
+        ciborium::from_reader(packet)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    fn media_type(&self) -> &'static str {
+        "application/cbor"
+    }
+}
+
+/// The codec encodes packets as [MessagePack](https://msgpack.org).
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MsgPackCodec;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MsgPackCodec {
+    fn encode(&self, value: &Value) -> io::Result<Vec<u8>> {
+        rmp_serde::to_vec(value).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn decode(&self, packet: &[u8]) -> io::Result<Value> {
+        rmp_serde::from_slice(packet).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn media_type(&self) -> &'static str {
+        "application/msgpack"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{client::JsonRpcClientBuilder, server::JsonRpcServerState, ErrorCode};
+
+    use super::*;
+
+    async fn roundtrip<C: Codec + Clone + 'static>(codec: C) {
+        let client = JsonRpcClientBuilder::new(10).codec(codec.clone()).create();
+
+        let server = JsonRpcServerState::with_codec(codec.clone());
+
+        server.handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+        let mut call = Box::pin(client.call::<_, _, i32>("add", (1, 2)));
+
+        assert!(futures::poll!(&mut call).is_pending());
+
+        let state = client.to_state();
+
+        let (_, packet) = state.send().await.unwrap();
+
+        assert_eq!(
+            codec.decode(&packet).unwrap(),
+            serde_json::json!({"id":0,"jsonrpc":"2.0","method":"add","params":[1,2]})
+        );
+
+        let packet = server.dispatch(packet).await.unwrap().unwrap();
+
+        state.recv(packet).await.unwrap();
+
+        assert_eq!(call.await.unwrap(), 3);
+
+        // a truncated packet is replied with a parse error.
+        let packet = server.dispatch([0x92]).await.unwrap().unwrap();
+
+        let resp = codec.decode(&packet).unwrap();
+
+        assert_eq!(resp["error"]["code"], ErrorCode::ParseError.code());
+    }
+
+    #[futures_test::test]
+    async fn test_json() {
+        roundtrip(JsonCodec).await;
+    }
+
+    #[cfg(feature = "cbor")]
+    #[futures_test::test]
+    async fn test_cbor() {
+        roundtrip(CborCodec).await;
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[futures_test::test]
+    async fn test_msgpack() {
+        roundtrip(MsgPackCodec).await;
+    }
+}
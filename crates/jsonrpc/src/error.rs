@@ -0,0 +1,88 @@
+use std::io;
+
+use serde_json::Value;
+
+use crate::{Error, ErrorCode};
+
+/// The error type of jsonrpc calls.
+#[derive(Debug, thiserror::Error)]
+pub enum JsonRpcError {
+    /// The error object replied by the peer, which keeps the error code and data.
+    #[error(transparent)]
+    Rpc(Error<String, Value>),
+    /// The transport is broken or failed to deliver the call.
+    #[error(transparent)]
+    Transport(io::Error),
+    /// Failed to serialize the params or deserialize the result.
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    /// The call didn't receive its response before the deadline.
+    #[error("jsonrpc call timeout")]
+    Timeout,
+    /// The client is closed.
+    #[error("JsonRpcClient is closed")]
+    Closed,
+    /// The call was canceled without response.
+    #[error("jsonrpc canceled.")]
+    Canceled,
+}
+
+/// The result type of jsonrpc calls.
+pub type JsonRpcResult<T> = Result<T, JsonRpcError>;
+
+impl JsonRpcError {
+    /// Returns the error code replied by the peer.
+    pub fn code(&self) -> Option<&ErrorCode> {
+        self.rpc_error().map(|err| &err.code)
+    }
+
+    /// Returns the error data replied by the peer.
+    pub fn data(&self) -> Option<&Value> {
+        self.rpc_error().and_then(|err| err.data.as_ref())
+    }
+
+    /// Returns the error object replied by the peer.
+    pub fn rpc_error(&self) -> Option<&Error<String, Value>> {
+        match self {
+            JsonRpcError::Rpc(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error<String, Value>> for JsonRpcError {
+    fn from(err: Error<String, Value>) -> Self {
+        JsonRpcError::Rpc(err)
+    }
+}
+
+impl From<io::Error> for JsonRpcError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut => JsonRpcError::Timeout,
+            _ => JsonRpcError::Transport(err),
+        }
+    }
+}
+
+impl From<JsonRpcError> for io::Error {
+    fn from(err: JsonRpcError) -> Self {
+        match err {
+            JsonRpcError::Transport(err) => err,
+            JsonRpcError::Serde(err) => io::Error::new(io::ErrorKind::InvalidData, err),
+            JsonRpcError::Timeout => io::Error::new(io::ErrorKind::TimedOut, err),
+            JsonRpcError::Closed => io::Error::new(io::ErrorKind::BrokenPipe, err),
+            err => io::Error::other(err),
+        }
+    }
+}
+
+/// Forwards the error object of a nested call as is, e.g, a method handler calls other servers.
+impl From<JsonRpcError> for Error<String, Value> {
+    fn from(err: JsonRpcError) -> Self {
+        match err {
+            JsonRpcError::Rpc(err) => err,
+            err => Error::from_std_error(err),
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Framing codecs for jsonrpc packets over byte streams, e.g, tcp, unix socket or stdio.
+
+use std::io;
+
+use futures::{
+    io::BufReader, AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite,
+    AsyncWriteExt, Sink, Stream,
+};
+
+/// The max length of a `Content-Length` framing header line.
+const MAX_HEADER_LINE_LEN: usize = 1024;
+
+/// The framing of jsonrpc packets over a byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Packets are delimited by `\n`, blank lines are skipped.
+    Newline,
+    /// Packets are prefixed with a `Content-Length: N\r\n\r\n` header,
+    /// which is used by the language server protocol.
+    ContentLength,
+    /// Packets are prefixed with a 4-byte big-endian length.
+    LengthPrefixed,
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn check_packet_len(len: usize, max_packet_len: usize) -> io::Result<()> {
+    if len > max_packet_len {
+        return Err(invalid_data(format!(
+            "Packet length too long, len={}, max_packet_len={}",
+            len, max_packet_len
+        )));
+    }
+
+    Ok(())
+}
+
+impl Framing {
+    /// Read a packet from `reader`, returns `None` if `reader` reaches EOF before a new packet.
+    pub async fn read_packet<R>(
+        &self,
+        reader: &mut R,
+        max_packet_len: usize,
+    ) -> io::Result<Option<Vec<u8>>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        match self {
+            Framing::Newline => Self::read_line(reader, max_packet_len).await,
+            Framing::ContentLength => Self::read_content_length(reader, max_packet_len).await,
+            Framing::LengthPrefixed => Self::read_length_prefixed(reader, max_packet_len).await,
+        }
+    }
+
+    async fn read_line<R>(reader: &mut R, max_packet_len: usize) -> io::Result<Option<Vec<u8>>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        loop {
+            let mut buf = vec![];
+
+            let read_size = (&mut *reader)
+                .take(max_packet_len as u64 + 2)
+                .read_until(b'\n', &mut buf)
+                .await?;
+
+            if read_size == 0 {
+                return Ok(None);
+            }
+
+            if buf.ends_with(b"\n") {
+                buf.pop();
+            }
+
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+
+            check_packet_len(buf.len(), max_packet_len)?;
+
+            if !buf.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(buf));
+            }
+        }
+    }
+
+    async fn read_content_length<R>(
+        reader: &mut R,
+        max_packet_len: usize,
+    ) -> io::Result<Option<Vec<u8>>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let mut content_length = None;
+        let mut headers = 0;
+
+        loop {
+            let mut line = vec![];
+
+            let read_size = (&mut *reader)
+                .take(MAX_HEADER_LINE_LEN as u64)
+                .read_until(b'\n', &mut line)
+                .await?;
+
+            if read_size == 0 {
+                if headers == 0 {
+                    return Ok(None);
+                }
+
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Unexpected EOF in framing headers",
+                ));
+            }
+
+            if !line.ends_with(b"\n") {
+                return Err(invalid_data("Framing header line too long"));
+            }
+
+            let line = std::str::from_utf8(&line).map_err(invalid_data)?.trim();
+
+            if line.is_empty() {
+                if headers == 0 {
+                    continue;
+                }
+
+                break;
+            }
+
+            headers += 1;
+
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid_data(format!("Invalid framing header: {}", line)))?;
+
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(invalid_data)?);
+            }
+        }
+
+        let len = content_length.ok_or_else(|| invalid_data("Content-Length header not found"))?;
+
+        check_packet_len(len, max_packet_len)?;
+
+        let mut buf = vec![0; len];
+
+        reader.read_exact(&mut buf).await?;
+
+        Ok(Some(buf))
+    }
+
+    async fn read_length_prefixed<R>(
+        reader: &mut R,
+        max_packet_len: usize,
+    ) -> io::Result<Option<Vec<u8>>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        if reader.fill_buf().await?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut len = [0u8; 4];
+
+        reader.read_exact(&mut len).await?;
+
+        let len = u32::from_be_bytes(len) as usize;
+
+        check_packet_len(len, max_packet_len)?;
+
+        let mut buf = vec![0; len];
+
+        reader.read_exact(&mut buf).await?;
+
+        Ok(Some(buf))
+    }
+
+    /// Write a `packet` to `writer` and flush it.
+    pub async fn write_packet<W>(&self, writer: &mut W, packet: &[u8]) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match self {
+            Framing::Newline => {
+                writer.write_all(packet).await?;
+                writer.write_all(b"\n").await?;
+            }
+            Framing::ContentLength => {
+                writer
+                    .write_all(format!("Content-Length: {}\r\n\r\n", packet.len()).as_bytes())
+                    .await?;
+                writer.write_all(packet).await?;
+            }
+            Framing::LengthPrefixed => {
+                let len = u32::try_from(packet.len()).map_err(invalid_data)?;
+
+                writer.write_all(&len.to_be_bytes()).await?;
+                writer.write_all(packet).await?;
+            }
+        }
+
+        writer.flush().await
+    }
+
+    /// Returns a stream of packets read from `read`.
+    ///
+    /// The stream ends when `read` reaches EOF or a read error occurs.
+    pub fn reader<R>(
+        self,
+        read: R,
+        max_packet_len: usize,
+    ) -> impl Stream<Item = Vec<u8>> + Send + Unpin
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        Box::pin(futures::stream::unfold(
+            BufReader::new(read),
+            move |mut reader| async move {
+                match self.read_packet(&mut reader, max_packet_len).await {
+                    Ok(Some(packet)) => Some((packet, reader)),
+                    Ok(None) => None,
+                    Err(err) => {
+                        log::error!("read jsonrpc packet, {:?}, {}", self, err);
+                        None
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Returns a sink that writes packets to `write`.
+    pub fn writer<W>(self, write: W) -> impl Sink<Vec<u8>, Error = io::Error> + Send + Unpin
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        Box::pin(futures::sink::unfold(
+            write,
+            move |mut write, packet: Vec<u8>| async move {
+                self.write_packet(&mut write, &packet).await?;
+
+                Ok::<_, io::Error>(write)
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{io::Cursor, StreamExt};
+
+    use super::*;
+
+    async fn write_packets(framing: Framing, packets: &[&str]) -> Vec<u8> {
+        let mut buf = Cursor::new(vec![]);
+
+        for packet in packets {
+            framing
+                .write_packet(&mut buf, packet.as_bytes())
+                .await
+                .unwrap();
+        }
+
+        buf.into_inner()
+    }
+
+    #[futures_test::test]
+    async fn test_framing() {
+        let packets = [r#"{"id":1}"#, r#"{"id":2}"#, "[]"];
+
+        for framing in [
+            Framing::Newline,
+            Framing::ContentLength,
+            Framing::LengthPrefixed,
+        ] {
+            let buf = write_packets(framing, &packets).await;
+
+            let read = framing
+                .reader(Cursor::new(buf), 1024)
+                .collect::<Vec<_>>()
+                .await;
+
+            assert_eq!(
+                read,
+                packets.map(|packet| packet.as_bytes().to_vec()),
+                "{:?}",
+                framing
+            );
+        }
+    }
+
+    #[futures_test::test]
+    async fn test_lsp_headers() {
+        let buf = b"Content-Length: 2\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}\r\ncontent-length:4\r\n\r\nnull";
+
+        let mut reader = BufReader::new(Cursor::new(buf.to_vec()));
+
+        assert_eq!(
+            Framing::ContentLength
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            Some(b"{}".to_vec())
+        );
+
+        assert_eq!(
+            Framing::ContentLength
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            Some(b"null".to_vec())
+        );
+
+        assert_eq!(
+            Framing::ContentLength
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[futures_test::test]
+    async fn test_newline_blank_lines() {
+        let mut reader = BufReader::new(Cursor::new(b"\r\n\n{}\r\n\n[1]".to_vec()));
+
+        assert_eq!(
+            Framing::Newline
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            Some(b"{}".to_vec())
+        );
+
+        assert_eq!(
+            Framing::Newline
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            Some(b"[1]".to_vec())
+        );
+
+        assert_eq!(
+            Framing::Newline
+                .read_packet(&mut reader, 1024)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[futures_test::test]
+    async fn test_max_packet_len() {
+        for framing in [
+            Framing::Newline,
+            Framing::ContentLength,
+            Framing::LengthPrefixed,
+        ] {
+            let buf = write_packets(framing, &[r#"{"id":1}"#]).await;
+
+            let mut reader = BufReader::new(Cursor::new(buf));
+
+            let err = framing.read_packet(&mut reader, 4).await.unwrap_err();
+
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", framing);
+        }
+    }
+}
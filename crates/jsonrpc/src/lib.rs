@@ -1,7 +1,27 @@
 mod object;
 pub use object::*;
 
+mod error;
+pub use error::*;
+
 pub mod client;
+pub mod codec;
+pub mod framed;
+pub mod peer;
+pub mod server;
+
+#[cfg(feature = "openrpc")]
+pub mod openrpc;
 
 #[cfg(feature = "with_rasi")]
 pub mod rasi;
+
+#[cfg(feature = "macros")]
+pub use futures_jsonrpcv2_macros::jsonrpc;
+
+/// The dependencies used by the generated code of macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    pub use serde_json;
+}
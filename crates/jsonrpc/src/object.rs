@@ -13,8 +13,12 @@ where
     /// An identifier established by the Client that MUST contain a String, Number,
     /// or NULL value if included. If it is not included it is assumed to be a notification.
     /// The value SHOULD normally not be Null and Numbers SHOULD NOT contain fractional parts
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_id"
+    )]
+    pub id: Option<Id>,
     /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
     pub jsonrpc: Version,
     /// A String containing the name of the method to be invoked. Method names
@@ -25,6 +29,55 @@ where
     pub params: P,
 }
 
+/// An identifier established by the Client, which is a Number, a String or Null.
+///
+/// visit [`here`](https://www.jsonrpc.org/specification#request_object) for details
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+    Number(serde_json::Number),
+    String(String),
+    #[default]
+    Null,
+}
+
+impl Display for Id {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Id::Number(id) => write!(f, "{}", id),
+            Id::String(id) => write!(f, "{:?}", id),
+            Id::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl From<u64> for Id {
+    fn from(id: u64) -> Self {
+        Id::Number(id.into())
+    }
+}
+
+/// Deserialize an included `id` into `Some`, even if it is null,
+/// so only the objects without `id` are notifications.
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<Id>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Id::deserialize(deserializer).map(Some)
+}
+
+impl From<String> for Id {
+    fn from(id: String) -> Self {
+        Id::String(id)
+    }
+}
+
+impl From<&str> for Id {
+    fn from(id: &str) -> Self {
+        Id::String(id.to_owned())
+    }
+}
+
 /// JSONRPC version type.
 ///
 /// When [`Serialize`]/[`Deserialize`] JSONRPC object, automatic fill or check version string "2.0"
@@ -59,9 +112,10 @@ pub struct Response<S, R, D>
 where
     S: AsRef<str>,
 {
-    /// This member is REQUIRED on error.
-    /// This member MUST NOT exist if there was no error triggered during invocation.
-    pub id: usize,
+    /// This member is REQUIRED.
+    /// It MUST be the same as the value of the id member in the Request Object,
+    /// or Null if there was an error in detecting the id in the Request object.
+    pub id: Id,
     /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
     pub jsonrpc: Version,
     /// This member is REQUIRED on success.
@@ -85,8 +139,12 @@ where
     /// An identifier established by the Client that MUST contain a String, Number,
     /// or NULL value if included. If it is not included it is assumed to be a notification.
     /// The value SHOULD normally not be Null and Numbers SHOULD NOT contain fractional parts
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_id"
+    )]
+    pub id: Option<Id>,
     /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
     pub jsonrpc: Version,
     /// A String containing the name of the method to be invoked. Method names
@@ -109,6 +167,26 @@ where
     pub error: Option<Error<S, D>>,
 }
 
+/// A jsonrpc packet, which is a single object or a batch array of objects.
+///
+/// visit [`here`](https://www.jsonrpc.org/specification#batch) for details
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Packet<T> {
+    Batch(Vec<T>),
+    Single(T),
+}
+
+impl<T> Packet<T> {
+    /// Returns the objects in this packet.
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Packet::Batch(objects) => objects,
+            Packet::Single(object) => vec![object],
+        }
+    }
+}
+
 /// When a rpc call encounters an error,
 /// the Response Object MUST contain the error member with a value that is a Object.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, thiserror::Error)]
@@ -186,24 +264,32 @@ pub enum ErrorCode {
     #[error("Server error({0}),{1}")]
     ServerError(i64, String),
 
-    #[error("Invalid JSONRPC error code ({0})")]
+    /// The application-defined error codes out of the reserved range.
+    #[error("Application error code ({0})")]
     Unknown(i64),
 }
 
+impl ErrorCode {
+    /// Returns the numeric error code.
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::ParseError => -32700,
+            Self::InvalidRequest => -32600,
+            Self::MethodNotFound => -32601,
+            Self::InvalidParams => -32602,
+            Self::InternalError => -32603,
+            Self::ServerError(code, _) => *code,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
 impl serde::Serialize for ErrorCode {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        match self {
-            Self::ParseError => serializer.serialize_i64(-32700),
-            Self::InvalidRequest => serializer.serialize_i64(-32600),
-            Self::MethodNotFound => serializer.serialize_i64(-32601),
-            Self::InvalidParams => serializer.serialize_i64(-32602),
-            Self::InternalError => serializer.serialize_i64(-32603),
-            Self::ServerError(code, _) => serializer.serialize_i64(*code),
-            Self::Unknown(code) => serializer.serialize_i64(*code),
-        }
+        serializer.serialize_i64(self.code())
     }
 }
 
@@ -225,7 +311,7 @@ impl<'de> serde::Deserialize<'de> for ErrorCode {
                 if code <= -32000 && code >= -32099 {
                     Ok(ErrorCode::ServerError(code, "".to_owned()))
                 } else {
-                    Err(ErrorCode::Unknown(code)).map_err(serde::de::Error::custom)
+                    Ok(ErrorCode::Unknown(code))
                 }
             }
         }
@@ -244,7 +330,7 @@ mod visitor {
         type Value = i64;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("Version string MUST be exactly 2.0")
+            formatter.write_str("an integer between -2^63 and 2^63")
         }
 
         fn visit_i8<E>(self, value: i8) -> Result<Self::Value, E>
@@ -267,6 +353,13 @@ mod visitor {
         {
             Ok(value)
         }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            i64::try_from(value).map_err(de::Error::custom)
+        }
     }
 
     pub struct VersionVisitor;
@@ -275,7 +368,7 @@ mod visitor {
         type Value = Version;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("an integer between -2^63 and 2^63")
+            formatter.write_str("Version string MUST be exactly 2.0")
         }
 
         fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -316,7 +409,7 @@ mod tests {
     use serde::{Deserialize, Serialize};
     use serde_json::json;
 
-    use crate::Request;
+    use crate::{Id, Packet, Request, Response};
 
     #[test]
     fn test_array_params() {
@@ -406,4 +499,89 @@ mod tests {
         assert_eq!(request.params.id, 20);
         assert_eq!(request.params.name, "hello");
     }
+
+    #[test]
+    fn test_batch_packet() {
+        let packet = serde_json::from_value::<Packet<Response<String, i32, ()>>>(
+            json!([{"id":1,"jsonrpc":"2.0","result":1},{"id":2,"jsonrpc":"2.0","result":2}]),
+        )
+        .expect("deserialize batch");
+
+        assert_eq!(
+            packet
+                .into_vec()
+                .into_iter()
+                .map(|resp| (resp.id, resp.result))
+                .collect::<Vec<_>>(),
+            [(Id::from(1), Some(1)), (Id::from(2), Some(2))]
+        );
+
+        let packet = serde_json::from_value::<Packet<Response<String, i32, ()>>>(
+            json!({"id":1,"jsonrpc":"2.0","result":1}),
+        )
+        .expect("deserialize single");
+
+        assert!(matches!(packet, Packet::Single(_)));
+    }
+
+    #[test]
+    fn test_id() {
+        let packet = serde_json::from_value::<Packet<Response<String, i32, ()>>>(json!([
+            {"id":1,"jsonrpc":"2.0","result":1},
+            {"id":"c9a0b1e2","jsonrpc":"2.0","result":2},
+            {"id":null,"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"}}
+        ]))
+        .expect("deserialize ids");
+
+        assert_eq!(
+            packet
+                .into_vec()
+                .into_iter()
+                .map(|resp| resp.id)
+                .collect::<Vec<_>>(),
+            [Id::from(1), Id::from("c9a0b1e2"), Id::Null]
+        );
+
+        let request = Request {
+            id: Some(Id::from("c9a0b1e2")),
+            method: "hello",
+            params: (),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({"id":"c9a0b1e2","jsonrpc":"2.0","method":"hello","params":null})
+        );
+
+        assert_eq!(
+            serde_json::to_value(Id::Null).unwrap(),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_request_id() {
+        let request = |value| serde_json::from_value::<Request<String, ()>>(value).unwrap();
+
+        // a null id is not a notification.
+        assert_eq!(
+            request(json!({"id":null,"jsonrpc":"2.0","method":"hello","params":null})).id,
+            Some(Id::Null)
+        );
+
+        assert_eq!(
+            request(json!({"jsonrpc":"2.0","method":"hello","params":null})).id,
+            None
+        );
+
+        assert_eq!(
+            request(json!({"id":-1,"jsonrpc":"2.0","method":"hello","params":null})).id,
+            Some(Id::Number((-1).into()))
+        );
+
+        let id = request(json!({"id":1.5,"jsonrpc":"2.0","method":"hello","params":null})).id;
+
+        assert_eq!(serde_json::to_value(id).unwrap(), json!(1.5));
+    }
 }
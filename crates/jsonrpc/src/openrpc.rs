@@ -0,0 +1,438 @@
+//! [OpenRPC](https://spec.open-rpc.org) documents of jsonrpc servers.
+//!
+//! The params and results are described by the JSON Schema (draft 7) generated by [`schemars`].
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use schemars::{self, JsonSchema};
+
+use schemars::generate::SchemaSettings;
+
+/// The OpenRPC specification version of the generated documents.
+pub const OPENRPC_VERSION: &str = "1.3.2";
+
+/// The reserved method name of service discovery, which returns the [`OpenRpc`] document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// The root object of an OpenRPC document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenRpc {
+    /// The semantic version number of the OpenRPC Specification version that the document uses.
+    pub openrpc: String,
+    /// Provides metadata about the API.
+    pub info: Info,
+    /// The available methods for the API.
+    pub methods: Vec<Method>,
+    /// The reusable schemas referenced by the methods, e.g, the schemas of recursive types.
+    #[serde(default, skip_serializing_if = "Components::is_empty")]
+    pub components: Components,
+}
+
+/// The metadata about the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+    /// The title of the application.
+    pub title: String,
+    /// The version of the OpenRPC document.
+    pub version: String,
+    /// A verbose description of the application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Info {
+    /// Create a new `Info` with `title` and `version`.
+    pub fn new<T: Into<String>, V: Into<String>>(title: T, version: V) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+        }
+    }
+
+    /// Set the verbose description of the application.
+    pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// The reusable objects of an OpenRPC document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Components {
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub schemas: Map<String, Value>,
+}
+
+impl Components {
+    /// Returns true if there are no reusable objects.
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+}
+
+/// The expected format of the parameters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamStructure {
+    #[serde(rename = "by-position")]
+    ByPosition,
+    #[serde(rename = "by-name")]
+    ByName,
+    #[default]
+    #[serde(rename = "either")]
+    Either,
+}
+
+/// Describes the interface of a method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Method {
+    /// The canonical name of the method.
+    pub name: String,
+    /// A verbose explanation of the method behavior.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The parameters of the method, the order is significant for positional params.
+    pub params: Vec<ContentDescriptor>,
+    /// The description of the result, is `None` for notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<ContentDescriptor>,
+    /// The expected format of the parameters.
+    #[serde(default)]
+    pub param_structure: ParamStructure,
+    /// The schemas referenced by the params and result, which are moved into [`Components`].
+    #[serde(skip)]
+    definitions: Map<String, Value>,
+}
+
+impl Method {
+    /// Create a new method description without params and result.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            params: vec![],
+            result: None,
+            param_structure: ParamStructure::default(),
+            definitions: Map::new(),
+        }
+    }
+
+    /// Set the verbose explanation of the method behavior.
+    pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the expected format of the parameters.
+    pub fn param_structure(mut self, param_structure: ParamStructure) -> Self {
+        self.param_structure = param_structure;
+        self
+    }
+
+    /// Appends a required param of type `T`.
+    pub fn param<T: JsonSchema + ?Sized, N: Into<String>>(self, name: N) -> Self {
+        self.push_param::<T>(name.into(), true)
+    }
+
+    /// Appends an optional param of type `T`, which can be omitted by clients.
+    pub fn optional_param<T: JsonSchema + ?Sized, N: Into<String>>(self, name: N) -> Self {
+        self.push_param::<T>(name.into(), false)
+    }
+
+    fn push_param<T: JsonSchema + ?Sized>(mut self, name: String, required: bool) -> Self {
+        let schema = self.schema_for::<T>();
+
+        self.params.push(ContentDescriptor {
+            name,
+            required,
+            schema,
+        });
+
+        self
+    }
+
+    /// Set the result of type `T`.
+    pub fn result<T: JsonSchema + ?Sized, N: Into<String>>(mut self, name: N) -> Self {
+        let schema = self.schema_for::<T>();
+
+        self.result = Some(ContentDescriptor {
+            name: name.into(),
+            required: true,
+            schema,
+        });
+
+        self
+    }
+
+    /// Generates the schema of `T`, the subschemas are inlined except of recursive ones,
+    /// which are referenced from [`Components`].
+    fn schema_for<T: JsonSchema + ?Sized>(&mut self) -> Value {
+        let mut generator = SchemaSettings::draft07()
+            .with(|settings| {
+                settings.inline_subschemas = true;
+                settings.definitions_path = "/components/schemas".into();
+            })
+            .into_generator();
+
+        let mut schema = generator.subschema_for::<T>();
+
+        for transform in generator.transforms_mut() {
+            transform.transform(&mut schema);
+        }
+
+        self.definitions.extend(generator.take_definitions(true));
+
+        schema.to_value()
+    }
+}
+
+/// Describes the param or result of a method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentDescriptor {
+    /// The name of the content descriptor.
+    pub name: String,
+    /// Determines if the content is required.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// The JSON Schema of the content.
+    pub schema: Value,
+}
+
+impl OpenRpc {
+    /// Create a new document of `methods`.
+    pub fn new<I: IntoIterator<Item = Method>>(info: Info, methods: I) -> Self {
+        let mut components = Components::default();
+
+        let methods = methods
+            .into_iter()
+            .map(|mut method| {
+                components
+                    .schemas
+                    .extend(std::mem::take(&mut method.definitions));
+
+                method
+            })
+            .collect();
+
+        Self {
+            openrpc: OPENRPC_VERSION.to_owned(),
+            info,
+            methods,
+            components,
+        }
+    }
+
+    /// Returns the method description of `name`.
+    pub fn method(&self, name: &str) -> Option<&Method> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// Checks the methods of a client stub against this document, e.g, a published one.
+    ///
+    /// A stub method matches if the document has a method with the same name, param structure,
+    /// params and result. The schemas are compared as is, so both sides should be generated
+    /// by the same schema generator.
+    pub fn check<'a, I>(&self, stub: I) -> Result<(), Vec<Mismatch>>
+    where
+        I: IntoIterator<Item = &'a Method>,
+    {
+        let mismatches = stub
+            .into_iter()
+            .flat_map(|method| self.check_method(method))
+            .collect::<Vec<_>>();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+
+    fn check_method(&self, stub: &Method) -> Vec<Mismatch> {
+        let Some(method) = self.method(&stub.name) else {
+            return vec![Mismatch::MethodNotFound(stub.name.clone())];
+        };
+
+        let mut mismatches = vec![];
+
+        let param_structure = match (stub.param_structure, method.param_structure) {
+            (ParamStructure::Either, _) => method.param_structure,
+            (param_structure, ParamStructure::Either) => param_structure,
+            (stub_structure, param_structure) => {
+                if stub_structure != param_structure {
+                    mismatches.push(Mismatch::ParamStructure {
+                        method: stub.name.clone(),
+                        expected: param_structure,
+                        found: stub_structure,
+                    });
+                }
+
+                param_structure
+            }
+        };
+
+        if param_structure == ParamStructure::ByName {
+            for param in &stub.params {
+                match method.params.iter().find(|p| p.name == param.name) {
+                    Some(p) if p.schema == param.schema => {}
+                    _ => mismatches.push(Mismatch::Param {
+                        method: stub.name.clone(),
+                        param: param.name.clone(),
+                    }),
+                }
+            }
+
+            for param in method.params.iter().filter(|param| param.required) {
+                if !stub.params.iter().any(|p| p.name == param.name) {
+                    mismatches.push(Mismatch::Param {
+                        method: stub.name.clone(),
+                        param: param.name.clone(),
+                    });
+                }
+            }
+        } else {
+            let required = method.params.iter().filter(|param| param.required).count();
+
+            if stub.params.len() < required || stub.params.len() > method.params.len() {
+                mismatches.push(Mismatch::ParamsLen {
+                    method: stub.name.clone(),
+                    expected: method.params.len(),
+                    found: stub.params.len(),
+                });
+            }
+
+            for (param, p) in stub.params.iter().zip(method.params.iter()) {
+                if param.schema != p.schema {
+                    mismatches.push(Mismatch::Param {
+                        method: stub.name.clone(),
+                        param: param.name.clone(),
+                    });
+                }
+            }
+        }
+
+        // a notification of a method with result is allowed, the result is discarded.
+        if let Some(result) = &stub.result {
+            if method.result.as_ref().map(|r| &r.schema) != Some(&result.schema) {
+                mismatches.push(Mismatch::Result(stub.name.clone()));
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// A mismatch between a client stub and an OpenRPC document, see [`OpenRpc::check`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Mismatch {
+    #[error("method {0} is not found")]
+    MethodNotFound(String),
+    #[error("method {method} expects {expected:?} params, found {found:?}")]
+    ParamStructure {
+        method: String,
+        expected: ParamStructure,
+        found: ParamStructure,
+    },
+    #[error("method {method} expects {expected} params, found {found}")]
+    ParamsLen {
+        method: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("the param {param} of method {method} mismatches")]
+    Param { method: String, param: String },
+    #[error("the result of method {0} mismatches")]
+    Result(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(JsonSchema)]
+    #[allow(unused)]
+    struct Node {
+        value: i32,
+        children: Vec<Node>,
+    }
+
+    fn methods() -> Vec<Method> {
+        vec![
+            Method::new("add")
+                .description("Returns a + b")
+                .param_structure(ParamStructure::ByPosition)
+                .param::<i32, _>("a")
+                .param::<i32, _>("b")
+                .result::<i32, _>("sum"),
+            Method::new("tree")
+                .param_structure(ParamStructure::ByName)
+                .param::<Node, _>("root")
+                .optional_param::<Option<String>, _>("filter"),
+        ]
+    }
+
+    #[test]
+    fn test_document() {
+        let document = OpenRpc::new(Info::new("calculator", "1.0.0"), methods());
+
+        let value = serde_json::to_value(&document).unwrap();
+
+        assert_eq!(value["openrpc"], OPENRPC_VERSION);
+        assert_eq!(
+            value["methods"][0],
+            json!({
+                "name": "add",
+                "description": "Returns a + b",
+                "params": [
+                    {"name":"a","required":true,"schema":{"type":"integer","format":"int32"}},
+                    {"name":"b","required":true,"schema":{"type":"integer","format":"int32"}}
+                ],
+                "result": {"name":"sum","required":true,"schema":{"type":"integer","format":"int32"}},
+                "paramStructure": "by-position"
+            })
+        );
+
+        // the recursive type is referenced from components.
+        assert!(value["components"]["schemas"]["Node"].is_object());
+
+        let document: OpenRpc = serde_json::from_value(value).unwrap();
+
+        assert_eq!(document.check(&methods()), Ok(()));
+    }
+
+    #[test]
+    fn test_check() {
+        let document = OpenRpc::new(Info::new("calculator", "1.0.0"), methods());
+
+        let stub = [
+            Method::new("add")
+                .param::<i32, _>("a")
+                .param::<String, _>("b")
+                .result::<i32, _>("sum"),
+            Method::new("tree")
+                .param_structure(ParamStructure::ByPosition)
+                .param::<Node, _>("root"),
+            Method::new("sub").param::<i32, _>("a"),
+        ];
+
+        assert_eq!(
+            document.check(&stub),
+            Err(vec![
+                Mismatch::Param {
+                    method: "add".to_owned(),
+                    param: "b".to_owned()
+                },
+                Mismatch::ParamStructure {
+                    method: "tree".to_owned(),
+                    expected: ParamStructure::ByName,
+                    found: ParamStructure::ByPosition
+                },
+                Mismatch::MethodNotFound("sub".to_owned())
+            ])
+        );
+    }
+}
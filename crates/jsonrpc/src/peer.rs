@@ -0,0 +1,426 @@
+use std::{
+    future::poll_fn,
+    io,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use futures::{
+    future::{select, Either},
+    Future, SinkExt, StreamExt,
+};
+use futures_map::FuturesUnorderedMap;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    client::{
+        JsonRpcBatch, JsonRpcClientBuilder, JsonRpcClientReceiver, JsonRpcClientSender,
+        JsonRpcClientState,
+    },
+    codec::{Codec, JsonCodec},
+    server::{HandlerResult, JsonRpcServerState},
+    JsonRpcResult,
+};
+
+struct RawJsonRpcPeerState {
+    client: JsonRpcClientState,
+    server: JsonRpcServerState,
+    codec: Arc<dyn Codec>,
+    next_dispatch_id: AtomicUsize,
+    dispatches: FuturesUnorderedMap<usize, io::Result<()>>,
+}
+
+/// The jsonrpc peer without [`Drop`] support.
+///
+/// A peer is both a client and a server over one duplex transport, the packets received from
+/// the transport are demultiplexed into responses of the pending calls and requests from the
+/// remote peer, the calls and the replies share one send queue.
+#[derive(Clone)]
+pub struct JsonRpcPeerState(Arc<RawJsonRpcPeerState>);
+
+impl JsonRpcPeerState {
+    /// Create a new `JsonRpcPeerState` with provided send cache channel length.
+    pub fn new(max_send_queue_size: usize) -> Self {
+        Self::with_codec(max_send_queue_size, JsonCodec)
+    }
+
+    /// Create a new `JsonRpcPeerState` with provided send cache channel length,
+    /// which encodes and decodes packets with `codec`.
+    pub fn with_codec<C: Codec + 'static>(max_send_queue_size: usize, codec: C) -> Self {
+        let codec: Arc<dyn Codec> = Arc::new(codec);
+
+        Self(Arc::new(RawJsonRpcPeerState {
+            client: JsonRpcClientBuilder::new(max_send_queue_size)
+                .shared_codec(codec.clone())
+                .create_state(),
+            server: JsonRpcServerState::with_shared_codec(codec.clone()),
+            codec,
+            next_dispatch_id: Default::default(),
+            dispatches: FuturesUnorderedMap::new(),
+        }))
+    }
+
+    /// Register an async `handler` for `method` called by the remote peer,
+    /// see [`JsonRpcServerState::handle`] for more details.
+    pub fn handle<M, F, Fut, P, R>(&self, method: M, handler: F)
+    where
+        M: Into<String>,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult<R>> + Send + 'static,
+        P: DeserializeOwned,
+        R: Serialize,
+    {
+        self.0.server.handle(method, handler)
+    }
+
+    /// Invoke a jsonrpc v2.0 call and waiting for response.
+    pub async fn call<M, P, R>(&self, method: M, params: P) -> JsonRpcResult<R>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        self.0.client.call(method, params).await
+    }
+
+    /// Send a jsonrpc v2.0 notification, which has no response.
+    pub async fn notify<M, P>(&self, method: M, params: P) -> JsonRpcResult<()>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+    {
+        self.0.client.notify(method, params).await
+    }
+
+    /// Create a new [`JsonRpcBatch`] to send several calls in one packet.
+    pub fn batch(&self) -> JsonRpcBatch {
+        self.0.client.batch()
+    }
+
+    /// Processes jsonrpc packet received from the remote peer.
+    ///
+    /// Unlike [`JsonRpcServerState::recv`], this function doesn't wait for the method handlers,
+    /// which are driven by [`send`](Self::send) instead. So the handlers can call the remote peer.
+    pub async fn recv<V: AsRef<[u8]>>(&self, packet: V) -> std::io::Result<()> {
+        if self.is_closed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "JsonRpcPeer is closed",
+            ));
+        }
+
+        let Ok(value) = self.0.codec.decode(packet.as_ref()) else {
+            // reply with a `ParseError` response.
+            if let Some(packet) = self.0.server.dispatch(packet).await? {
+                self.0.client.push_packet(packet).await;
+            }
+
+            return Ok(());
+        };
+
+        let (is_batch, objects) = match value {
+            Value::Array(objects) => (true, objects),
+            value => (false, vec![value]),
+        };
+
+        let (responses, requests): (Vec<_>, Vec<_>) = objects.into_iter().partition(is_response);
+
+        if !responses.is_empty() {
+            self.0.client.recv_objects(responses).await?;
+
+            // the batch only contains responses.
+            if requests.is_empty() {
+                return Ok(());
+            }
+        }
+
+        let value = if is_batch {
+            Value::Array(requests)
+        } else {
+            requests.into_iter().next().expect("single request")
+        };
+
+        let server = self.0.server.clone();
+        let client = self.0.client.clone();
+
+        self.0.dispatches.insert(
+            self.0.next_dispatch_id.fetch_add(1, Ordering::Relaxed),
+            async move {
+                if let Some(packet) = server.dispatch_value(value).await? {
+                    client.push_packet(packet).await;
+                }
+
+                Ok(())
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Writes a single jsonrpc packet to be sent to the remote peer,
+    /// which is a request of local calls or a reply of remote calls.
+    ///
+    /// Only one task can call this function at a time.
+    pub async fn send(&self) -> std::io::Result<Vec<u8>> {
+        loop {
+            let dispatch = poll_fn(|cx| self.0.dispatches.poll_next(cx));
+
+            match select(pin!(self.0.client.send()), pin!(dispatch)).await {
+                Either::Left((packet, _)) => return packet.map(|(_, packet)| packet),
+                Either::Right(((_, Err(err)), _)) => {
+                    log::error!("dispatch jsonrpc request, {}", err);
+                }
+                Either::Right(_) => {}
+            }
+        }
+    }
+
+    /// Drives this peer over a duplex transport until the `receiver` stream ends,
+    /// or returns an error if the transport is broken or the peer is closed.
+    ///
+    /// On return, the pending calls are failed with [`ConnectionReset`](io::ErrorKind::ConnectionReset).
+    pub async fn run<S, R, E>(&self, mut sender: S, mut receiver: R) -> std::io::Result<()>
+    where
+        S: JsonRpcClientSender<E>,
+        R: JsonRpcClientReceiver,
+        E: ToString,
+    {
+        let result = loop {
+            match select(pin!(self.send()), receiver.next()).await {
+                Either::Left((packet, _)) => {
+                    let packet = match packet {
+                        Ok(packet) => packet,
+                        Err(err) => break Err(err),
+                    };
+
+                    if let Err(err) = sender.send(packet).await {
+                        break Err(io::Error::new(io::ErrorKind::BrokenPipe, err.to_string()));
+                    }
+                }
+                Either::Right((Some(packet), _)) => {
+                    if let Err(err) = self.recv(packet).await {
+                        if self.is_closed() {
+                            break Err(err);
+                        }
+
+                        log::error!("recv jsonrpc packet, {}", err);
+                    }
+                }
+                Either::Right((None, _)) => break Ok(()),
+            }
+        };
+
+        let message = match &result {
+            Ok(_) => "transport is closed".to_owned(),
+            Err(err) => err.to_string(),
+        };
+
+        self.0
+            .client
+            .fail_pending(io::ErrorKind::ConnectionReset, message)
+            .await;
+
+        result
+    }
+
+    /// Close the jsonrpc peer.
+    pub fn close(&self) {
+        self.0.client.close();
+        self.0.server.close();
+    }
+
+    /// Returns true if this peer is already closed.
+    pub fn is_closed(&self) -> bool {
+        self.0.client.is_closed()
+    }
+}
+
+/// Returns true if `object` is a response, which has an id but no method member.
+fn is_response(object: &Value) -> bool {
+    object.get("method").is_none() && object.get("id").is_some()
+}
+
+/// Jsonrpc v2.0 peer state machine.
+pub struct JsonRpcPeer(JsonRpcPeerState);
+
+impl Drop for JsonRpcPeer {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+impl Default for JsonRpcPeer {
+    fn default() -> Self {
+        Self::new(128)
+    }
+}
+
+impl JsonRpcPeer {
+    /// Create a new `JsonRpcPeer` with provided send cache channel length.
+    pub fn new(max_send_queue_size: usize) -> Self {
+        Self(JsonRpcPeerState::new(max_send_queue_size))
+    }
+
+    /// Create a new `JsonRpcPeer` with provided send cache channel length and `codec`.
+    pub fn with_codec<C: Codec + 'static>(max_send_queue_size: usize, codec: C) -> Self {
+        Self(JsonRpcPeerState::with_codec(max_send_queue_size, codec))
+    }
+
+    /// Register an async `handler` for `method` called by the remote peer.
+    pub fn handle<M, F, Fut, P, R>(self, method: M, handler: F) -> Self
+    where
+        M: Into<String>,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult<R>> + Send + 'static,
+        P: DeserializeOwned,
+        R: Serialize,
+    {
+        self.0.handle(method, handler);
+        self
+    }
+
+    /// Invoke a jsonrpc v2.0 call and waiting for response.
+    pub async fn call<M, P, R>(&self, method: M, params: P) -> JsonRpcResult<R>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+        for<'a> R: serde::Deserialize<'a>,
+    {
+        self.0.call(method, params).await
+    }
+
+    /// Send a jsonrpc v2.0 notification, which has no response.
+    pub async fn notify<M, P>(&self, method: M, params: P) -> JsonRpcResult<()>
+    where
+        M: AsRef<str>,
+        P: serde::Serialize,
+    {
+        self.0.notify(method, params).await
+    }
+
+    /// Create a new [`JsonRpcBatch`] to send several calls in one packet.
+    pub fn batch(&self) -> JsonRpcBatch {
+        self.0.batch()
+    }
+
+    /// Get the inner [`JsonRpcPeerState`] instance.
+    pub fn to_state(&self) -> JsonRpcPeerState {
+        self.0.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{channel::mpsc, executor::ThreadPool, poll};
+
+    use crate::JsonRpcError;
+
+    use super::*;
+
+    #[futures_test::test]
+    async fn test_peer() {
+        let pool = ThreadPool::new().unwrap();
+
+        let (a_sender, b_receiver) = mpsc::unbounded::<Vec<u8>>();
+        let (b_sender, a_receiver) = mpsc::unbounded::<Vec<u8>>();
+
+        let a = JsonRpcPeer::default().handle("add", |(x, y): (i32, i32)| async move { Ok(x + y) });
+
+        let b = JsonRpcPeer::default();
+
+        let b_state = b.to_state();
+
+        // calls back into the remote peer from a handler.
+        let b = b.handle("double_add", move |(x, y): (i32, i32)| {
+            let b_state = b_state.clone();
+
+            async move {
+                let sum: i32 = b_state.call("add", (x, y)).await?;
+
+                Ok(sum * 2)
+            }
+        });
+
+        let state = a.to_state();
+
+        pool.spawn_ok(async move {
+            _ = state.run(a_sender, a_receiver).await;
+        });
+
+        let state = b.to_state();
+
+        pool.spawn_ok(async move {
+            _ = state.run(b_sender, b_receiver).await;
+        });
+
+        let sum: i32 = b.call("add", (1, 2)).await.unwrap();
+
+        assert_eq!(sum, 3);
+
+        let sum: i32 = a.call("double_add", (1, 2)).await.unwrap();
+
+        assert_eq!(sum, 6);
+
+        let err = a.call::<_, _, i32>("not_found", ()).await.unwrap_err();
+
+        assert!(err.to_string().contains("Method not found"));
+
+        a.notify("add", (1, 2)).await.unwrap();
+
+        let mut batch = b.batch();
+
+        let first = batch.call::<_, _, i32>("add", (1, 1)).unwrap();
+        let second = batch.call::<_, _, i32>("add", (2, 2)).unwrap();
+
+        batch.send().await.unwrap();
+
+        assert_eq!(first.await.unwrap(), 2);
+        assert_eq!(second.await.unwrap(), 4);
+    }
+
+    #[futures_test::test]
+    async fn test_run_fail_pending() {
+        let pool = ThreadPool::new().unwrap();
+
+        // the receiver ends, or the sender fails.
+        for close_receiver in [true, false] {
+            let (sender, remote_receiver) = mpsc::unbounded::<Vec<u8>>();
+            let (remote_sender, receiver) = mpsc::unbounded::<Vec<u8>>();
+
+            let peer = JsonRpcPeer::default();
+
+            let state = peer.to_state();
+
+            if !close_receiver {
+                drop(remote_receiver);
+            }
+
+            let mut call = Box::pin(peer.call::<_, _, i32>("add", (1, 2)));
+
+            assert!(poll!(&mut call).is_pending());
+
+            let (result, done) = futures::channel::oneshot::channel();
+
+            pool.spawn_ok(async move {
+                _ = result.send(state.run(sender, receiver).await);
+            });
+
+            if close_receiver {
+                drop(remote_sender);
+            }
+
+            match call.await.unwrap_err() {
+                JsonRpcError::Transport(err) => {
+                    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset)
+                }
+                err => panic!("unexpected error: {}", err),
+            }
+
+            assert_eq!(done.await.unwrap().is_ok(), close_receiver);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    io::{self, Read, Write},
+    pin::Pin,
+    process::{Child, Command, Stdio},
+    task::{Context, Poll},
+    thread,
+};
+
+use futures::{
+    channel::mpsc::{channel, unbounded, Sender, UnboundedReceiver},
+    executor::block_on_stream,
+    stream::IntoAsyncRead,
+    AsyncRead, AsyncWrite, SinkExt, StreamExt, TryStreamExt,
+};
+use rasi::task::spawn_ok;
+
+use crate::{client::JsonRpcClient, framed::Framing, peer::JsonRpcPeer, server::JsonRpcServer};
+
+/// A builder to drive jsonrpc state machines over a framed byte stream,
+/// e.g, tcp, unix socket or the stdio of a child process.
+pub struct FramedJsonRpc<R, W> {
+    framing: Framing,
+    read: R,
+    write: W,
+    max_packet_len: usize,
+}
+
+impl<R, W> FramedJsonRpc<R, W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    /// Create a new builder to read packets from `read` and write packets to `write` with `framing`.
+    pub fn new(framing: Framing, read: R, write: W) -> Self {
+        Self {
+            framing,
+            read,
+            write,
+            max_packet_len: 4 * 1024 * 1024,
+        }
+    }
+
+    /// Set the max length of the received packets, the default value is 4MiB.
+    pub fn max_packet_len(mut self, len: usize) -> Self {
+        self.max_packet_len = len;
+        self
+    }
+
+    /// Spawn background tasks to drive `client`.
+    ///
+    /// The client is closed when the byte stream is broken.
+    pub fn spawn_client(self, client: &JsonRpcClient) {
+        let mut reader = self.framing.reader(self.read, self.max_packet_len);
+        let mut writer = self.framing.writer(self.write);
+
+        let background = client.to_state();
+
+        spawn_ok(async move {
+            while let Ok((_, packet)) = background.send().await {
+                if let Err(err) = writer.send(packet).await {
+                    log::error!(target: "FramedJsonRpc", "send jsonrpc packet, {}", err);
+                    break;
+                }
+            }
+
+            if let Err(err) = writer.close().await {
+                log::error!(target: "FramedJsonRpc", "close jsonrpc writer, {}", err);
+            }
+
+            background.close();
+        });
+
+        let background = client.to_state();
+
+        spawn_ok(async move {
+            while let Some(packet) = reader.next().await {
+                if let Err(err) = background.recv(packet).await {
+                    log::error!(target: "FramedJsonRpc", "recv jsonrpc packet, {}", err);
+                }
+            }
+
+            background.close();
+        });
+    }
+
+    /// Spawn background tasks to drive `server`, the requests are processed concurrently.
+    ///
+    /// The server is closed when the byte stream is broken.
+    pub fn spawn_server(self, server: &JsonRpcServer) {
+        let mut reader = self.framing.reader(self.read, self.max_packet_len);
+        let mut writer = self.framing.writer(self.write);
+
+        let background = server.to_state();
+
+        spawn_ok(async move {
+            while let Ok(packet) = background.send().await {
+                if let Err(err) = writer.send(packet).await {
+                    log::error!(target: "FramedJsonRpc", "send jsonrpc packet, {}", err);
+                    break;
+                }
+            }
+
+            if let Err(err) = writer.close().await {
+                log::error!(target: "FramedJsonRpc", "close jsonrpc writer, {}", err);
+            }
+
+            background.close();
+        });
+
+        let background = server.to_state();
+
+        spawn_ok(async move {
+            while let Some(packet) = reader.next().await {
+                let background = background.clone();
+
+                spawn_ok(async move {
+                    if let Err(err) = background.recv(packet).await {
+                        log::error!(target: "FramedJsonRpc", "recv jsonrpc packet, {}", err);
+                    }
+                });
+            }
+
+            background.close();
+        });
+    }
+
+    /// Spawn a background task to drive `peer`.
+    ///
+    /// The peer is closed when the byte stream is broken.
+    pub fn spawn_peer(self, peer: &JsonRpcPeer) {
+        let reader = self.framing.reader(self.read, self.max_packet_len);
+        let writer = self.framing.writer(self.write);
+
+        let background = peer.to_state();
+
+        spawn_ok(async move {
+            if let Err(err) = background.run(writer, reader).await {
+                log::error!(target: "FramedJsonRpc", "stop background task, {}", err);
+            }
+
+            background.close();
+        });
+    }
+}
+
+impl FramedJsonRpc<ChildStdout, ChildStdin> {
+    /// Spawn a child process with `command`, and create a builder over its stdin/stdout.
+    ///
+    /// The stdio pipes are pumped by two background threads,
+    /// which exit when the child process closes its stdio.
+    ///
+    /// Closing the returned [`ChildStdin`] closes the child's stdin.
+    pub fn spawn_command(framing: Framing, command: &mut Command) -> io::Result<(Self, Child)> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let mut stdout = child.stdout.take().expect("piped stdout");
+        let mut stdin = child.stdin.take().expect("piped stdin");
+
+        let (sender, receiver) = unbounded();
+
+        thread::spawn(move || {
+            let mut buf = vec![0; 4096];
+
+            loop {
+                let read = match stdout.read(&mut buf) {
+                    Ok(0) => return,
+                    Ok(read_size) => Ok(buf[..read_size].to_vec()),
+                    Err(err) => Err(err),
+                };
+
+                let is_err = read.is_err();
+
+                if sender.unbounded_send(read).is_err() || is_err {
+                    return;
+                }
+            }
+        });
+
+        let (sender, receiver_stdin) = channel::<Vec<u8>>(CHILD_STDIN_BUFFER);
+
+        thread::spawn(move || {
+            for buf in block_on_stream(receiver_stdin) {
+                if let Err(err) = stdin.write_all(&buf).and_then(|_| stdin.flush()) {
+                    log::error!(target: "FramedJsonRpc", "write child stdin, {}", err);
+                    return;
+                }
+            }
+        });
+
+        Ok((
+            Self::new(
+                framing,
+                ChildStdout(receiver.into_async_read()),
+                ChildStdin(Some(sender)),
+            ),
+            child,
+        ))
+    }
+}
+
+/// The async reader of a child process's stdout, see [`FramedJsonRpc::spawn_command`].
+pub struct ChildStdout(IntoAsyncRead<UnboundedReceiver<io::Result<Vec<u8>>>>);
+
+impl AsyncRead for ChildStdout {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+/// The max number of pending writes queued for the child's stdin writer thread.
+const CHILD_STDIN_BUFFER: usize = 16;
+
+/// The async writer of a child process's stdin, see [`FramedJsonRpc::spawn_command`].
+///
+/// Writes wait while the stdin writer thread is behind, and closing this writer
+/// closes the child's stdin, so the child reads EOF.
+pub struct ChildStdin(Option<Sender<Vec<u8>>>);
+
+impl ChildStdin {
+    fn broken_pipe() -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "Child stdin is closed")
+    }
+}
+
+impl AsyncWrite for ChildStdin {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let sender = self.0.as_mut().ok_or_else(Self::broken_pipe)?;
+
+        futures::ready!(sender.poll_ready(cx)).map_err(|_| Self::broken_pipe())?;
+
+        sender
+            .start_send(buf.to_vec())
+            .map_err(|_| Self::broken_pipe())?;
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Dropping the only sender ends the writer thread, which drops the child's stdin.
+        self.0.take();
+
+        Poll::Ready(Ok(()))
+    }
+}
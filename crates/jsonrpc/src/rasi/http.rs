@@ -1,22 +1,46 @@
-use std::{any::Any, io, net::ToSocketAddrs, path::Path, str::from_utf8, time::Duration};
+use std::{
+    any::Any,
+    collections::{HashSet, VecDeque},
+    io,
+    net::ToSocketAddrs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use futures::TryStreamExt;
+use futures::{stream, AsyncRead, AsyncWrite, Stream, TryStreamExt};
 use futures_http::{
     body::BodyReader,
     client::rasio::{HttpClient, HttpClientOptions, HttpClientOptionsBuilder},
+    fluent::{ClientError, ClientResult, ResponseExt},
+    server::HttpServer,
     types::{
+        header::{ALLOW, AUTHORIZATION, CONTENT_TYPE},
         request::{Builder as RequestBuilder, Parts},
-        Error as HttpError, HeaderName, HeaderValue, Request, StatusCode, Uri,
+        Error as HttpError, HeaderMap, HeaderName, HeaderValue, Method, Request, Response,
+        StatusCode, Uri,
     },
+    writer::HttpWriter,
+};
+use rand::Rng;
+use rasi::{
+    task::spawn_ok,
+    timer::{sleep, TimeoutExt},
 };
-use rasi::{task::spawn_ok, timer::TimeoutExt};
-use serde_json::json;
+use serde::Deserialize;
+use serde_json::Value;
 
+use super::packet_ids;
 use crate::{
     client::{JsonRpcClient, JsonRpcClientState},
-    Error, ErrorCode,
+    server::{JsonRpcServer, JsonRpcServerState},
+    Error, Id, Packet,
 };
 
+type IdempotentFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+type RetryableError = Arc<dyn Fn(&Error<String, Value>) -> bool + Send + Sync>;
+
 /// A builder to create a http jsonrpc client.
 pub struct HttpJsonRpcClient {
     max_body_size: usize,
@@ -24,6 +48,16 @@ pub struct HttpJsonRpcClient {
     timeout: Duration,
     builder: RequestBuilder,
     send_ops: HttpClientOptionsBuilder,
+    endpoints: Vec<Result<Uri, HttpError>>,
+    max_failures: usize,
+    cooldown: Duration,
+    max_retries: usize,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    idempotent: Option<IdempotentFilter>,
+    retryable_error: Option<RetryableError>,
+    concurrency: usize,
+    batch_window: Option<(Duration, usize)>,
 }
 
 impl HttpJsonRpcClient {
@@ -37,8 +71,21 @@ impl HttpJsonRpcClient {
             max_body_size: 1024 * 1024,
             send_cached_len: 10,
             timeout: Duration::from_secs(5),
-            builder: RequestBuilder::new().method("POST").uri(uri),
+            builder: RequestBuilder::new()
+                .method("POST")
+                .uri(uri)
+                .header(CONTENT_TYPE, "application/json"),
             send_ops: HttpClientOptions::new(),
+            endpoints: vec![],
+            max_failures: 3,
+            cooldown: Duration::from_secs(30),
+            max_retries: 3,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            idempotent: None,
+            retryable_error: None,
+            concurrency: 10,
+            batch_window: None,
         }
     }
 
@@ -97,14 +144,127 @@ impl HttpJsonRpcClient {
         self
     }
 
+    /// Send http requests over the unix domain socket at `path`, e.g. to talk to a local daemon.
+    #[cfg(unix)]
+    pub fn with_unix_socket<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.send_ops = self.send_ops.with_unix_socket(path);
+        self
+    }
+
+    /// Appends a failover endpoint.
+    ///
+    /// The requests are sent to the first healthy endpoint in order, starting with the uri passed to [`new`](Self::new),
+    /// and the retries rotate to the next healthy endpoint.
+    /// The headers and the connection options, e.g, [`redirect`](Self::redirect), apply to all endpoints.
+    pub fn endpoint<T>(mut self, uri: T) -> Self
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<HttpError>,
+    {
+        self.endpoints.push(Uri::try_from(uri).map_err(Into::into));
+        self
+    }
+
+    /// Set the health policy of endpoints, the default value is 3 failures and 30s.
+    ///
+    /// An endpoint fails `max_failures` times in a row is taken out of rotation for the `cooldown` duration,
+    /// a transport error, a timeout, a `5xx`, `408` or `429` response is counted as a failure.
+    pub fn failover(mut self, max_failures: usize, cooldown: Duration) -> Self {
+        self.max_failures = max_failures.max(1);
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Mark the methods accepted by `filter` as idempotent, only the idempotent calls are retried.
+    ///
+    /// A batch is retried only if all of its calls are idempotent.
+    pub fn idempotent<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.idempotent = Some(Arc::new(filter));
+        self
+    }
+
+    /// Set the max retry times of a idempotent call, the default value is 3.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the exponential backoff between retries, the default value is from 100ms to 5s.
+    ///
+    /// The delay doubles after every retry up to `max`, the actual delay is randomized in `[delay/2, delay]`.
+    pub fn retry_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Retry the idempotent calls replied with the errors accepted by `hook`.
+    ///
+    /// By default, the error objects replied by the server are returned to the callers directly.
+    pub fn retryable_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Error<String, Value>) -> bool + Send + Sync + 'static,
+    {
+        self.retryable_error = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set the max number of in-flight http requests, the default value is 10.
+    ///
+    /// The concurrent requests may be processed by the server out of order,
+    /// sets it to 1 to send requests one by one.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Merge the packets queued within `window` into one jsonrpc batch, at most `max_packets` packets are merged.
+    ///
+    /// The auto-batching is disabled by default.
+    pub fn batch_window(mut self, window: Duration, max_packets: usize) -> Self {
+        self.batch_window = Some((window, max_packets.max(1)));
+        self
+    }
+
     /// Consume builder and create a new `JsonRpcClient` instance.
     pub fn create(self) -> io::Result<JsonRpcClient> {
+        let request = self
+            .builder
+            .body(())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        let (parts, _) = request.into_parts();
+
+        let mut uris = vec![parts.uri.clone()];
+
+        for uri in self.endpoints {
+            uris.push(uri.map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?);
+        }
+
+        let driver = HttpDriver {
+            max_body_size: self.max_body_size,
+            timeout: self.timeout,
+            max_retries: self.max_retries,
+            min_backoff: self.min_backoff,
+            max_backoff: self.max_backoff,
+            idempotent: self.idempotent,
+            retryable_error: self.retryable_error,
+            concurrency: self.concurrency,
+            batch_window: self.batch_window,
+            ops: self.send_ops.try_into()?,
+            parts,
+            endpoints: Endpoints::new(uris, self.max_failures, self.cooldown),
+        };
+
         let client = JsonRpcClient::new(self.send_cached_len);
 
         let background = client.to_state();
 
         spawn_ok(async move {
-            if let Err(err) = self.run_loop(background).await {
+            if let Err(err) = driver.run_loop(background).await {
                 log::error!(target: "HttpJsonRpcClient", "stop background task, {}",err);
             } else {
                 log::info!(target: "HttpJsonRpcClient", "stop background task");
@@ -113,103 +273,616 @@ impl HttpJsonRpcClient {
 
         Ok(client)
     }
+}
+
+#[derive(Default)]
+struct Health {
+    failures: usize,
+    unhealthy_until: Option<Instant>,
+}
+
+/// The endpoints of a http jsonrpc client and their health states.
+struct Endpoints {
+    uris: Vec<Uri>,
+    max_failures: usize,
+    cooldown: Duration,
+    health: Mutex<Vec<Health>>,
+}
 
+impl Endpoints {
+    fn new(uris: Vec<Uri>, max_failures: usize, cooldown: Duration) -> Self {
+        Self {
+            health: Mutex::new(uris.iter().map(|_| Health::default()).collect()),
+            uris,
+            max_failures,
+            cooldown,
+        }
+    }
+
+    /// Returns the first endpoint in rotation from `start`,
+    /// or the endpoint back to rotation soonest if all endpoints are out of rotation.
+    fn select(&self, start: usize) -> usize {
+        let health = self.health.lock().unwrap();
+
+        let now = Instant::now();
+
+        let len = self.uris.len();
+
+        (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|index| {
+                health[*index]
+                    .unhealthy_until
+                    .map(|until| until <= now)
+                    .unwrap_or(true)
+            })
+            .or_else(|| (0..len).min_by_key(|index| health[*index].unhealthy_until))
+            .unwrap_or(0)
+    }
+
+    /// Updates the health state of endpoint `index` with the result of a request.
+    fn report(&self, index: usize, ok: bool) {
+        let mut health = self.health.lock().unwrap();
+
+        let health = &mut health[index];
+
+        if ok {
+            *health = Health::default();
+            return;
+        }
+
+        health.failures += 1;
+
+        if health.failures >= self.max_failures {
+            health.unhealthy_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// The background driver of a http jsonrpc client.
+struct HttpDriver {
+    max_body_size: usize,
+    timeout: Duration,
+    max_retries: usize,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    idempotent: Option<IdempotentFilter>,
+    retryable_error: Option<RetryableError>,
+    concurrency: usize,
+    batch_window: Option<(Duration, usize)>,
+    ops: HttpClientOptions,
+    parts: Parts,
+    endpoints: Endpoints,
+}
+
+impl HttpDriver {
     async fn run_loop(self, background: JsonRpcClientState) -> std::io::Result<()> {
-        let request = self
-            .builder
-            .body(())
-            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let this = &self;
 
-        let (parts, _) = request.into_parts();
+        let background = &background;
+
+        stream::unfold(VecDeque::new(), |mut queued| async move {
+            loop {
+                if let Some(packet) = queued.pop_front() {
+                    return Some((Ok(packet), queued));
+                }
+
+                match this.next_packets(background).await {
+                    Ok(packets) => queued.extend(packets),
+                    Err(err) => return Some((Err(err), queued)),
+                }
+            }
+        })
+        .try_for_each_concurrent(self.concurrency, |packet| async move {
+            this.send_packet(background, packet).await;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns the next packets to send, the packets queued within the batch window are merged into one batch.
+    async fn next_packets(&self, background: &JsonRpcClientState) -> io::Result<Vec<Vec<u8>>> {
+        let (_, packet) = background.send().await?;
+
+        let Some((window, max_packets)) = self.batch_window else {
+            return Ok(vec![packet]);
+        };
+
+        let mut packets = vec![packet];
+
+        let deadline = Instant::now() + window;
+
+        while packets.len() < max_packets {
+            let now = Instant::now();
+
+            if now >= deadline {
+                break;
+            }
+
+            match background.send().timeout(deadline - now).await {
+                Some(Ok((_, packet))) => packets.push(packet),
+                // the error is returned by the next call.
+                Some(Err(_)) | None => break,
+            }
+        }
+
+        Ok(merge_packets(packets))
+    }
 
-        let ops: HttpClientOptions = self.send_ops.try_into()?;
+    /// Sends `packet` and delivers the responses to `background`,
+    /// the idempotent calls are retried on the retryable failures.
+    async fn send_packet(&self, background: &JsonRpcClientState, mut packet: Vec<u8>) {
+        let idempotent = self.is_idempotent(&packet);
+
+        let mut endpoint = self.endpoints.select(0);
+
+        let mut backoff = self.min_backoff;
+
+        let mut attempt = 0;
 
         loop {
-            let (id, packet) = background.send().await?;
+            let can_retry = idempotent && attempt < self.max_retries;
 
-            log::trace!("send jsonrpc: {}", from_utf8(&packet).unwrap());
+            log::trace!(
+                "send jsonrpc to {}: {}",
+                self.endpoints.uris[endpoint],
+                String::from_utf8_lossy(&packet)
+            );
 
-            let call = Self::send_request(&ops, self.max_body_size, parts.clone(), packet)
+            let call = self
+                .send_request(endpoint, packet.clone())
                 .timeout(self.timeout)
                 .await;
 
-            let buf = match call {
-                Some(Ok(buf)) => buf,
-                Some(Err(err)) => {
-                    _ = background
-                        .recv(
-                            json!({
-                            "id":id,"jsonrpc":"2.0","error": Error {
-                                         code: ErrorCode::InternalError,
-                                         message: err.to_string(),
-                                         data: None::<()>
-                                      }
-                             })
-                            .to_string(),
-                        )
-                        .await;
-
-                    continue;
-                }
-                None => {
-                    _ = background
-                        .recv(
-                            json!({
-                            "id":id,"jsonrpc":"2.0","error": Error {
-                                         code: ErrorCode::InternalError,
-                                         message: "Timeout",
-                                         data: None::<()>
-                                      }
-                             })
-                            .to_string(),
-                        )
-                        .await;
-
-                    continue;
+            let err = match call {
+                Some(Ok(buf)) => {
+                    self.endpoints.report(endpoint, true);
+
+                    match self.handle_recv(background, &packet, buf, can_retry).await {
+                        Some(retry) => {
+                            log::warn!(target: "HttpJsonRpcClient", "retry jsonrpc calls replied with retryable errors");
+                            packet = retry;
+                            None
+                        }
+                        None => return,
+                    }
                 }
+                Some(Err(err)) => Some(err),
+                None => Some(ClientError::Timeout(self.timeout)),
             };
 
-            Self::handle_recv(&background, buf).await;
+            if let Some(err) = err {
+                let retryable = is_retryable(&err);
+
+                if retryable {
+                    self.endpoints.report(endpoint, false);
+                }
+
+                if !(can_retry && retryable) {
+                    Self::fail(background, &packet, err).await;
+                    return;
+                }
+
+                log::warn!(target: "HttpJsonRpcClient", "retry jsonrpc calls, {}", err);
+            }
+
+            sleep(jitter(backoff)).await;
+
+            backoff = (backoff * 2).min(self.max_backoff);
+            attempt += 1;
+            endpoint = self.endpoints.select(endpoint + 1);
+        }
+    }
+
+    /// Returns true if all calls in `packet` are idempotent.
+    fn is_idempotent(&self, packet: &[u8]) -> bool {
+        let Some(idempotent) = &self.idempotent else {
+            return false;
+        };
+
+        let Ok(packet) = serde_json::from_slice::<Packet<Value>>(packet) else {
+            return false;
+        };
+
+        packet.into_vec().iter().all(|object| {
+            object
+                .get("method")
+                .and_then(Value::as_str)
+                .map(|method| idempotent(method))
+                .unwrap_or(false)
+        })
+    }
+
+    async fn fail(background: &JsonRpcClientState, packet: &[u8], err: ClientError) {
+        let ids = packet_ids(packet);
+
+        let err: io::Error = err.into();
+
+        if ids.is_empty() {
+            log::error!(target: "HttpJsonRpcClient", "send notification, {}", err);
+            return;
+        }
+
+        background.fail_calls(ids, err.kind(), err).await;
+    }
+
+    /// Delivers the responses to `background`,
+    /// returns the packet to retry if some calls of `request` are replied with retryable errors.
+    async fn handle_recv(
+        &self,
+        background: &JsonRpcClientState,
+        request: &[u8],
+        response: Vec<u8>,
+        can_retry: bool,
+    ) -> Option<Vec<u8>> {
+        // the response of notifications is empty.
+        if response.is_empty() {
+            return None;
+        }
+
+        log::trace!("recv jsonrpc: {}", String::from_utf8_lossy(&response));
+
+        let retryable_error = match &self.retryable_error {
+            Some(hook) if can_retry => hook,
+            _ => {
+                Self::deliver(background, Ok(response)).await;
+                return None;
+            }
+        };
+
+        let (Ok(responses), Ok(requests)) = (
+            serde_json::from_slice::<Packet<Value>>(&response),
+            serde_json::from_slice::<Packet<Value>>(request),
+        ) else {
+            Self::deliver(background, Ok(response)).await;
+            return None;
+        };
+
+        let mut retry_ids = HashSet::new();
+
+        let mut objects = vec![];
+
+        for object in responses.into_vec() {
+            let retryable = object
+                .get("error")
+                .and_then(|error| Error::<String, Value>::deserialize(error).ok())
+                .map(|error| retryable_error(&error))
+                .unwrap_or(false);
+
+            match object.get("id").and_then(|id| Id::deserialize(id).ok()) {
+                Some(id) if retryable && id != Id::Null => {
+                    retry_ids.insert(id);
+                }
+                _ => objects.push(object),
+            }
+        }
+
+        let is_batch = matches!(requests, Packet::Batch(_));
+
+        let mut retry = requests
+            .into_vec()
+            .into_iter()
+            .filter(|object| {
+                object
+                    .get("id")
+                    .and_then(|id| Id::deserialize(id).ok())
+                    .map(|id| retry_ids.contains(&id))
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+
+        // the retryable responses are unmatched to the requests, delivers them as usual.
+        if retry.len() != retry_ids.len() {
+            Self::deliver(background, Ok(response)).await;
+            return None;
+        }
+
+        if !objects.is_empty() {
+            Self::deliver(background, Err(objects)).await;
+        }
+
+        if retry.is_empty() {
+            return None;
         }
+
+        let retry = if is_batch {
+            Value::Array(retry)
+        } else {
+            retry.remove(0)
+        };
+
+        Some(serde_json::to_vec(&retry).expect("serialize json value"))
     }
 
-    async fn handle_recv<P: AsRef<[u8]>>(client: &JsonRpcClientState, packet: P) {
-        log::trace!("recv jsonrpc: {}", from_utf8(packet.as_ref()).unwrap());
+    /// Delivers a response packet or the decoded objects to `background`.
+    async fn deliver(background: &JsonRpcClientState, response: Result<Vec<u8>, Vec<Value>>) {
+        let result = match response {
+            Ok(packet) => background.recv(packet).await,
+            Err(objects) => background.recv_objects(objects).await,
+        };
 
-        if let Err(err) = client.recv(packet).await {
+        if let Err(err) = result {
             log::error!("handle http jsonrpc recv with error: {}", err);
         }
     }
 
-    async fn send_request(
-        ops: &HttpClientOptions,
-        max_body_size: usize,
-        parts: Parts,
-        packet: Vec<u8>,
-    ) -> io::Result<Vec<u8>> {
-        let request = Request::from_parts(parts, BodyReader::from(packet));
+    async fn send_request(&self, endpoint: usize, packet: Vec<u8>) -> ClientResult<Vec<u8>> {
+        let mut parts = self.parts.clone();
+
+        parts.uri = self.endpoints.uris[endpoint].clone();
 
-        let resp = request.send(ops).await?;
+        let buf = Request::from_parts(parts, BodyReader::from(packet))
+            .send(&self.ops)
+            .await?
+            .error_for_status()
+            .await?
+            .bytes_limited(self.max_body_size)
+            .await?;
 
-        if StatusCode::OK != resp.status() {
-            return Err(io::Error::new(io::ErrorKind::Other, resp.status().as_str()));
+        Ok(buf)
+    }
+}
+
+/// Merges jsonrpc `packets` into one batch, the packets failed to parse are returned after the batch as is.
+fn merge_packets(packets: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    if packets.len() == 1 {
+        return packets;
+    }
+
+    let mut objects = vec![];
+
+    // the packets that can't be merged are sent on their own, so their calls are still replied.
+    let mut unmerged = vec![];
+
+    for packet in packets {
+        match serde_json::from_slice::<Packet<Value>>(&packet) {
+            Ok(packet) => objects.append(&mut packet.into_vec()),
+            Err(err) => {
+                log::warn!(target: "HttpJsonRpcClient", "merge jsonrpc packet, {}", err);
+                unmerged.push(packet);
+            }
         }
+    }
 
-        let (_, mut body) = resp.into_parts();
+    if !objects.is_empty() {
+        unmerged.insert(
+            0,
+            serde_json::to_vec(&Value::Array(objects)).expect("serialize json value"),
+        );
+    }
 
-        let mut buf = vec![];
+    unmerged
+}
 
-        while let Some(mut chunk) = body.try_next().await? {
-            buf.append(&mut chunk);
+/// Returns true if the request failed with `err` may succeed on retry, e.g, on another endpoint.
+fn is_retryable(err: &ClientError) -> bool {
+    match err {
+        ClientError::Io(_) | ClientError::Timeout(_) => true,
+        ClientError::Status { status, .. } => {
+            status.is_server_error()
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+/// Returns a random delay in `[backoff/2, backoff]`.
+fn jitter(backoff: Duration) -> Duration {
+    backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
 
-            if buf.len() > max_body_size {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Body length too long: {}", max_body_size),
-                ));
+type Authorizer = Arc<dyn Fn(&HeaderMap) -> bool + Send + Sync>;
+
+/// A http adapter to serve a [`JsonRpcServer`] over `POST` requests,
+/// the server-side counterpart of [`HttpJsonRpcClient`].
+///
+/// The `Content-Type` of requests and responses is the media type of the server's
+/// [`Codec`](crate::codec::Codec), e.g, `application/json`.
+#[derive(Clone)]
+pub struct HttpJsonRpcServer {
+    path: String,
+    max_body_size: usize,
+    server: JsonRpcServerState,
+    authorizer: Option<Authorizer>,
+}
+
+impl HttpJsonRpcServer {
+    /// Create a new http adapter of `server`, which serves on the root path `/`.
+    pub fn new(server: &JsonRpcServer) -> Self {
+        Self {
+            path: "/".to_owned(),
+            max_body_size: 1024 * 1024,
+            server: server.to_state(),
+            authorizer: None,
+        }
+    }
+
+    /// Set the request path to serve, the other paths are replied with `404 Not Found`.
+    pub fn path<P: Into<String>>(mut self, path: P) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set the max size of request body, the default value is 1MiB.
+    ///
+    /// The larger requests are replied with `413 Payload Too Large`.
+    pub fn max_body_size(mut self, len: usize) -> Self {
+        self.max_body_size = len;
+        self
+    }
+
+    /// Authorize requests by their headers, the unauthorized requests
+    /// are replied with `401 Unauthorized`.
+    pub fn auth<F>(mut self, authorizer: F) -> Self
+    where
+        F: Fn(&HeaderMap) -> bool + Send + Sync + 'static,
+    {
+        self.authorizer = Some(Arc::new(authorizer));
+        self
+    }
+
+    /// Only accept requests with a `Authorization: Bearer <token>` header.
+    pub fn bearer_auth<T: std::fmt::Display>(self, token: T) -> Self {
+        let expected = format!("Bearer {}", token);
+
+        self.auth(move |headers| {
+            headers
+                .get(AUTHORIZATION)
+                .map(|value| value.as_bytes() == expected.as_bytes())
+                .unwrap_or(false)
+        })
+    }
+
+    /// Accepts requests from `server` and replies them in background tasks,
+    /// returns an error if the `server` is shutdown.
+    pub async fn serve<I, S, E>(self, mut server: HttpServer<I>) -> io::Result<()>
+    where
+        I: Stream<Item = Result<S, E>> + Unpin,
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        E: std::error::Error,
+    {
+        loop {
+            let (request, mut write) = server.accept().await?;
+
+            let this = self.clone();
+
+            spawn_ok(async move {
+                let response = this.handle(request).await;
+
+                if let Err(err) = write.write_response(response).await {
+                    log::error!(target: "HttpJsonRpcServer", "write response, {}", err);
+                }
+            });
+        }
+    }
+
+    /// Processes a http `request` and returns the response to reply,
+    /// which is useful to mount this adapter on a custom http router.
+    pub async fn handle(&self, request: Request<BodyReader>) -> Response<BodyReader> {
+        if request.uri().path() != self.path {
+            return status_response(StatusCode::NOT_FOUND);
+        }
+
+        if request.method() != Method::POST {
+            let mut response = status_response(StatusCode::METHOD_NOT_ALLOWED);
+
+            response
+                .headers_mut()
+                .insert(ALLOW, HeaderValue::from_static("POST"));
+
+            return response;
+        }
+
+        if let Some(authorizer) = &self.authorizer {
+            if !authorizer(request.headers()) {
+                return status_response(StatusCode::UNAUTHORIZED);
             }
         }
 
-        Ok(buf)
+        let media_type = self.server.media_type();
+
+        if !has_media_type(request.headers(), media_type) {
+            return status_response(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+
+        let packet = match read_body(request.into_body(), self.max_body_size).await {
+            Ok(Some(packet)) => packet,
+            Ok(None) => return status_response(StatusCode::PAYLOAD_TOO_LARGE),
+            Err(err) => {
+                log::error!(target: "HttpJsonRpcServer", "read request body, {}", err);
+                return status_response(StatusCode::BAD_REQUEST);
+            }
+        };
+
+        match self.server.dispatch(packet).await {
+            Ok(Some(packet)) => {
+                let mut response = Response::new(BodyReader::from(packet));
+
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(media_type));
+
+                response
+            }
+            // the packet only contains notifications.
+            Ok(None) => status_response(StatusCode::NO_CONTENT),
+            Err(err) => {
+                log::error!(target: "HttpJsonRpcServer", "dispatch jsonrpc request, {}", err);
+                status_response(StatusCode::SERVICE_UNAVAILABLE)
+            }
+        }
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<BodyReader> {
+    let mut response = Response::new(BodyReader::empty());
+
+    *response.status_mut() = status;
+
+    response
+}
+
+/// Returns true if the `Content-Type` is `media_type`, the parameters e.g, `charset` are ignored.
+fn has_media_type(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().eq_ignore_ascii_case(media_type))
+        .unwrap_or(false)
+}
+
+/// Read the whole `body`, returns `None` if the body is larger than `limit`.
+async fn read_body(mut body: BodyReader, limit: usize) -> io::Result<Option<Vec<u8>>> {
+    if body.len().map(|len| len > limit).unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let mut buf = vec![];
+
+    while let Some(mut chunk) = body.try_next().await? {
+        buf.append(&mut chunk);
+
+        if buf.len() > limit {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_packets() {
+        let packets = vec![
+            json!({"id":0,"jsonrpc":"2.0","method":"echo","params":[1]})
+                .to_string()
+                .into_bytes(),
+            b"{".to_vec(),
+            json!([{"id":1,"jsonrpc":"2.0","method":"echo","params":[2]}])
+                .to_string()
+                .into_bytes(),
+        ];
+
+        let merged = merge_packets(packets);
+
+        assert_eq!(merged.len(), 2);
+
+        assert_eq!(
+            serde_json::from_slice::<Value>(&merged[0]).unwrap(),
+            json!([
+                {"id":0,"jsonrpc":"2.0","method":"echo","params":[1]},
+                {"id":1,"jsonrpc":"2.0","method":"echo","params":[2]}
+            ])
+        );
+
+        // the unparsable packet is sent on its own.
+        assert_eq!(merged[1], b"{");
     }
 }
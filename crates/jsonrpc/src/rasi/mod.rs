@@ -1 +1,20 @@
+pub mod framed;
 pub mod http;
+pub mod ws;
+
+use serde_json::Value;
+
+use crate::{Id, Packet};
+
+/// Returns the ids of the calls or responses in `packet`, notifications have no id.
+pub(crate) fn packet_ids<P: AsRef<[u8]>>(packet: P) -> Vec<Id> {
+    let Ok(packet) = serde_json::from_slice::<Packet<Value>>(packet.as_ref()) else {
+        return vec![];
+    };
+
+    packet
+        .into_vec()
+        .into_iter()
+        .filter_map(|mut object| serde_json::from_value(object.get_mut("id")?.take()).ok())
+        .collect()
+}
@@ -0,0 +1,262 @@
+use std::{io, net::ToSocketAddrs, path::Path, pin::pin, time::Duration};
+
+use async_tungstenite::{
+    client_async,
+    tungstenite::{client::IntoClientRequest, Message},
+    WebSocketStream,
+};
+use futures::{
+    future::{select, Either},
+    AsyncRead, AsyncWrite, StreamExt,
+};
+use futures_http::{
+    client::rasio::{HttpClientOptions, HttpClientOptionsBuilder},
+    types::{
+        request::{Builder as RequestBuilder, Parts},
+        Error as HttpError, HeaderName, HeaderValue, Uri,
+    },
+};
+use rasi::{
+    task::spawn_ok,
+    timer::{sleep, TimeoutExt},
+};
+
+use crate::client::{JsonRpcClient, JsonRpcClientState};
+
+/// A builder to create a websocket jsonrpc client.
+///
+/// The background task reconnects to the server with exponential backoff when the connection drops,
+/// the pending calls are failed with a [`ConnectionReset`](io::ErrorKind::ConnectionReset) error,
+/// and the calls issued while reconnecting are sent over the new connection.
+pub struct WsJsonRpcClient {
+    send_cached_len: usize,
+    timeout: Duration,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    builder: RequestBuilder,
+    send_ops: HttpClientOptionsBuilder,
+}
+
+impl WsJsonRpcClient {
+    /// Create new websocket rpc client builder with server uri, e.g, `ws://` or `wss://`.
+    pub fn new<T>(uri: T) -> Self
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<HttpError>,
+    {
+        WsJsonRpcClient {
+            send_cached_len: 10,
+            timeout: Duration::from_secs(5),
+            min_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            builder: RequestBuilder::new().method("GET").uri(uri),
+            send_ops: HttpClientOptions::new(),
+        }
+    }
+
+    /// Appends a http header to the websocket handshake request.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        self.builder = self.builder.header(key, value);
+
+        self
+    }
+
+    /// Rewrite the server's host:port fields and connect to the specified `raddrs`.
+    pub fn redirect<R: ToSocketAddrs>(mut self, raddrs: R) -> Self {
+        self.send_ops = self.send_ops.redirect(raddrs);
+
+        self
+    }
+
+    /// Set remote server's server name, which is used to verify the tls certificate.
+    pub fn with_server_name(mut self, server_name: &str) -> Self {
+        self.send_ops = self.send_ops.with_server_name(server_name);
+
+        self
+    }
+
+    /// Set the server verification ca file, this is useful for self signed server.
+    pub fn with_ca_file<P: AsRef<Path>>(mut self, ca_file: P) -> Self {
+        self.send_ops = self.send_ops.with_ca_file(ca_file);
+        self
+    }
+
+    /// Set the timeout duration of connecting to the server, including the websocket handshake.
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.timeout = duration;
+        self
+    }
+
+    /// Configures the use of Server Name Indication (SNI) when connecting.
+    /// Defaults to true.
+    pub fn set_use_server_name_indication(mut self, value: bool) -> Self {
+        self.send_ops = self.send_ops.set_use_server_name_indication(value);
+        self
+    }
+
+    /// Set the delay range of reconnecting, the delay starts from `min` and doubles
+    /// after each failed attempt up to `max`. Defaults to 500ms..30s.
+    pub fn reconnect_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Consume builder and create a new `JsonRpcClient` instance.
+    pub fn create(self) -> io::Result<JsonRpcClient> {
+        let request = self
+            .builder
+            .body(())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        let (parts, _) = request.into_parts();
+
+        let ops: HttpClientOptions = self.send_ops.try_into()?;
+
+        let client = JsonRpcClient::new(self.send_cached_len);
+
+        let background = client.to_state();
+
+        let driver = WsDriver {
+            timeout: self.timeout,
+            min_backoff: self.min_backoff,
+            max_backoff: self.max_backoff,
+            parts,
+            ops,
+        };
+
+        spawn_ok(async move {
+            if let Err(err) = driver.run_loop(background).await {
+                log::error!(target: "WsJsonRpcClient", "stop background task, {}",err);
+            } else {
+                log::info!(target: "WsJsonRpcClient", "stop background task");
+            }
+        });
+
+        Ok(client)
+    }
+}
+
+struct WsDriver {
+    timeout: Duration,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    parts: Parts,
+    ops: HttpClientOptions,
+}
+
+impl WsDriver {
+    async fn run_loop(self, background: JsonRpcClientState) -> io::Result<()> {
+        let mut backoff = self.min_backoff;
+
+        loop {
+            let connect = self.connect().timeout(self.timeout).await;
+
+            match connect {
+                Some(Ok(stream)) => {
+                    backoff = self.min_backoff;
+
+                    self.run_connection(&background, stream).await?;
+                }
+                Some(Err(err)) => {
+                    log::error!(target: "WsJsonRpcClient", "connect to {}, {}", self.parts.uri, err);
+                }
+                None => {
+                    log::error!(target: "WsJsonRpcClient", "connect to {}, Timeout", self.parts.uri);
+                }
+            }
+
+            if background.is_closed() {
+                return Ok(());
+            }
+
+            log::trace!(target: "WsJsonRpcClient", "reconnect after {:?}", backoff);
+
+            sleep(backoff).await;
+
+            if background.is_closed() {
+                return Ok(());
+            }
+
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+    }
+
+    async fn connect(&self) -> io::Result<WebSocketStream<impl AsyncRead + AsyncWrite + Unpin>> {
+        let mut request = self
+            .parts
+            .uri
+            .clone()
+            .into_client_request()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        request.headers_mut().extend(self.parts.headers.clone());
+
+        let transport = self.ops.connect(&self.parts.uri).await?;
+
+        let (stream, _) = client_async(request, transport)
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::ConnectionRefused, err))?;
+
+        Ok(stream)
+    }
+
+    /// Drives `background` over the websocket `stream` until the connection drops,
+    /// or returns an error if `background` is closed.
+    async fn run_connection<S>(
+        &self,
+        background: &JsonRpcClientState,
+        mut stream: WebSocketStream<S>,
+    ) -> io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let message = loop {
+            match select(pin!(background.send()), stream.next()).await {
+                Either::Left((Ok((_, packet)), _)) => {
+                    let packet = String::from_utf8_lossy(&packet).into_owned();
+
+                    log::trace!("send jsonrpc: {}", packet);
+
+                    if let Err(err) = stream.send(Message::text(packet)).await {
+                        break err.to_string();
+                    }
+                }
+                Either::Left((Err(err), _)) => {
+                    _ = stream.close(None).await;
+                    return Err(err);
+                }
+                Either::Right((Some(Ok(message)), _)) => {
+                    let packet = match message {
+                        Message::Text(text) => text.as_bytes().to_vec(),
+                        Message::Binary(buf) => buf.to_vec(),
+                        Message::Close(_) => break "Connection closed by server".to_owned(),
+                        _ => continue,
+                    };
+
+                    log::trace!("recv jsonrpc: {}", String::from_utf8_lossy(&packet));
+
+                    if let Err(err) = background.recv(packet).await {
+                        log::error!("handle websocket jsonrpc recv with error: {}", err);
+                    }
+                }
+                Either::Right((Some(Err(err)), _)) => break err.to_string(),
+                Either::Right((None, _)) => break "Connection closed by server".to_owned(),
+            }
+        };
+
+        log::error!(target: "WsJsonRpcClient", "disconnect from {}, {}", self.parts.uri, message);
+
+        background
+            .fail_pending(io::ErrorKind::ConnectionReset, message)
+            .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,678 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use futures::{
+    future::{join_all, BoxFuture},
+    lock::Mutex,
+    Future, FutureExt,
+};
+
+use futures_map::KeyWaitMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    codec::{Codec, JsonCodec},
+    Error, ErrorCode, Id, Request, Response, Version,
+};
+
+/// The result type returned by jsonrpc method handlers.
+pub type HandlerResult<R> = Result<R, Error<String, Value>>;
+
+type Handler = Arc<dyn Fn(Value) -> BoxFuture<'static, HandlerResult<Value>> + Send + Sync>;
+
+type ServerResponse = Response<String, Value, Value>;
+
+/// Create an error response, the `id` is null if it can't be detected from a invalid request.
+fn error_response<M: ToString>(id: Id, code: ErrorCode, message: M) -> ServerResponse {
+    Response {
+        id,
+        jsonrpc: Version,
+        result: None,
+        error: Some(Error {
+            code,
+            message: message.to_string(),
+            data: None,
+        }),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+enum JsonRpcServerEvent {
+    Forward,
+}
+
+struct RawJsonRpcServerState {
+    is_closed: AtomicBool,
+    handlers: RwLock<HashMap<String, Handler>>,
+    /// The descriptions of methods, which are used to generate the OpenRPC document.
+    #[cfg(feature = "openrpc")]
+    descriptions: RwLock<HashMap<String, crate::openrpc::Method>>,
+    send_queue: Mutex<VecDeque<Vec<u8>>>,
+    wait_map: KeyWaitMap<JsonRpcServerEvent, ()>,
+    codec: Arc<dyn Codec>,
+}
+
+/// The jsonrpc server without [`Drop`] support.
+///
+/// Like [`JsonRpcClientState`](crate::client::JsonRpcClientState), the server doesn't own any transport,
+/// the caller should pump packets received from peer into [`recv`](Self::recv)
+/// and write packets returned by [`send`](Self::send) to peer.
+#[derive(Clone)]
+pub struct JsonRpcServerState(Arc<RawJsonRpcServerState>);
+
+impl Default for JsonRpcServerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonRpcServerState {
+    /// Create a new `JsonRpcServerState` without any method handlers.
+    pub fn new() -> Self {
+        Self::with_codec(JsonCodec)
+    }
+
+    /// Create a new `JsonRpcServerState` which encodes and decodes packets with `codec`.
+    pub fn with_codec<C: Codec + 'static>(codec: C) -> Self {
+        Self::with_shared_codec(Arc::new(codec))
+    }
+
+    pub(crate) fn with_shared_codec(codec: Arc<dyn Codec>) -> Self {
+        Self(Arc::new(RawJsonRpcServerState {
+            is_closed: Default::default(),
+            handlers: Default::default(),
+            #[cfg(feature = "openrpc")]
+            descriptions: Default::default(),
+            send_queue: Default::default(),
+            wait_map: KeyWaitMap::new(),
+            codec,
+        }))
+    }
+
+    /// Returns the media type of the packets encoded by this server's codec.
+    pub fn media_type(&self) -> &'static str {
+        self.0.codec.media_type()
+    }
+
+    /// Register an async `handler` for `method`, replaces the older one if exists.
+    ///
+    /// The request params are deserialized as `P`, a deserialization failure is replied
+    /// with [`ErrorCode::InvalidParams`] without calling the handler.
+    pub fn handle<M, F, Fut, P, R>(&self, method: M, handler: F)
+    where
+        M: Into<String>,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult<R>> + Send + 'static,
+        P: DeserializeOwned,
+        R: Serialize,
+    {
+        let handler: Handler = Arc::new(move |params| match serde_json::from_value::<P>(params) {
+            Ok(params) => {
+                let fut = handler(params);
+
+                async move {
+                    serde_json::to_value(fut.await?).map_err(|err| Error {
+                        code: ErrorCode::InternalError,
+                        message: err.to_string(),
+                        data: None,
+                    })
+                }
+                .boxed()
+            }
+            Err(err) => {
+                let err = Error {
+                    code: ErrorCode::InvalidParams,
+                    message: err.to_string(),
+                    data: None,
+                };
+
+                async move { Err(err) }.boxed()
+            }
+        });
+
+        self.0
+            .handlers
+            .write()
+            .unwrap()
+            .insert(method.into(), handler);
+    }
+
+    /// Register the description of a method, which replaces the older one if exists.
+    ///
+    /// The description is included in the [`openrpc`](Self::openrpc) document
+    /// only if the method has a handler.
+    #[cfg(feature = "openrpc")]
+    pub fn describe(&self, method: crate::openrpc::Method) {
+        self.0
+            .descriptions
+            .write()
+            .unwrap()
+            .insert(method.name.clone(), method);
+    }
+
+    /// Generates the OpenRPC document of the registered methods, sorted by name.
+    ///
+    /// The methods without description are described without params and result,
+    /// the reserved `rpc.*` methods are not included.
+    #[cfg(feature = "openrpc")]
+    pub fn openrpc(&self, info: crate::openrpc::Info) -> crate::openrpc::OpenRpc {
+        use crate::openrpc::{Method, OpenRpc};
+
+        let mut names = self
+            .0
+            .handlers
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| !name.starts_with("rpc."))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        names.sort();
+
+        let descriptions = self.0.descriptions.read().unwrap();
+
+        let methods = names.into_iter().map(|name| {
+            descriptions
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| Method::new(name))
+        });
+
+        OpenRpc::new(info, methods)
+    }
+
+    /// Register the [`rpc.discover`](crate::openrpc::DISCOVER_METHOD) method,
+    /// which returns the [`openrpc`](Self::openrpc) document generated at call time.
+    #[cfg(feature = "openrpc")]
+    pub fn discover(&self, info: crate::openrpc::Info) {
+        // holds a weak reference to avoid the reference cycle of handlers.
+        let state = Arc::downgrade(&self.0);
+
+        self.handle(crate::openrpc::DISCOVER_METHOD, move |_: Value| {
+            let document = state
+                .upgrade()
+                .map(|state| JsonRpcServerState(state).openrpc(info.clone()));
+
+            async move {
+                document.ok_or_else(|| Error {
+                    code: ErrorCode::InternalError,
+                    message: "JsonRpcServer is closed".to_owned(),
+                    data: None,
+                })
+            }
+        });
+    }
+
+    /// Processes a jsonrpc packet and returns the response packet to reply,
+    /// or returns `None` if the packet is a notification or a batch of notifications.
+    ///
+    /// The requests in a batch are processed concurrently.
+    ///
+    /// Use this function directly for request/response transports, e.g, http.
+    pub async fn dispatch<V: AsRef<[u8]>>(&self, packet: V) -> std::io::Result<Option<Vec<u8>>> {
+        if self.is_closed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "JsonRpcServer is closed",
+            ));
+        }
+
+        let value = match self.0.codec.decode(packet.as_ref()) {
+            Ok(value) => value,
+            Err(err) => {
+                let resp = error_response(Id::Null, ErrorCode::ParseError, err);
+
+                return Ok(Some(self.encode(&resp)?));
+            }
+        };
+
+        self.dispatch_value(value).await
+    }
+
+    /// Processes a parsed jsonrpc packet, see [`dispatch`](Self::dispatch) for more details.
+    pub(crate) async fn dispatch_value(&self, value: Value) -> std::io::Result<Option<Vec<u8>>> {
+        let Value::Array(requests) = value else {
+            return match self.dispatch_request(value).await {
+                Some(resp) => Ok(Some(self.encode(&resp)?)),
+                None => Ok(None),
+            };
+        };
+
+        if requests.is_empty() {
+            let resp = error_response(Id::Null, ErrorCode::InvalidRequest, "Empty batch");
+
+            return Ok(Some(self.encode(&resp)?));
+        }
+
+        let resps = join_all(
+            requests
+                .into_iter()
+                .map(|request| self.dispatch_request(request)),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        // Nothing is replied for a batch of notifications.
+        if resps.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(self.encode(&resps)?))
+        }
+    }
+
+    fn encode<T: Serialize>(&self, value: &T) -> std::io::Result<Vec<u8>> {
+        self.0.codec.encode(&serde_json::to_value(value)?)
+    }
+
+    async fn dispatch_request(&self, value: Value) -> Option<ServerResponse> {
+        let id = value
+            .get("id")
+            .and_then(|id| Id::deserialize(id).ok())
+            .unwrap_or_default();
+
+        let request: Request<String, Option<Value>> = match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(err) => return Some(error_response(id, ErrorCode::InvalidRequest, err)),
+        };
+
+        let handler = self
+            .0
+            .handlers
+            .read()
+            .unwrap()
+            .get(&request.method)
+            .cloned();
+
+        let result = if let Some(handler) = handler {
+            handler(request.params.unwrap_or_default()).await
+        } else {
+            Err(Error {
+                code: ErrorCode::MethodNotFound,
+                message: format!("Method not found: {}", request.method),
+                data: None,
+            })
+        };
+
+        // The Server MUST NOT reply to a Notification.
+        let Some(id) = request.id else {
+            if let Err(err) = result {
+                log::trace!("notification {}, {}", request.method, err);
+            }
+
+            return None;
+        };
+
+        Some(match result {
+            Ok(result) => ServerResponse {
+                id,
+                jsonrpc: Version,
+                result: Some(result),
+                error: None,
+            },
+            Err(err) => ServerResponse {
+                id,
+                jsonrpc: Version,
+                result: None,
+                error: Some(err),
+            },
+        })
+    }
+
+    /// Processes jsonrpc packet received from the peer.
+    ///
+    /// This function returns after the method handler is finished,
+    /// calls it concurrently to process requests concurrently.
+    pub async fn recv<V: AsRef<[u8]>>(&self, packet: V) -> std::io::Result<()> {
+        if let Some(packet) = self.dispatch(packet).await? {
+            self.0.send_queue.lock().await.push_back(packet);
+
+            self.0.wait_map.insert(JsonRpcServerEvent::Forward, ());
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single jsonrpc response packet to be sent to the peer.
+    pub async fn send(&self) -> std::io::Result<Vec<u8>> {
+        loop {
+            let mut send_queue = self.0.send_queue.lock().await;
+
+            if self.is_closed() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "JsonRpcServer is closed",
+                ));
+            }
+
+            if let Some(packet) = send_queue.pop_front() {
+                return Ok(packet);
+            }
+
+            self.0
+                .wait_map
+                .wait(&JsonRpcServerEvent::Forward, send_queue)
+                .await;
+        }
+    }
+
+    /// Close the jsonrpc server.
+    pub fn close(&self) {
+        self.0.is_closed.store(true, Ordering::SeqCst);
+        self.0.wait_map.cancel_all();
+    }
+
+    /// Returns true if this server is already closed.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed.load(Ordering::SeqCst)
+    }
+}
+
+/// Jsonrpc v2.0 server state machine.
+#[derive(Default)]
+pub struct JsonRpcServer(JsonRpcServerState);
+
+impl Drop for JsonRpcServer {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+impl JsonRpcServer {
+    /// Create a new `JsonRpcServer` without any method handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new `JsonRpcServer` which encodes and decodes packets with `codec`.
+    pub fn with_codec<C: Codec + 'static>(codec: C) -> Self {
+        Self(JsonRpcServerState::with_codec(codec))
+    }
+
+    /// Register an async `handler` for `method`, see [`JsonRpcServerState::handle`] for more details.
+    pub fn handle<M, F, Fut, P, R>(self, method: M, handler: F) -> Self
+    where
+        M: Into<String>,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult<R>> + Send + 'static,
+        P: DeserializeOwned,
+        R: Serialize,
+    {
+        self.0.handle(method, handler);
+        self
+    }
+
+    /// Register the description of a method, see [`JsonRpcServerState::describe`] for more details.
+    #[cfg(feature = "openrpc")]
+    pub fn describe(self, method: crate::openrpc::Method) -> Self {
+        self.0.describe(method);
+        self
+    }
+
+    /// Register the `rpc.discover` method, see [`JsonRpcServerState::discover`] for more details.
+    #[cfg(feature = "openrpc")]
+    pub fn discover(self, info: crate::openrpc::Info) -> Self {
+        self.0.discover(info);
+        self
+    }
+
+    /// Generates the OpenRPC document, see [`JsonRpcServerState::openrpc`] for more details.
+    #[cfg(feature = "openrpc")]
+    pub fn openrpc(&self, info: crate::openrpc::Info) -> crate::openrpc::OpenRpc {
+        self.0.openrpc(info)
+    }
+
+    /// Get the inner [`JsonRpcServerState`] instance.
+    pub fn to_state(&self) -> JsonRpcServerState {
+        self.0.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::task::Poll;
+
+    use futures::poll;
+    use serde_json::json;
+
+    use super::*;
+
+    fn server() -> JsonRpcServer {
+        JsonRpcServer::new()
+            .handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) })
+            .handle("echo", |params: Value| async move { Ok(params) })
+            .handle("fail", |_: ()| async move {
+                Err::<(), _>(Error {
+                    code: ErrorCode::ServerError(-32000, "".to_owned()),
+                    message: "fail".to_owned(),
+                    data: None,
+                })
+            })
+    }
+
+    async fn dispatch(server: &JsonRpcServerState, packet: Value) -> Option<Value> {
+        server
+            .dispatch(packet.to_string())
+            .await
+            .unwrap()
+            .map(|buf| serde_json::from_slice(&buf).unwrap())
+    }
+
+    #[futures_test::test]
+    async fn test_dispatch() {
+        let server = server();
+
+        let server = server.to_state();
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!({"id":1,"jsonrpc":"2.0","method":"add","params":[1,2]})
+            )
+            .await,
+            Some(json!({"id":1,"jsonrpc":"2.0","result":3}))
+        );
+
+        assert_eq!(
+            dispatch(&server, json!({"id":2,"jsonrpc":"2.0","method":"echo"})).await,
+            Some(json!({"id":2,"jsonrpc":"2.0","result":null}))
+        );
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!({"id":"c9a0b1e2","jsonrpc":"2.0","method":"add","params":[1,2]})
+            )
+            .await,
+            Some(json!({"id":"c9a0b1e2","jsonrpc":"2.0","result":3}))
+        );
+
+        assert_eq!(
+            dispatch(&server, json!({"id":3,"jsonrpc":"2.0","method":"fail"})).await,
+            Some(
+                json!({"id":3,"jsonrpc":"2.0","error":{"code":-32000,"message":"fail","data":null}})
+            )
+        );
+    }
+
+    #[futures_test::test]
+    async fn test_dispatch_id() {
+        let server = server();
+
+        let server = server.to_state();
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!({"id":null,"jsonrpc":"2.0","method":"add","params":[1,2]})
+            )
+            .await,
+            Some(json!({"id":null,"jsonrpc":"2.0","result":3}))
+        );
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!({"id":-1,"jsonrpc":"2.0","method":"add","params":[1,2]})
+            )
+            .await,
+            Some(json!({"id":-1,"jsonrpc":"2.0","result":3}))
+        );
+    }
+
+    #[futures_test::test]
+    async fn test_notification() {
+        let server = server();
+
+        let server = server.to_state();
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!({"jsonrpc":"2.0","method":"add","params":[1,2]})
+            )
+            .await,
+            None
+        );
+
+        assert_eq!(
+            dispatch(&server, json!({"jsonrpc":"2.0","method":"not_found"})).await,
+            None
+        );
+    }
+
+    #[futures_test::test]
+    async fn test_errors() {
+        let server = server();
+
+        let server = server.to_state();
+
+        let code = |resp: Option<Value>| {
+            let resp = resp.unwrap();
+            (resp["id"].clone(), resp["error"]["code"].clone())
+        };
+
+        assert_eq!(
+            code(
+                dispatch(
+                    &server,
+                    json!({"id":1,"jsonrpc":"2.0","method":"not_found"})
+                )
+                .await
+            ),
+            (json!(1), json!(-32601))
+        );
+
+        assert_eq!(
+            code(
+                dispatch(
+                    &server,
+                    json!({"id":2,"jsonrpc":"2.0","method":"add","params":["1",2]})
+                )
+                .await
+            ),
+            (json!(2), json!(-32602))
+        );
+
+        assert_eq!(
+            code(dispatch(&server, json!({"id":3,"jsonrpc":"3.0","method":"add"})).await),
+            (json!(3), json!(-32600))
+        );
+
+        let resp: Value =
+            serde_json::from_slice(&server.dispatch("{").await.unwrap().unwrap()).unwrap();
+
+        assert_eq!(code(Some(resp)), (Value::Null, json!(-32700)));
+    }
+
+    #[futures_test::test]
+    async fn test_batch() {
+        let server = server();
+
+        let server = server.to_state();
+
+        assert_eq!(
+            dispatch(
+                &server,
+                json!([
+                    {"id":1,"jsonrpc":"2.0","method":"add","params":[1,2]},
+                    {"jsonrpc":"2.0","method":"add","params":[1,2]},
+                    {"id":2,"jsonrpc":"2.0","method":"not_found"},
+                    1
+                ])
+            )
+            .await,
+            Some(json!([
+                {"id":1,"jsonrpc":"2.0","result":3},
+                {"id":2,"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found: not_found","data":null}},
+                {"id":null,"jsonrpc":"2.0","error":{"code":-32600,"message":"invalid type: integer `1`, expected struct Request","data":null}}
+            ]))
+        );
+
+        // all notifications.
+        assert_eq!(
+            dispatch(
+                &server,
+                json!([
+                    {"jsonrpc":"2.0","method":"add","params":[1,2]},
+                    {"jsonrpc":"2.0","method":"echo"}
+                ])
+            )
+            .await,
+            None
+        );
+
+        // empty batch.
+        let resp = dispatch(&server, json!([])).await.unwrap();
+
+        assert_eq!(resp["id"], Value::Null);
+        assert_eq!(resp["error"]["code"], json!(-32600));
+    }
+
+    #[futures_test::test]
+    async fn test_send_recv() {
+        let server = server();
+
+        let state = server.to_state();
+
+        let mut send = Box::pin(state.send());
+
+        assert!(poll!(&mut send).is_pending());
+
+        state
+            .recv(json!({"jsonrpc":"2.0","method":"add","params":[1,2]}).to_string())
+            .await
+            .unwrap();
+
+        assert!(poll!(&mut send).is_pending());
+
+        state
+            .recv(json!({"id":1,"jsonrpc":"2.0","method":"add","params":[1,2]}).to_string())
+            .await
+            .unwrap();
+
+        let buf = send.await.unwrap();
+
+        assert_eq!(
+            serde_json::from_slice::<Value>(&buf).unwrap(),
+            json!({"id":1,"jsonrpc":"2.0","result":3})
+        );
+
+        let mut send = Box::pin(state.send());
+
+        assert!(poll!(&mut send).is_pending());
+
+        drop(server);
+
+        assert!(matches!(poll!(&mut send), Poll::Ready(Err(_))));
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "with_rasi")]
+
+use std::{sync::Once, time::Duration};
+
+use futures::{AsyncReadExt, StreamExt};
+use futures_jsonrpcv2::{
+    client::JsonRpcClient, framed::Framing, peer::JsonRpcPeer, rasi::framed::FramedJsonRpc,
+    server::JsonRpcServer, JsonRpcError,
+};
+use rasi::{net::TcpListener, task::register_futures_spawn};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+        register_futures_spawn(10);
+    })
+}
+
+#[futures_test::test]
+async fn test_tcp() {
+    init();
+
+    for framing in [
+        Framing::Newline,
+        Framing::ContentLength,
+        Framing::LengthPrefixed,
+    ] {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let raddr = listener.local_addr().unwrap();
+
+        let server =
+            JsonRpcServer::new().handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+        let stream = rasi::net::TcpStream::connect(raddr).await.unwrap();
+
+        let (read, write) = stream.split();
+
+        let client = JsonRpcClient::default();
+
+        FramedJsonRpc::new(framing, read, write).spawn_client(&client);
+
+        let (read, write) = listener.next().await.unwrap().unwrap().split();
+
+        FramedJsonRpc::new(framing, read, write).spawn_server(&server);
+
+        let mut batch = client.batch();
+
+        let first = batch.call::<_, _, i32>("add", (1, 2)).unwrap();
+        let second = batch.call::<_, _, i32>("add", (3, 4)).unwrap();
+
+        batch.send().await.unwrap();
+
+        assert_eq!(first.await.unwrap(), 3, "{:?}", framing);
+        assert_eq!(second.await.unwrap(), 7, "{:?}", framing);
+
+        let sum: i32 = client.call("add", (5, 6)).await.unwrap();
+
+        assert_eq!(sum, 11, "{:?}", framing);
+    }
+}
+
+#[cfg(unix)]
+#[futures_test::test]
+async fn test_command() {
+    init();
+
+    // `cat` echoes the requests back, so the peer calls itself.
+    let (framed, mut child) = FramedJsonRpc::spawn_command(
+        Framing::ContentLength,
+        &mut std::process::Command::new("cat"),
+    )
+    .unwrap();
+
+    let peer = JsonRpcPeer::default().handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+    framed.spawn_peer(&peer);
+
+    let sum: i32 = peer.call("add", (1, 2)).await.unwrap();
+
+    assert_eq!(sum, 3);
+
+    drop(peer);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(unix)]
+#[futures_test::test]
+async fn test_command_eof() {
+    init();
+
+    let (framed, mut child) = FramedJsonRpc::spawn_command(
+        Framing::ContentLength,
+        &mut std::process::Command::new("cat"),
+    )
+    .unwrap();
+
+    let client = JsonRpcClient::default();
+
+    framed.spawn_client(&client);
+
+    // dropping the client closes the child's stdin, so `cat` exits by itself.
+    drop(client);
+
+    assert!(child.wait().unwrap().success());
+}
+
+#[futures_test::test]
+async fn test_call_timeout() {
+    init();
+
+    // a client without transport never receives responses.
+    let client = JsonRpcClient::default();
+
+    let err = client
+        .call_with_timeout::<_, _, i32>("add", (1, 2), Duration::from_millis(10))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, JsonRpcError::Timeout), "{}", err);
+}
@@ -0,0 +1,421 @@
+#![cfg(feature = "with_rasi")]
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Once,
+    },
+    time::Duration,
+};
+
+use futures_http::{
+    client::rasio::HttpClientOptions, fluent::Client, server::HttpServer, types::StatusCode,
+    writer::HttpWriter,
+};
+use futures_jsonrpcv2::{
+    rasi::http::{HttpJsonRpcClient, HttpJsonRpcServer},
+    server::JsonRpcServer,
+    Error, ErrorCode,
+};
+use rasi::{
+    net::TcpListener,
+    task::{register_futures_spawn, spawn_ok},
+    timer::{sleep, TimeoutExt},
+};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+        register_futures_spawn(10);
+    })
+}
+
+/// Serve a jsonrpc server with an `add` method on `/rpc`, which requires a bearer token.
+async fn spawn_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    let server = HttpServer::on(Some("jsonrpc_test"), listener);
+
+    let jsonrpc = JsonRpcServer::new().handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+    // fails with a server error before the third call.
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let jsonrpc = jsonrpc.handle("flaky", move |_: ()| {
+        let calls = calls.fetch_add(1, Ordering::SeqCst);
+
+        async move {
+            if calls < 2 {
+                return Err(Error {
+                    code: ErrorCode::ServerError(-32000, String::new()),
+                    message: "busy".to_owned(),
+                    data: None,
+                });
+            }
+
+            Ok(calls)
+        }
+    });
+
+    let adapter = HttpJsonRpcServer::new(&jsonrpc)
+        .path("/rpc")
+        .max_body_size(128)
+        .bearer_auth("hala");
+
+    spawn_ok(async move {
+        // keeps the jsonrpc server alive.
+        let _jsonrpc = jsonrpc;
+
+        _ = adapter.serve(server).await;
+    });
+
+    raddr
+}
+
+#[futures_test::test]
+async fn test_http_server() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = HttpJsonRpcClient::new(format!("http://{}/rpc", raddr))
+        .header("Authorization", "Bearer hala")
+        .create()
+        .unwrap();
+
+    let sum: i32 = client.call("add", (1, 2)).await.unwrap();
+
+    assert_eq!(sum, 3);
+
+    let mut batch = client.batch();
+
+    let first = batch.call::<_, _, i32>("add", (1, 1)).unwrap();
+    let second = batch.call::<_, _, i32>("add", (2, 2)).unwrap();
+
+    batch.send().await.unwrap();
+
+    assert_eq!(first.await.unwrap(), 2);
+    assert_eq!(second.await.unwrap(), 4);
+
+    let client = HttpJsonRpcClient::new(format!("http://{}/rpc", raddr))
+        .create()
+        .unwrap();
+
+    let err = client.call::<_, _, i32>("add", (1, 2)).await.unwrap_err();
+
+    assert!(err.to_string().contains("401"), "{}", err);
+}
+
+#[futures_test::test]
+async fn test_http_status() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let uri = format!("http://{}/rpc", raddr);
+
+    let notification = r#"{"jsonrpc":"2.0","method":"add","params":[1,2]}"#;
+
+    let cases = [
+        (
+            client.post(format!("http://{}/", raddr)),
+            StatusCode::NOT_FOUND,
+        ),
+        (client.get(&uri), StatusCode::METHOD_NOT_ALLOWED),
+        // the unauthorized requests are rejected before the content type check.
+        (
+            client
+                .post(&uri)
+                .header("Content-Type", "text/plain")
+                .body(notification),
+            StatusCode::UNAUTHORIZED,
+        ),
+        (
+            client
+                .post(&uri)
+                .bearer_auth("hala")
+                .header("Content-Type", "text/plain")
+                .body(notification),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ),
+        (
+            client.post(&uri).json(&serde_json::json!({})),
+            StatusCode::UNAUTHORIZED,
+        ),
+        (
+            client
+                .post(&uri)
+                .bearer_auth("hala")
+                .header("Content-Type", "application/json; charset=utf-8")
+                .body(notification),
+            StatusCode::NO_CONTENT,
+        ),
+        (
+            client.post(&uri).bearer_auth("hala").json(&vec![0; 128]),
+            StatusCode::PAYLOAD_TOO_LARGE,
+        ),
+    ];
+
+    for (request, status) in cases {
+        let response = request.send().await.unwrap();
+
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[cfg(feature = "cbor")]
+#[futures_test::test]
+async fn test_http_codec() {
+    use futures::TryStreamExt;
+    use futures_jsonrpcv2::codec::{CborCodec, Codec};
+
+    init();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    let jsonrpc = JsonRpcServer::with_codec(CborCodec)
+        .handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+    let adapter = HttpJsonRpcServer::new(&jsonrpc);
+
+    spawn_ok(async move {
+        let _jsonrpc = jsonrpc;
+
+        _ = adapter.serve(HttpServer::on(None, listener)).await;
+    });
+
+    let client = Client::new(HttpClientOptions::new()).unwrap();
+
+    let uri = format!("http://{}/", raddr);
+
+    let request = serde_json::json!({"id":0,"jsonrpc":"2.0","method":"add","params":[1,2]});
+
+    let response = client.post(&uri).json(&request).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let response = client
+        .post(&uri)
+        .header("Content-Type", "application/cbor")
+        .body(CborCodec.encode(&request).unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["Content-Type"], "application/cbor");
+
+    let body = response.into_body().try_concat().await.unwrap();
+
+    assert_eq!(
+        CborCodec.decode(&body).unwrap(),
+        serde_json::json!({"id":0,"jsonrpc":"2.0","result":3})
+    );
+}
+
+/// Returns an address without listener.
+async fn dead_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[futures_test::test]
+async fn test_http_failover() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let dead = dead_addr().await;
+
+    let builder = || {
+        HttpJsonRpcClient::new(format!("http://{}/rpc", dead))
+            .endpoint(format!("http://{}/rpc", raddr))
+            .header("Authorization", "Bearer hala")
+            .timeout(Duration::from_millis(500))
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(10))
+            .failover(1, Duration::from_secs(60))
+    };
+
+    // the calls are not retried by default.
+    let client = builder().create().unwrap();
+
+    client.call::<_, _, i32>("add", (1, 2)).await.unwrap_err();
+
+    let client = builder()
+        .idempotent(|method| method == "add")
+        .create()
+        .unwrap();
+
+    let sum: i32 = client.call("add", (1, 2)).await.unwrap();
+
+    assert_eq!(sum, 3);
+
+    // the dead endpoint is out of rotation.
+    let sum: i32 = client.call("add", (2, 2)).await.unwrap();
+
+    assert_eq!(sum, 4);
+
+    // a client error is not retried.
+    let client = HttpJsonRpcClient::new(format!("http://{}/rpc", raddr))
+        .idempotent(|_| true)
+        .create()
+        .unwrap();
+
+    let err = client.call::<_, _, i32>("add", (1, 2)).await.unwrap_err();
+
+    assert!(err.to_string().contains("401"), "{}", err);
+}
+
+#[futures_test::test]
+async fn test_http_retryable_error() {
+    init();
+
+    let raddr = spawn_server().await;
+
+    let builder = || {
+        HttpJsonRpcClient::new(format!("http://{}/rpc", raddr))
+            .header("Authorization", "Bearer hala")
+            .idempotent(|_| true)
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(10))
+            .retryable_error(|err| err.code.code() == -32000)
+    };
+
+    // the retries are disabled.
+    let client = builder().max_retries(0).create().unwrap();
+
+    let err = client.call::<_, _, usize>("flaky", ()).await.unwrap_err();
+
+    assert_eq!(err.code().map(ErrorCode::code), Some(-32000));
+
+    let client = builder().create().unwrap();
+
+    // only the failed call of the batch is retried.
+    let mut batch = client.batch();
+
+    let flaky = batch.call::<_, _, usize>("flaky", ()).unwrap();
+    let sum = batch.call::<_, _, i32>("add", (1, 1)).unwrap();
+
+    batch.send().await.unwrap();
+
+    assert_eq!(sum.await.unwrap(), 2);
+    assert_eq!(flaky.await.unwrap(), 2);
+}
+
+/// Serve `jsonrpc` on `/`, returns the server address and the counter of http requests.
+async fn spawn_counted_server(jsonrpc: JsonRpcServer) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    let mut server = HttpServer::on(Some("jsonrpc_test"), listener);
+
+    let adapter = HttpJsonRpcServer::new(&jsonrpc);
+
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let counter = requests.clone();
+
+    spawn_ok(async move {
+        // keeps the jsonrpc server alive.
+        let _jsonrpc = jsonrpc;
+
+        while let Ok((request, mut write)) = server.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+
+            let adapter = adapter.clone();
+
+            spawn_ok(async move {
+                let response = adapter.handle(request).await;
+
+                _ = write.write_response(response).await;
+            });
+        }
+    });
+
+    (raddr, requests)
+}
+
+#[futures_test::test]
+async fn test_http_concurrency() {
+    init();
+
+    // replies after 3 calls are in flight.
+    let arrived = Arc::new(AtomicUsize::new(0));
+
+    let jsonrpc = JsonRpcServer::new().handle("rendezvous", move |_: ()| {
+        let arrived = arrived.clone();
+
+        async move {
+            arrived.fetch_add(1, Ordering::SeqCst);
+
+            while arrived.load(Ordering::SeqCst) < 3 {
+                sleep(Duration::from_millis(10)).await;
+            }
+
+            Ok(())
+        }
+    });
+
+    let (raddr, requests) = spawn_counted_server(jsonrpc).await;
+
+    let client = HttpJsonRpcClient::new(format!("http://{}", raddr))
+        .concurrency(3)
+        .create()
+        .unwrap();
+
+    let calls = futures::future::join3(
+        client.call::<_, _, ()>("rendezvous", ()),
+        client.call::<_, _, ()>("rendezvous", ()),
+        client.call::<_, _, ()>("rendezvous", ()),
+    )
+    .timeout(Duration::from_secs(2))
+    .await
+    .expect("the calls are sent concurrently");
+
+    calls.0.unwrap();
+    calls.1.unwrap();
+    calls.2.unwrap();
+
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[futures_test::test]
+async fn test_http_batch_window() {
+    init();
+
+    let jsonrpc = JsonRpcServer::new().handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) });
+
+    let (raddr, requests) = spawn_counted_server(jsonrpc).await;
+
+    let client = HttpJsonRpcClient::new(format!("http://{}", raddr))
+        .batch_window(Duration::from_millis(50), 10)
+        .create()
+        .unwrap();
+
+    let (first, second, third) = futures::future::join3(
+        client.call::<_, _, i32>("add", (1, 1)),
+        client.call::<_, _, i32>("add", (2, 2)),
+        client.notify("add", (3, 3)),
+    )
+    .await;
+
+    assert_eq!(first.unwrap(), 2);
+    assert_eq!(second.unwrap(), 4);
+    third.unwrap();
+
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
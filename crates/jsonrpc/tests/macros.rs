@@ -0,0 +1,125 @@
+#![cfg(all(feature = "macros", feature = "with_rasi"))]
+
+use std::sync::Once;
+
+use futures::{
+    channel::mpsc::{unbounded, UnboundedSender},
+    AsyncReadExt, StreamExt,
+};
+use futures_jsonrpcv2::{
+    client::JsonRpcClient,
+    framed::Framing,
+    jsonrpc,
+    rasi::framed::FramedJsonRpc,
+    server::{HandlerResult, JsonRpcServer},
+    Error, ErrorCode, JsonRpcError,
+};
+use rasi::{net::TcpListener, task::register_futures_spawn};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+use serde_json::json;
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+        register_futures_spawn(10);
+    })
+}
+
+/// A calculator service.
+#[jsonrpc]
+pub trait Calculator {
+    /// Returns `a + b`.
+    async fn add(&self, a: i32, b: i32) -> HandlerResult<i32>;
+
+    #[jsonrpc(name = "math_sub", params = "named")]
+    async fn sub(&self, a: i32, b: i32) -> HandlerResult<i32>;
+
+    async fn div(&self, a: i32, b: i32) -> Result<i32, JsonRpcError>;
+
+    async fn version(&self) -> HandlerResult<String>;
+
+    #[jsonrpc(notification)]
+    async fn log(&self, message: String);
+}
+
+struct Service(UnboundedSender<String>);
+
+impl Calculator for Service {
+    async fn add(&self, a: i32, b: i32) -> HandlerResult<i32> {
+        Ok(a + b)
+    }
+
+    async fn sub(&self, a: i32, b: i32) -> HandlerResult<i32> {
+        Ok(a - b)
+    }
+
+    async fn div(&self, a: i32, b: i32) -> Result<i32, JsonRpcError> {
+        if b == 0 {
+            return Err(JsonRpcError::Rpc(Error {
+                code: ErrorCode::InvalidParams,
+                message: "division by zero".to_owned(),
+                data: None,
+            }));
+        }
+
+        Ok(a / b)
+    }
+
+    async fn version(&self) -> HandlerResult<String> {
+        Ok("1.0".to_owned())
+    }
+
+    async fn log(&self, message: String) {
+        self.0.unbounded_send(message).unwrap();
+    }
+}
+
+#[futures_test::test]
+async fn test_macros() {
+    init();
+
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    let (sender, mut logs) = unbounded();
+
+    let server = register_calculator(JsonRpcServer::new(), Service(sender));
+
+    let (read, write) = rasi::net::TcpStream::connect(raddr).await.unwrap().split();
+
+    let client = JsonRpcClient::default();
+
+    FramedJsonRpc::new(Framing::Newline, read, write).spawn_client(&client);
+
+    let (read, write) = listener.next().await.unwrap().unwrap().split();
+
+    FramedJsonRpc::new(Framing::Newline, read, write).spawn_server(&server);
+
+    let client = CalculatorClient::new(client);
+
+    assert_eq!(client.add(1, 2).await.unwrap(), 3);
+    assert_eq!(client.sub(3, 1).await.unwrap(), 2);
+    assert_eq!(client.div(6, 3).await.unwrap(), 2);
+    assert_eq!(client.version().await.unwrap(), "1.0");
+
+    let err = client.div(1, 0).await.unwrap_err();
+
+    assert_eq!(err.code(), Some(&ErrorCode::InvalidParams));
+
+    // the overridden method name with named params.
+    let diff: i32 = client
+        .inner()
+        .call("math_sub", json!({"a":5,"b":2}))
+        .await
+        .unwrap();
+
+    assert_eq!(diff, 3);
+
+    client.log("hello".to_owned()).await.unwrap();
+
+    assert_eq!(logs.next().await.unwrap(), "hello");
+}
@@ -0,0 +1,127 @@
+#![cfg(all(feature = "macros", feature = "openrpc"))]
+
+use futures_jsonrpcv2::{
+    jsonrpc,
+    openrpc::{Info, JsonSchema, Method, Mismatch, OpenRpc, ParamStructure},
+    server::{HandlerResult, JsonRpcServer},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Block {
+    number: u64,
+    hash: String,
+}
+
+/// A chain service.
+#[jsonrpc(openrpc)]
+pub trait Chain {
+    /// Returns the block of `number`.
+    ///
+    /// Returns `null` if the block is not found.
+    async fn block(&self, number: u64) -> HandlerResult<Option<Block>>;
+
+    #[jsonrpc(name = "chain_search", params = "named")]
+    async fn search(&self, hash: String, limit: Option<u32>) -> HandlerResult<Vec<Block>>;
+
+    #[jsonrpc(notification)]
+    async fn log(&self, message: String);
+}
+
+struct Service;
+
+impl Chain for Service {
+    async fn block(&self, number: u64) -> HandlerResult<Option<Block>> {
+        Ok(Some(Block {
+            number,
+            hash: "0x1".to_owned(),
+        }))
+    }
+
+    async fn search(&self, _hash: String, _limit: Option<u32>) -> HandlerResult<Vec<Block>> {
+        Ok(vec![])
+    }
+
+    async fn log(&self, _message: String) {}
+}
+
+async fn discover(server: &JsonRpcServer) -> OpenRpc {
+    let request = json!({"id":1,"jsonrpc":"2.0","method":"rpc.discover"}).to_string();
+
+    let response = server.to_state().dispatch(request).await.unwrap().unwrap();
+
+    let mut response: serde_json::Value = serde_json::from_slice(&response).unwrap();
+
+    serde_json::from_value(response["result"].take()).unwrap()
+}
+
+#[futures_test::test]
+async fn test_discover() {
+    let server = register_chain(JsonRpcServer::new(), Service)
+        .handle("ping", |_: ()| async { Ok("pong") })
+        .discover(Info::new("chain", "1.0.0"));
+
+    let document = discover(&server).await;
+
+    assert_eq!(document.info, Info::new("chain", "1.0.0"));
+
+    assert_eq!(
+        document
+            .methods
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect::<Vec<_>>(),
+        ["block", "chain_search", "log", "ping"]
+    );
+
+    let block = document.method("block").unwrap();
+
+    assert_eq!(
+        block.description.as_deref(),
+        Some("Returns the block of `number`.\n\nReturns `null` if the block is not found.")
+    );
+    assert_eq!(block.param_structure, ParamStructure::ByPosition);
+    assert_eq!(block.params[0].name, "number");
+
+    let search = document.method("chain_search").unwrap();
+
+    assert_eq!(search.param_structure, ParamStructure::ByName);
+    assert!(search.params[0].required);
+    assert!(!search.params[1].required);
+
+    assert!(document.method("log").unwrap().result.is_none());
+
+    // the undescribed method.
+    assert_eq!(document.method("ping"), Some(&Method::new("ping")));
+
+    assert_eq!(document.check(&ChainClient::openrpc_methods()), Ok(()));
+}
+
+#[futures_test::test]
+async fn test_check_stub() {
+    let server = JsonRpcServer::new()
+        .handle("block", |(number,): (String,)| async move { Ok(number) })
+        .describe(
+            Method::new("block")
+                .param_structure(ParamStructure::ByPosition)
+                .param::<String, _>("number")
+                .result::<String, _>("result"),
+        )
+        .discover(Info::new("chain", "2.0.0"));
+
+    let document = discover(&server).await;
+
+    assert_eq!(
+        document.check(&ChainClient::openrpc_methods()),
+        Err(vec![
+            Mismatch::Param {
+                method: "block".to_owned(),
+                param: "number".to_owned()
+            },
+            Mismatch::Result("block".to_owned()),
+            Mismatch::MethodNotFound("chain_search".to_owned()),
+            Mismatch::MethodNotFound("log".to_owned()),
+        ])
+    );
+}
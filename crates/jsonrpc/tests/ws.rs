@@ -0,0 +1,162 @@
+#![cfg(feature = "with_rasi")]
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Once,
+    },
+    time::Duration,
+};
+
+use async_tungstenite::{
+    accept_hdr_async,
+    tungstenite::{
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+        Message,
+    },
+};
+use futures::StreamExt;
+use futures_jsonrpcv2::{rasi::ws::WsJsonRpcClient, server::JsonRpcServer};
+use rasi::{
+    net::TcpListener,
+    task::{register_futures_spawn, spawn_ok},
+};
+use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
+use serde_json::json;
+
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        register_mio_network();
+        register_mio_timer();
+        register_futures_spawn(10);
+    })
+}
+
+/// Rejects the websocket handshake without a `x-token: hala` header.
+#[allow(clippy::result_large_err)]
+fn check_token(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    if request.headers().get("x-token").map(|v| v.as_bytes()) == Some(b"hala") {
+        return Ok(response);
+    }
+
+    let mut response = ErrorResponse::new(None);
+    *response.status_mut() = StatusCode::UNAUTHORIZED;
+
+    Err(response)
+}
+
+/// Start a websocket jsonrpc server, see [`check_token`] for the authorization.
+///
+/// The `drop` method closes the connection without response, and the `subscribe` method
+/// pushes a notification after the response.
+async fn spawn_server(connections: Arc<AtomicUsize>) -> SocketAddr {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let raddr = listener.local_addr().unwrap();
+
+    spawn_ok(async move {
+        while let Some(Ok(stream)) = listener.next().await {
+            let connections = connections.clone();
+
+            spawn_ok(async move {
+                let Ok(mut stream) = accept_hdr_async(stream, check_token).await else {
+                    return;
+                };
+
+                connections.fetch_add(1, Ordering::SeqCst);
+
+                let server = JsonRpcServer::new()
+                    .handle("add", |(a, b): (i32, i32)| async move { Ok(a + b) })
+                    .handle("subscribe", |_: ()| async move { Ok(true) });
+
+                let server = server.to_state();
+
+                while let Some(Ok(Message::Text(text))) = stream.next().await {
+                    if text.contains("\"drop\"") {
+                        return;
+                    }
+
+                    if let Some(packet) = server.dispatch(text.as_bytes()).await.unwrap() {
+                        let packet = String::from_utf8(packet).unwrap();
+
+                        stream.send(Message::text(packet)).await.unwrap();
+                    }
+
+                    if text.contains("\"subscribe\"") {
+                        let notification =
+                            json!({"jsonrpc":"2.0","method":"event","params":[1]}).to_string();
+
+                        stream.send(Message::text(notification)).await.unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    raddr
+}
+
+#[futures_test::test]
+async fn test_ws() {
+    init();
+
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let raddr = spawn_server(connections.clone()).await;
+
+    let client = WsJsonRpcClient::new(format!("ws://{}", raddr))
+        .header("x-token", "hala")
+        .reconnect_backoff(Duration::from_millis(10), Duration::from_millis(100))
+        .create()
+        .unwrap();
+
+    let sum: i32 = client.call("add", (1, 2)).await.unwrap();
+
+    assert_eq!(sum, 3);
+
+    let subscribed: bool = client.call("subscribe", ()).await.unwrap();
+
+    assert!(subscribed);
+
+    let event = client.incoming().next().await.unwrap();
+
+    assert_eq!(event.method, "event");
+
+    // the in-flight call fails when the connection drops.
+    client.call::<_, _, ()>("drop", ()).await.unwrap_err();
+
+    // the next call is sent over a new connection.
+    let sum: i32 = client.call("add", (3, 4)).await.unwrap();
+
+    assert_eq!(sum, 7);
+
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[futures_test::test]
+async fn test_ws_unauthorized() {
+    init();
+
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let raddr = spawn_server(connections.clone()).await;
+
+    let client = WsJsonRpcClient::new(format!("ws://{}", raddr))
+        .reconnect_backoff(Duration::from_millis(10), Duration::from_millis(10))
+        .create()
+        .unwrap();
+
+    let call = client.call::<_, _, i32>("add", (1, 2));
+
+    assert!(
+        rasi::timer::TimeoutExt::timeout(call, Duration::from_millis(200))
+            .await
+            .is_none()
+    );
+
+    assert_eq!(connections.load(Ordering::SeqCst), 0);
+}
@@ -142,6 +142,25 @@ where
         }
     }
 
+    /// Removes the value and the waiting task of the key without waking it up,
+    /// e.g, when the waiting task is dropped.
+    ///
+    /// Returns the value at the key if the key was previously in the map.
+    pub fn remove<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let mut raw = self.inner.lock().unwrap();
+
+        raw.wakers.remove(k);
+
+        match raw.kv.remove(k) {
+            Some(Event::Value(value)) => Some(value),
+            _ => None,
+        }
+    }
+
     /// Cancel all key waiting tasks.
     pub fn cancel_all(&self) {
         let mut raw = self.inner.lock().unwrap();
@@ -226,4 +245,26 @@ mod tests {
 
         assert_eq!(poll!(&mut wait), Poll::Ready(Some(2)));
     }
+
+    #[futures_test::test]
+    async fn test_remove() {
+        let event_map = KeyWaitMap::<usize, usize>::new();
+
+        event_map.insert(1, 1);
+
+        assert_eq!(event_map.remove(&1), Some(1));
+        assert_eq!(event_map.remove(&1), None);
+
+        let mut wait = Box::pin(event_map.wait(&2, ()));
+
+        assert_eq!(poll!(&mut wait), Poll::Pending);
+
+        drop(wait);
+
+        assert_eq!(event_map.remove(&2), None);
+
+        event_map.insert(2, 2);
+
+        assert_eq!(event_map.wait(&2, ()).await, Some(2));
+    }
 }
@@ -15,7 +15,8 @@ use std::{
 
 use futures::{future::BoxFuture, lock::Mutex, AsyncRead, AsyncWrite, FutureExt, Stream};
 use futures_map::KeyWaitMap;
-use quiche::{ConnectionId, RecvInfo, SendInfo};
+use quiche::{ConnectionId, PathEvent, RecvInfo, SendInfo};
+use ring::rand::{SecureRandom, SystemRandom};
 
 use crate::errors::map_quic_error;
 
@@ -34,19 +35,57 @@ enum QuicConnStateEvent {
     StreamAccept(ConnectionId<'static>),
     /// This event notify that peer_streams_left_bidi > 0
     OutboundStream(ConnectionId<'static>),
+
+    /// This event notify listener that one incoming unidirectional stream is valid.
+    UniStreamAccept(ConnectionId<'static>),
+    /// This event notify that peer_streams_left_uni > 0
+    OutboundUniStream(ConnectionId<'static>),
+
+    /// This event notify that the DATAGRAM receive queue is not empty.
+    DgramReadable(ConnectionId<'static>),
+
+    /// This event notify that the DATAGRAM send queue is not full.
+    DgramWritable(ConnectionId<'static>),
+
+    /// This event notify that one path event is valid.
+    PathEvent(ConnectionId<'static>),
+    /// This event notify that the validation state of paths may be changed.
+    PathValidation(ConnectionId<'static>),
+
+    /// This event notify that the handshake is complete.
+    Established(ConnectionId<'static>),
 }
 
+/// The max number of path events buffered for application.
+const MAX_PATH_EVENTS: usize = 64;
+
 struct QuicRawConnState {
     /// Quiche connection statement.
     conn: quiche::Connection,
     /// Next stream outbound id.
     next_outbound_stream_id: u64,
+    /// Next unidirectional stream outbound id.
+    next_outbound_uni_stream_id: u64,
     /// Known unclosed inbound streams
     inbound_stream_ids: HashSet<u64>,
     /// Known outbound streams that had never sent any data.
     outbound_stream_ids: HashSet<u64>,
+    /// Known outbound unidirectional streams that had never sent any data.
+    outbound_uni_stream_ids: HashSet<u64>,
     /// When first see a inbound stream, push it into this queue.
     incoming_streams: VecDeque<u64>,
+    /// When first see a inbound unidirectional stream, push it into this queue.
+    incoming_uni_streams: VecDeque<u64>,
+    /// The path events that had not been read by application.
+    path_events: VecDeque<PathEvent>,
+    /// The paths failed to be validated, which are waiting by [`QuicConnState::probe_path`].
+    failed_paths: HashSet<(SocketAddr, SocketAddr)>,
+    /// The source ids issued to peer, which are not registered by listener yet.
+    issued_scids: Vec<ConnectionId<'static>>,
+    /// The source ids retired by peer, which are not unregistered by listener yet.
+    retired_scids: Vec<ConnectionId<'static>>,
+    /// Whether the client had written stream data or datagrams in 0-RTT.
+    early_data: bool,
     /// The time interval for sending ack_eliciting packets.
     /// To disable the behaviour of sending ack_eliciting packets, set this field to [`None`].
     ack_eliciting_interval: Option<Duration>,
@@ -75,13 +114,29 @@ impl QuicRawConnState {
         QuicRawConnState {
             conn,
             next_outbound_stream_id: init_stream_outbound_id,
+            // the second least significant bit of unidirectional stream id is set.
+            next_outbound_uni_stream_id: init_stream_outbound_id + 2,
             inbound_stream_ids: Default::default(),
             outbound_stream_ids: Default::default(),
+            outbound_uni_stream_ids: Default::default(),
             incoming_streams: Default::default(),
+            incoming_uni_streams: Default::default(),
+            path_events: Default::default(),
+            failed_paths: Default::default(),
+            issued_scids: Default::default(),
+            retired_scids: Default::default(),
+            early_data: false,
             ack_eliciting_interval,
             latest_send_ack_eliciting_at: Instant::now(),
         }
     }
+
+    /// Records that application data was written, which is sent in 0-RTT in early data.
+    fn on_app_data_written(&mut self) {
+        if !self.conn.is_server() && self.conn.is_in_early_data() {
+            self.early_data = true;
+        }
+    }
 }
 
 #[derive(Default)]
@@ -124,6 +179,9 @@ pub struct QuicConnState {
     state: Arc<Mutex<QuicRawConnState>>,
     event_map: Arc<KeyWaitMap<QuicConnStateEvent, ()>>,
     stream_drop_table: Arc<QuicStreamDropTable>,
+    /// The sockets of one client connection, which is used by [`QuicConnMigrate`](crate::QuicConnMigrate).
+    #[cfg(feature = "with-rasi")]
+    pub(crate) udp_group: Arc<std::sync::OnceLock<crate::rasi::UdpGroup>>,
 }
 
 impl Debug for QuicConnState {
@@ -154,14 +212,19 @@ impl QuicConnState {
         let mut raised_events = vec![
             // as [`send`](https://docs.rs/quiche/latest/quiche/struct.Connection.html#method.send)
             // function description, that is, any time recv() is called, we should call send() again.
-            (
-                QuicConnStateEvent::Send(state.conn.source_id().into_owned()),
-                (),
-            ),
+            (QuicConnStateEvent::Send(self.id.clone()), ()),
         ];
 
+        if state.conn.is_established() {
+            raised_events.push((QuicConnStateEvent::Established(self.id.clone()), ()));
+        }
+
+        self.issue_scids(state);
+
         self.collect_stream_events(state, &mut raised_events);
 
+        self.collect_path_events(state, &mut raised_events);
+
         self.event_map.batch_insert(raised_events);
     }
     /// inner call this method after success send one packet.
@@ -170,9 +233,82 @@ impl QuicConnState {
 
         self.collect_stream_events(state, &mut raised_events);
 
+        self.collect_path_events(state, &mut raised_events);
+
         self.event_map.batch_insert(raised_events);
     }
 
+    /// Provides spare source ids to the peer, which are required by probing and migrating paths.
+    fn issue_scids(&self, state: &mut QuicRawConnState) {
+        if !state.conn.is_established() {
+            return;
+        }
+
+        let rng = SystemRandom::new();
+
+        while state.conn.scids_left() > 0 {
+            let mut scid = vec![0; quiche::MAX_CONN_ID_LEN];
+            let mut reset_token = [0; 16];
+
+            if let Err(err) = rng.fill(&mut scid).and(rng.fill(&mut reset_token)) {
+                log::error!("{:?}, generate source id failed, error={}", state, err);
+                break;
+            }
+
+            let scid = ConnectionId::from_vec(scid);
+
+            if let Err(err) = state
+                .conn
+                .new_scid(&scid, u128::from_be_bytes(reset_token), false)
+            {
+                log::error!("{:?}, issue source id failed, error={}", state, err);
+                break;
+            }
+
+            // only the server routes packets by the source ids.
+            if state.conn.is_server() {
+                state.issued_scids.push(scid);
+            }
+        }
+
+        while let Some(scid) = state.conn.retired_scid_next() {
+            if state.conn.is_server() {
+                state.retired_scids.push(scid);
+            }
+        }
+    }
+
+    fn collect_path_events(
+        &self,
+        state: &mut QuicRawConnState,
+        raised_events: &mut Vec<(QuicConnStateEvent, ())>,
+    ) {
+        // the validation state may be changed by received frames or timeout.
+        raised_events.push((QuicConnStateEvent::PathValidation(self.id.clone()), ()));
+
+        let mut has_events = false;
+
+        while let Some(event) = state.conn.path_event_next() {
+            log::trace!("{:?}, path event {:?}", state, event);
+
+            if let PathEvent::FailedValidation(local, peer) = &event {
+                state.failed_paths.insert((*local, *peer));
+            }
+
+            if state.path_events.len() == MAX_PATH_EVENTS {
+                state.path_events.pop_front();
+            }
+
+            state.path_events.push_back(event);
+
+            has_events = true;
+        }
+
+        if has_events {
+            raised_events.push((QuicConnStateEvent::PathEvent(self.id.clone()), ()));
+        }
+    }
+
     async fn send_ack_eliciting(&self, state: &mut QuicRawConnState) -> Result<bool> {
         if let Some(ack_eliciting_interval) = state.ack_eliciting_interval {
             if state.latest_send_ack_eliciting_at.elapsed() >= ack_eliciting_interval {
@@ -197,13 +333,25 @@ impl QuicConnState {
         if let Some(drain) = self.stream_drop_table.drain() {
             let drop_streams = drain.len();
             for stream_id in drain {
-                if let Err(err) = state.conn.stream_send(stream_id, b"", true) {
-                    log::error!(
-                        "{:?}, drop stream failed, stream_id={}, error={}",
-                        state,
-                        stream_id,
-                        err
-                    );
+                let is_local = stream_id % 2 == state.next_outbound_stream_id % 2;
+                let is_uni = stream_id & 0x2 != 0;
+
+                // the receive-only stream can't send fin.
+                if !is_uni || is_local {
+                    if let Err(err) = state.conn.stream_send(stream_id, b"", true) {
+                        log::error!(
+                            "{:?}, drop stream failed, stream_id={}, error={}",
+                            state,
+                            stream_id,
+                            err
+                        );
+                    }
+                }
+
+                // the send-only stream has no read side to shutdown.
+                if is_uni && is_local {
+                    state.outbound_uni_stream_ids.remove(&stream_id);
+                    continue;
                 }
 
                 if !state.conn.stream_finished(stream_id) {
@@ -240,40 +388,67 @@ impl QuicConnState {
                 && !state.inbound_stream_ids.contains(&stream_id)
             {
                 state.inbound_stream_ids.insert(stream_id);
+
+                if stream_id & 0x2 != 0 {
+                    state.incoming_uni_streams.push_back(stream_id);
+
+                    log::trace!(
+                        "{:?}, accept a new inbound unidirectional stream id={}",
+                        state,
+                        stream_id
+                    );
+
+                    raised_events.push((QuicConnStateEvent::UniStreamAccept(self.id.clone()), ()));
+
+                    continue;
+                }
+
                 state.incoming_streams.push_back(stream_id);
 
                 log::trace!("{:?}, accept a new inbound stream id={}", state, stream_id);
 
-                raised_events.push((
-                    QuicConnStateEvent::StreamAccept(state.conn.source_id().into_owned()),
-                    (),
-                ));
+                raised_events.push((QuicConnStateEvent::StreamAccept(self.id.clone()), ()));
 
                 continue;
             }
 
             raised_events.push((
-                QuicConnStateEvent::StreamReadable(state.conn.source_id().into_owned(), stream_id),
+                QuicConnStateEvent::StreamReadable(self.id.clone(), stream_id),
                 (),
             ));
         }
 
         for stream_id in state.conn.writable() {
             raised_events.push((
-                QuicConnStateEvent::StreamWritable(state.conn.source_id().into_owned(), stream_id),
+                QuicConnStateEvent::StreamWritable(self.id.clone(), stream_id),
                 (),
             ));
         }
+
+        // the peer may raise the limit of unidirectional streams by MAX_STREAMS frames.
+        if self.peer_streams_left_uni_priv(state) > 0 {
+            raised_events.push((QuicConnStateEvent::OutboundUniStream(self.id.clone()), ()));
+        }
+
+        if state.conn.dgram_recv_queue_len() > 0 {
+            raised_events.push((QuicConnStateEvent::DgramReadable(self.id.clone()), ()));
+        }
+
+        if !state.conn.is_dgram_send_queue_full() {
+            raised_events.push((QuicConnStateEvent::DgramWritable(self.id.clone()), ()));
+        }
     }
 
     fn peer_streams_left_bidi_priv(&self, state: &QuicRawConnState) -> u64 {
         let peer_streams_left_bidi = state.conn.peer_streams_left_bidi();
         let outgoing_cached = state.outbound_stream_ids.len() as u64;
+        // the peer's parameters are not parsed until its handshake reply is processed in 0-RTT,
+        // the limit restored from the session is used instead.
         let initial_max_streams_bidi = state
             .conn
             .peer_transport_params()
-            .unwrap()
-            .initial_max_streams_bidi;
+            .map(|params| params.initial_max_streams_bidi)
+            .unwrap_or(peer_streams_left_bidi);
 
         if peer_streams_left_bidi > outgoing_cached {
             if initial_max_streams_bidi == peer_streams_left_bidi {
@@ -286,6 +461,13 @@ impl QuicConnState {
         }
     }
 
+    fn peer_streams_left_uni_priv(&self, state: &QuicRawConnState) -> u64 {
+        state
+            .conn
+            .peer_streams_left_uni()
+            .saturating_sub(state.outbound_uni_stream_ids.len() as u64)
+    }
+
     pub(crate) async fn send_owned(self) -> Result<(Vec<u8>, SendInfo)> {
         let mut buf = vec![0; self.max_send_udp_payload_size];
 
@@ -323,6 +505,8 @@ impl QuicConnState {
             ))),
             event_map: Arc::new(KeyWaitMap::new()),
             stream_drop_table: Default::default(),
+            #[cfg(feature = "with-rasi")]
+            udp_group: Default::default(),
         }
     }
 
@@ -331,6 +515,54 @@ impl QuicConnState {
         self.state.lock().await.conn.is_established()
     }
 
+    /// Returns true if the handshake is pending and the connection can send early data.
+    pub async fn is_in_early_data(&self) -> bool {
+        self.state.lock().await.conn.is_in_early_data()
+    }
+
+    /// Returns true if the connection is resumed from a previous session.
+    pub async fn is_resumed(&self) -> bool {
+        self.state.lock().await.conn.is_resumed()
+    }
+
+    /// Waits until the handshake is complete, and returns whether early data was likely accepted.
+    ///
+    /// This is a heuristic, quiche doesn't expose whether the server accepted the early data.
+    /// Returns true if the client wrote stream data or datagrams in 0-RTT and the server resumed
+    /// the session. A server that resumes the session but rejects the early data, e.g. one
+    /// without [`Config::enable_early_data`](quiche::Config::enable_early_data), also returns true.
+    /// Either way, the rejected data is retransmitted after the handshake.
+    pub async fn early_data_maybe_accepted(&self) -> Result<bool> {
+        loop {
+            let state = self.state.lock().await;
+
+            if state.conn.is_established() {
+                return Ok(state.early_data && state.conn.is_resumed());
+            }
+
+            if self.is_closed() || state.conn.is_closed() {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    format!("connection is closed: {:?}", *state),
+                ));
+            }
+
+            let event = QuicConnStateEvent::Established(self.id.clone());
+
+            self.event_map.wait(&event, state).await;
+        }
+    }
+
+    /// Returns the serialized TLS session if it is not equal to `last`.
+    pub(crate) async fn session_if_changed(&self, last: Option<&[u8]>) -> Option<Vec<u8>> {
+        let state = self.state.lock().await;
+
+        match state.conn.session() {
+            Some(session) if Some(session) != last => Some(session.to_vec()),
+            _ => None,
+        }
+    }
+
     /// Returns when the next timeout event will occur.
     ///
     /// Once the timeout Instant has been reached, the `on_timeout()` method
@@ -361,6 +593,13 @@ impl QuicConnState {
                 log::trace!("{:?}, send data, timeout", *state);
                 on_timout = false;
                 state.conn.on_timeout();
+
+                // the path validation may be failed by timeout.
+                let mut raised_events = vec![];
+
+                self.collect_path_events(&mut state, &mut raised_events);
+
+                self.event_map.batch_insert(raised_events);
             }
 
             self.handle_stream_drop(&mut state).await;
@@ -413,8 +652,7 @@ impl QuicConnState {
                         continue;
                     }
 
-                    let event =
-                        QuicConnStateEvent::Send(state.conn.source_id().into_owned().clone());
+                    let event = QuicConnStateEvent::Send(self.id.clone());
 
                     use rasi::timer::TimeoutExt;
 
@@ -470,7 +708,7 @@ impl QuicConnState {
         }
     }
 
-    /// Accept a new inbound stream.
+    /// Accept a new inbound bidirectional stream.
     pub async fn accept(&self) -> Result<QuicStream> {
         loop {
             if self.is_closed.load(Ordering::SeqCst) {
@@ -488,7 +726,7 @@ impl QuicConnState {
                 return Ok(QuicStream::new(stream_id, self.clone()));
             }
 
-            let event = QuicConnStateEvent::StreamAccept(state.conn.source_id().into_owned());
+            let event = QuicConnStateEvent::StreamAccept(self.id.clone());
 
             log::trace!("accept new incoming stream -- waiting");
 
@@ -520,7 +758,7 @@ impl QuicConnState {
                     ));
                 }
 
-                let event = QuicConnStateEvent::OutboundStream(state.conn.source_id().into_owned());
+                let event = QuicConnStateEvent::OutboundStream(self.id.clone());
 
                 self.event_map.wait(&event, state).await;
 
@@ -538,6 +776,175 @@ impl QuicConnState {
         }
     }
 
+    /// Sends data in a DATAGRAM frame.
+    ///
+    /// If the DATAGRAM send queue is full, waits until the queued frames are sent.
+    /// Returns an error if the peer does not support DATAGRAM frames,
+    /// or `buf` is larger than [`dgram_max_writable_len`](Self::dgram_max_writable_len).
+    pub async fn send_datagram<Buf: AsRef<[u8]>>(&self, buf: Buf) -> Result<()> {
+        let buf = buf.as_ref();
+
+        loop {
+            let mut state = self.state.lock().await;
+
+            if self.is_closed() || state.conn.is_closed() || state.conn.is_draining() {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    format!("connection is closed/draining: {:?}", *state),
+                ));
+            }
+
+            match state.conn.dgram_send(buf) {
+                Ok(()) => {
+                    state.on_app_data_written();
+
+                    self.event_map
+                        .insert(QuicConnStateEvent::Send(self.id.clone()), ());
+
+                    return Ok(());
+                }
+                Err(quiche::Error::Done) => {
+                    // the send queue is full, waits until the `send()` function drains it.
+                    self.event_map
+                        .insert(QuicConnStateEvent::Send(self.id.clone()), ());
+
+                    let event = QuicConnStateEvent::DgramWritable(self.id.clone());
+
+                    self.event_map.wait(&event, state).await;
+
+                    continue;
+                }
+                Err(quiche::Error::InvalidState) => {
+                    return Err(Error::new(
+                        ErrorKind::Unsupported,
+                        "DATAGRAM frames are not supported by the peer",
+                    ));
+                }
+                Err(quiche::Error::BufferTooShort) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "DATAGRAM is too large, len={}, max_writable_len={:?}",
+                            buf.len(),
+                            state.conn.dgram_max_writable_len()
+                        ),
+                    ));
+                }
+                Err(err) => return Err(map_quic_error(err)),
+            }
+        }
+    }
+
+    /// Reads the first received DATAGRAM into the provided slice.
+    ///
+    /// On success the number of bytes read is returned.
+    /// If the slice is shorter than the DATAGRAM, returns an [`ErrorKind::InvalidInput`] error
+    /// and the DATAGRAM is kept in the receive queue.
+    pub async fn recv_datagram<Buf: AsMut<[u8]>>(&self, mut buf: Buf) -> Result<usize> {
+        let buf = buf.as_mut();
+
+        loop {
+            let mut state = self.state.lock().await;
+
+            if let Some(len) = state.conn.dgram_recv_front_len() {
+                if len > buf.len() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "buffer is too short, len={}, datagram len={}",
+                            buf.len(),
+                            len
+                        ),
+                    ));
+                }
+
+                return state.conn.dgram_recv(buf).map_err(map_quic_error);
+            }
+
+            if self.is_closed() || state.conn.is_closed() || state.conn.is_draining() {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    format!("connection is closed/draining: {:?}", *state),
+                ));
+            }
+
+            let event = QuicConnStateEvent::DgramReadable(self.id.clone());
+
+            self.event_map.wait(&event, state).await;
+        }
+    }
+
+    /// Returns the maximum DATAGRAM payload that can be sent,
+    /// or `None` if the peer does not support DATAGRAM frames.
+    pub async fn dgram_max_writable_len(&self) -> Option<usize> {
+        self.state.lock().await.conn.dgram_max_writable_len()
+    }
+
+    /// Accept a new inbound unidirectional stream, which is receive-only.
+    pub async fn accept_uni(&self) -> Result<QuicRecvStream> {
+        loop {
+            if self.is_closed.load(Ordering::SeqCst) {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    "Underly quiche connection is closed.",
+                ));
+            }
+
+            let mut state = self.state.lock().await;
+
+            self.handle_stream_drop(&mut state).await;
+
+            if let Some(stream_id) = state.incoming_uni_streams.pop_front() {
+                return Ok(QuicRecvStream {
+                    stream: QuicStream::new(stream_id, self.clone()),
+                    fin: false,
+                });
+            }
+
+            let event = QuicConnStateEvent::UniStreamAccept(self.id.clone());
+
+            self.event_map.wait(&event, state).await;
+        }
+    }
+
+    /// Open a new outbound unidirectional stream over this connection, which is send-only.
+    ///
+    /// # nonblocking
+    ///
+    /// When nonblocking parameter is true and the `peer_streams_left_uni` is zero,
+    /// this function will cause an [`ErrorKind::WouldBlock`] error.
+    pub async fn open_uni(&self, nonblocking: bool) -> Result<QuicSendStream> {
+        loop {
+            let mut state = self.state.lock().await;
+
+            self.handle_stream_drop(&mut state).await;
+
+            if self.peer_streams_left_uni_priv(&state) == 0 {
+                if nonblocking {
+                    return Err(Error::new(
+                        ErrorKind::WouldBlock,
+                        quiche::Error::StreamLimit,
+                    ));
+                }
+
+                let event = QuicConnStateEvent::OutboundUniStream(self.id.clone());
+
+                self.event_map.wait(&event, state).await;
+
+                continue;
+            }
+
+            let stream_id = state.next_outbound_uni_stream_id;
+
+            state.next_outbound_uni_stream_id += 4;
+
+            // removed after first call to stream_send.
+            state.outbound_uni_stream_ids.insert(stream_id);
+
+            return Ok(QuicSendStream(QuicStream::new(stream_id, self.clone())));
+        }
+    }
+
     /// Returns the peer's cert in der format if valid.
     pub async fn peer_cert(&self) -> Option<Vec<u8>> {
         let state = self.state.lock().await;
@@ -552,18 +959,152 @@ impl QuicConnState {
         state.conn.paths_iter(laddr).next()
     }
 
+    /// Get the active path `(local, peer)` of this connection.
     pub async fn path(&self) -> Option<(SocketAddr, SocketAddr)> {
         let state = self.state.lock().await;
 
         let result = state
             .conn
             .path_stats()
-            .next()
+            .find(|stats| stats.active)
             .map(|stats| (stats.local_addr, stats.peer_addr));
 
         result
     }
 
+    /// Returns the probe timeout of the path `(local, peer)`, estimated from its rtt as RFC 9002 does.
+    pub(crate) async fn path_pto(&self, local: SocketAddr, peer: SocketAddr) -> Option<Duration> {
+        let state = self.state.lock().await;
+
+        let result = state
+            .conn
+            .path_stats()
+            .find(|stats| stats.local_addr == local && stats.peer_addr == peer)
+            .map(|stats| {
+                // the default `max_ack_delay` of the transport parameters is 25ms.
+                stats.rtt
+                    + (stats.rttvar * 4).max(Duration::from_millis(1))
+                    + Duration::from_millis(25)
+            });
+
+        result
+    }
+
+    /// Returns the next path event of this connection, e.g, a new path is validated or the peer migrated.
+    ///
+    /// At most 64 unread events are buffered, the older events are dropped.
+    pub async fn path_event(&self) -> Result<PathEvent> {
+        loop {
+            let mut state = self.state.lock().await;
+
+            if let Some(event) = state.path_events.pop_front() {
+                return Ok(event);
+            }
+
+            if self.is_closed() || state.conn.is_closed() {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    format!("connection is closed: {:?}", *state),
+                ));
+            }
+
+            let event = QuicConnStateEvent::PathEvent(self.id.clone());
+
+            self.event_map.wait(&event, state).await;
+        }
+    }
+
+    /// Probes the path between `local` and `peer`, and waits until the path is validated.
+    ///
+    /// A client can probe a new path, a server can only probe the paths seen on received packets.
+    /// Probing a new path requires spare connection ids, this function waits until the peer provides them.
+    /// Returns an [`ErrorKind::TimedOut`] error if the path fails to be validated.
+    pub async fn probe_path(&self, local: SocketAddr, peer: SocketAddr) -> Result<()> {
+        let mut probing = false;
+
+        loop {
+            let mut state = self.state.lock().await;
+
+            if self.is_closed() || state.conn.is_closed() || state.conn.is_draining() {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    format!("connection is closed/draining: {:?}", *state),
+                ));
+            }
+
+            if !probing {
+                match state.conn.probe_path(local, peer) {
+                    Ok(_) => {
+                        probing = true;
+
+                        state.failed_paths.remove(&(local, peer));
+
+                        self.event_map
+                            .insert(QuicConnStateEvent::Send(self.id.clone()), ());
+                    }
+                    // waits for the NEW_CONNECTION_ID frames from peer.
+                    Err(quiche::Error::OutOfIdentifiers) => {}
+                    Err(err) => return Err(map_quic_error(err)),
+                }
+            }
+
+            if probing {
+                if state
+                    .conn
+                    .is_path_validated(local, peer)
+                    .map_err(map_quic_error)?
+                {
+                    return Ok(());
+                }
+
+                if state.failed_paths.remove(&(local, peer)) {
+                    return Err(Error::new(
+                        ErrorKind::TimedOut,
+                        format!("path validation failed, local={}, peer={}", local, peer),
+                    ));
+                }
+            }
+
+            let event = QuicConnStateEvent::PathValidation(self.id.clone());
+
+            self.event_map.wait(&event, state).await;
+        }
+    }
+
+    /// Migrates this connection to the path between `local` and `peer`,
+    /// the path should be validated by [`probe_path`](Self::probe_path) first.
+    ///
+    /// Only the client can migrate the connection.
+    pub async fn migrate(&self, local: SocketAddr, peer: SocketAddr) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        state.conn.migrate(local, peer).map_err(map_quic_error)?;
+
+        log::trace!(
+            "{:?}, migrate to path, local={}, peer={}",
+            *state,
+            local,
+            peer
+        );
+
+        self.event_map
+            .insert(QuicConnStateEvent::Send(self.id.clone()), ());
+
+        Ok(())
+    }
+
+    /// Takes the source ids issued and retired since last call.
+    pub(crate) async fn take_scid_changes(
+        &self,
+    ) -> (Vec<ConnectionId<'static>>, Vec<ConnectionId<'static>>) {
+        let mut state = self.state.lock().await;
+
+        (
+            std::mem::take(&mut state.issued_scids),
+            std::mem::take(&mut state.retired_scids),
+        )
+    }
+
     /// Get this source id of this connection.
     pub fn scid(&self) -> &ConnectionId<'_> {
         &self.id
@@ -577,6 +1118,18 @@ impl QuicConnState {
             .insert(QuicConnStateEvent::Send(self.id.clone()), ());
         self.event_map
             .insert(QuicConnStateEvent::StreamAccept(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::UniStreamAccept(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::DgramReadable(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::DgramWritable(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::PathEvent(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::PathValidation(self.id.clone()), ());
+        self.event_map
+            .insert(QuicConnStateEvent::Established(self.id.clone()), ());
 
         Ok(())
     }
@@ -661,8 +1214,12 @@ impl RawQuicStream {
             if self.stream_id % 2 == state.next_outbound_stream_id % 2 {
                 // notify can open next stream.
                 if state.outbound_stream_ids.remove(&self.stream_id) {
+                    self.conn
+                        .event_map
+                        .insert(QuicConnStateEvent::OutboundStream(self.conn.id.clone()), ());
+                } else if state.outbound_uni_stream_ids.remove(&self.stream_id) {
                     self.conn.event_map.insert(
-                        QuicConnStateEvent::OutboundStream(state.conn.source_id().into_owned()),
+                        QuicConnStateEvent::OutboundUniStream(self.conn.id.clone()),
                         (),
                     );
                 }
@@ -670,27 +1227,25 @@ impl RawQuicStream {
 
             match result {
                 Ok(send_size) => {
+                    state.on_app_data_written();
+
                     // According to the function A [`send`](https://docs.rs/quiche/latest/quiche/struct.Connection.html#method.send)
                     // document description, we should call the send function immediately.
-                    self.conn.event_map.insert(
-                        QuicConnStateEvent::Send(state.conn.source_id().into_owned()),
-                        (),
-                    );
+                    self.conn
+                        .event_map
+                        .insert(QuicConnStateEvent::Send(self.conn.id.clone()), ());
 
                     return Ok(send_size);
                 }
                 Err(quiche::Error::Done) => {
                     // if no data was written(e.g. because the stream has no capacity),
                     // call `send()` function immediately
-                    self.conn.event_map.insert(
-                        QuicConnStateEvent::Send(state.conn.source_id().into_owned()),
-                        (),
-                    );
+                    self.conn
+                        .event_map
+                        .insert(QuicConnStateEvent::Send(self.conn.id.clone()), ());
 
-                    let event = QuicConnStateEvent::StreamWritable(
-                        state.conn.source_id().into_owned(),
-                        self.stream_id,
-                    );
+                    let event =
+                        QuicConnStateEvent::StreamWritable(self.conn.id.clone(), self.stream_id);
 
                     self.conn.event_map.wait(&event, state).await;
 
@@ -716,18 +1271,15 @@ impl RawQuicStream {
 
             match state.conn.stream_recv(self.stream_id, buf) {
                 Ok((read_size, fin)) => {
-                    self.conn.event_map.insert(
-                        QuicConnStateEvent::Send(state.conn.source_id().into_owned()),
-                        (),
-                    );
+                    self.conn
+                        .event_map
+                        .insert(QuicConnStateEvent::Send(self.conn.id.clone()), ());
 
                     return Ok((read_size, fin));
                 }
                 Err(quiche::Error::Done) => {
-                    let event = QuicConnStateEvent::StreamReadable(
-                        state.conn.source_id().into_owned(),
-                        self.stream_id,
-                    );
+                    let event =
+                        QuicConnStateEvent::StreamReadable(self.conn.id.clone(), self.stream_id);
 
                     self.conn.event_map.wait(&event, state).await;
                     log::trace!("stream wakeup: {:?}", event);
@@ -737,7 +1289,7 @@ impl RawQuicStream {
                     // the stream is not created yet.
                     if state.outbound_stream_ids.contains(&self.stream_id) {
                         let event = QuicConnStateEvent::StreamReadable(
-                            state.conn.source_id().into_owned(),
+                            self.conn.id.clone(),
                             self.stream_id,
                         );
 
@@ -915,3 +1467,109 @@ impl AsyncRead for QuicStream {
         }
     }
 }
+
+/// A send-only unidirectional stream, created by [`QuicConnState::open_uni`].
+pub struct QuicSendStream(QuicStream);
+
+impl QuicSendStream {
+    /// Returns current stream id value.
+    pub fn id(&self) -> u64 {
+        self.0.id()
+    }
+
+    /// Returns this stream's source connection id.
+    pub fn scid(&self) -> &ConnectionId<'_> {
+        self.0.scid()
+    }
+
+    /// Writes data to a stream.
+    ///
+    /// On success the number of bytes written is returned.
+    pub async fn send<Buf: AsRef<[u8]>>(&self, buf: Buf, fin: bool) -> Result<usize> {
+        self.0.send(buf, fin).await
+    }
+}
+
+impl AsyncWrite for QuicSendStream {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<Result<usize>> {
+        std::pin::Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>> {
+        std::pin::Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>> {
+        std::pin::Pin::new(&mut self.0).poll_close(cx)
+    }
+}
+
+/// A receive-only unidirectional stream, created by [`QuicConnState::accept_uni`].
+pub struct QuicRecvStream {
+    stream: QuicStream,
+    /// The fin flag had been read, the stream may be collected by quiche.
+    fin: bool,
+}
+
+impl QuicRecvStream {
+    /// Returns current stream id value.
+    pub fn id(&self) -> u64 {
+        self.stream.id()
+    }
+
+    /// Returns this stream's source connection id.
+    pub fn scid(&self) -> &ConnectionId<'_> {
+        self.stream.scid()
+    }
+
+    /// Reads contiguous data from a stream into the provided slice.
+    ///
+    /// On success the amount of bytes read and a flag indicating the fin state is
+    /// returned as a tuple.
+    pub async fn recv<Buf: AsMut<[u8]>>(&self, buf: Buf) -> Result<(usize, bool)> {
+        self.stream.recv(buf).await
+    }
+}
+
+impl AsyncRead for QuicRecvStream {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<Result<usize>> {
+        if self.fin {
+            return Poll::Ready(Ok(0));
+        }
+
+        let mut fut = if let Some(QuicStreamPoll::PollRead(fut)) = self.stream.poll.take() {
+            fut
+        } else {
+            Box::pin(self.stream.state.clone().recv_owned(buf.len()))
+        };
+
+        match fut.poll_unpin(cx) {
+            Poll::Ready(Ok((packet, fin))) => {
+                buf[..packet.len()].copy_from_slice(&packet);
+
+                self.fin = fin;
+
+                Poll::Ready(Ok(packet.len()))
+            }
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => {
+                self.stream.poll = Some(QuicStreamPoll::PollRead(fut));
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -4,6 +4,9 @@ pub use conn::*;
 mod listener;
 pub use listener::*;
 
+mod session;
+pub use session::*;
+
 mod errors;
 
 #[cfg(feature = "with-rasi")]
@@ -1,7 +1,8 @@
 use std::collections::{HashMap, VecDeque};
 use std::io::{Error, ErrorKind, Result};
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures::lock::Mutex;
 use futures::stream::unfold;
@@ -29,12 +30,82 @@ enum QuicListenerHandshake {
     },
 }
 
+/// The policy of sending Retry packets to validate the addresses of new connections.
+///
+/// quiche doesn't support NEW_TOKEN frames, so returning clients can't skip the Retry round trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuicRetryPolicy {
+    /// Sends Retry packets to all new connections.
+    Always,
+    /// Never sends Retry packets.
+    Never,
+    /// Sends Retry packets only when more than `N` handshakes are in flight.
+    HandshakesAbove(usize),
+}
+
+/// The options of [`QuicListener`].
+#[derive(Debug, Clone)]
+pub struct QuicListenerOptions {
+    retry_policy: QuicRetryPolicy,
+    token_lifetime: Duration,
+    key_rotation: Duration,
+}
+
+impl Default for QuicListenerOptions {
+    fn default() -> Self {
+        Self {
+            retry_policy: QuicRetryPolicy::Always,
+            token_lifetime: Duration::from_secs(10),
+            key_rotation: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl QuicListenerOptions {
+    /// Create options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the retry policy, the default value is [`QuicRetryPolicy::Always`].
+    pub fn retry_policy(mut self, policy: QuicRetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set the lifetime of retry tokens, the default value is 10s.
+    pub fn token_lifetime(mut self, lifetime: Duration) -> Self {
+        self.token_lifetime = lifetime;
+        self
+    }
+
+    /// Set the rotation interval of the token key, the default value is 1 hour.
+    ///
+    /// The tokens signed by the previous key are still accepted,
+    /// so the interval should be longer than the token lifetime.
+    pub fn key_rotation(mut self, interval: Duration) -> Self {
+        self.key_rotation = interval;
+        self
+    }
+}
+
+/// The rotating keys for signing tokens.
+struct TokenKeys {
+    current: Key,
+    previous: Option<Key>,
+    rotated_at: Instant,
+}
+
 /// Server-side incoming connection handshake pool.
 pub struct QuicListenerState {
     /// The quic config shared between connections for this listener.
     config: Config,
+    /// The options of this listener.
+    options: QuicListenerOptions,
     /// The seed for generating source id
     seed_key: Key,
+    /// The keys for signing tokens.
+    token_keys: TokenKeys,
     /// A quic connection pool that is in the handshake phase
     handshaking_pool: HashMap<ConnectionId<'static>, QuicConnState>,
     /// A quic connection pool that is already connected
@@ -45,21 +116,27 @@ pub struct QuicListenerState {
 
 impl QuicListenerState {
     /// Create `HandshakePool` with provided `config`.
-    fn new(config: Config) -> Result<Self> {
-        let rng = SystemRandom::new();
-
-        let seed_key = ring::hmac::Key::generate(ring::hmac::HMAC_SHA256, &rng)
-            .map_err(|err| Error::new(ErrorKind::Other, format!("{}", err)))?;
-
+    fn new(config: Config, options: QuicListenerOptions) -> Result<Self> {
         Ok(Self {
             config,
-            seed_key,
+            options,
+            seed_key: Self::generate_key()?,
+            token_keys: TokenKeys {
+                current: Self::generate_key()?,
+                previous: None,
+                rotated_at: Instant::now(),
+            },
             handshaking_pool: Default::default(),
             incoming_conns: Default::default(),
             established_conns: Default::default(),
         })
     }
 
+    fn generate_key() -> Result<Key> {
+        ring::hmac::Key::generate(ring::hmac::HMAC_SHA256, &SystemRandom::new())
+            .map_err(|err| Error::other(err.to_string()))
+    }
+
     /// Get connection by id.
     ///
     /// If found, returns tuple (QuicConnState, is_established).
@@ -72,11 +149,15 @@ impl QuicListenerState {
             return Some((conn.clone(), true));
         }
 
+        // the packets sent before the client sees the source id chosen by server.
+        if let Some(conn) = self.handshaking_pool.get(&self.derive_scid(id)) {
+            return Some((conn.clone(), false));
+        }
+
         None
     }
 
     /// Move connection from handshaking set to established set by id.
-    #[allow(unused)]
     fn established<'a>(&mut self, id: &ConnectionId<'a>) {
         let id = id.clone().into_owned();
         if let Some(conn) = self.handshaking_pool.remove(&id) {
@@ -85,6 +166,21 @@ impl QuicListenerState {
         }
     }
 
+    /// Returns true if should send a Retry packet to a new connection.
+    fn should_retry(&self) -> bool {
+        match self.options.retry_policy {
+            QuicRetryPolicy::Always => true,
+            QuicRetryPolicy::Never => false,
+            QuicRetryPolicy::HandshakesAbove(n) => self.handshaking_pool.len() > n,
+        }
+    }
+
+    /// Generate the source id of the server from the first destination id of client.
+    fn derive_scid<'a>(&self, dcid: &ConnectionId<'a>) -> ConnectionId<'static> {
+        let scid = ring::hmac::sign(&self.seed_key, dcid);
+        ConnectionId::from_vec(scid.as_ref()[..quiche::MAX_CONN_ID_LEN].to_vec())
+    }
+
     /// remove connection from pool.
     fn remove_conn<'a>(&mut self, id: &ConnectionId<'a>) -> bool {
         let id = id.clone().into_owned();
@@ -92,13 +188,33 @@ impl QuicListenerState {
             return true;
         }
 
-        if self.established_conns.remove(&id).is_some() {
+        if let Some(conn) = self.established_conns.remove(&id) {
+            // also removes the source ids issued by this connection.
+            self.established_conns.retain(|_, v| *v != conn);
             return true;
         }
 
         false
     }
 
+    /// Update the routing source ids of one established connection.
+    fn update_scids(
+        &mut self,
+        conn: &QuicConnState,
+        issued: Vec<ConnectionId<'static>>,
+        retired: Vec<ConnectionId<'static>>,
+    ) {
+        for scid in issued {
+            log::trace!("{:?}, route new source id, scid={:?}", conn, scid);
+            self.established_conns.insert(scid, conn.clone());
+        }
+
+        for scid in retired {
+            log::trace!("{:?}, unroute retired source id, scid={:?}", conn, scid);
+            self.established_conns.remove(&scid);
+        }
+    }
+
     /// Process Initial packet.
     fn handshake<'a>(
         &mut self,
@@ -128,26 +244,32 @@ impl QuicListenerState {
 
         let token = header.token.as_ref().unwrap();
 
-        // generate new token and retry
-        if token.is_empty() {
-            return self.retry(header, recv_info, buf);
-        }
+        let (scid, odcid) = if token.is_empty() {
+            // generate new token and retry
+            if self.should_retry() {
+                return self.retry(header, recv_info, buf);
+            }
 
-        // check token .
-        let odcid = Self::validate_token(token, &recv_info.from)?;
+            (self.derive_scid(&header.dcid), None)
+        } else {
+            // check token .
+            let odcid = self.validate_token(token, &recv_info.from)?;
 
-        let scid: quiche::ConnectionId<'_> = header.dcid.clone();
+            let scid = header.dcid.clone().into_owned();
 
-        if quiche::MAX_CONN_ID_LEN != scid.len() {
-            return Err(Error::new(
-                ErrorKind::Interrupted,
-                format!("Check dcid length error, len={}", scid.len()),
-            ));
-        }
+            if quiche::MAX_CONN_ID_LEN != scid.len() {
+                return Err(Error::new(
+                    ErrorKind::Interrupted,
+                    format!("Check dcid length error, len={}", scid.len()),
+                ));
+            }
+
+            (scid, Some(odcid))
+        };
 
         let mut quiche_conn = quiche::accept(
             &scid,
-            Some(&odcid),
+            odcid.as_ref(),
             recv_info.to,
             recv_info.from,
             &mut self.config,
@@ -206,11 +328,9 @@ impl QuicListenerState {
         recv_info: RecvInfo,
         buf: &mut [u8],
     ) -> Result<QuicListenerHandshake> {
-        let token = self.mint_token(&header, &recv_info.from);
+        let token = self.mint_token(header, &recv_info.from)?;
 
-        let new_scid = ring::hmac::sign(&self.seed_key, &header.dcid);
-        let new_scid = &new_scid.as_ref()[..quiche::MAX_CONN_ID_LEN];
-        let new_scid = quiche::ConnectionId::from_vec(new_scid.to_vec());
+        let new_scid = self.derive_scid(&header.dcid);
 
         let scid = header.scid.clone().into_owned();
         let dcid: ConnectionId<'_> = header.dcid.clone().into_owned();
@@ -229,52 +349,105 @@ impl QuicListenerState {
         })
     }
 
-    fn validate_token<'a>(token: &'a [u8], src: &SocketAddr) -> Result<quiche::ConnectionId<'a>> {
-        if token.len() < 6 {
-            return Err(Error::new(
-                ErrorKind::Interrupted,
-                format!("Invalid token, token length < 6"),
-            ));
+    /// Rotate the token key if the rotation interval elapsed.
+    fn rotate_token_key(&mut self) -> Result<()> {
+        if self.token_keys.rotated_at.elapsed() < self.options.key_rotation {
+            return Ok(());
         }
 
-        if &token[..6] != b"quiche" {
+        let key = Self::generate_key()?;
+
+        self.token_keys.previous = Some(std::mem::replace(&mut self.token_keys.current, key));
+        self.token_keys.rotated_at = Instant::now();
+
+        Ok(())
+    }
+
+    /// Returns the signed content of one token.
+    fn token_message(issued_at: &[u8], src: &SocketAddr, odcid: &[u8]) -> Vec<u8> {
+        let mut message = issued_at.to_vec();
+
+        match src.ip() {
+            IpAddr::V4(a) => message.extend_from_slice(&a.octets()),
+            IpAddr::V6(a) => message.extend_from_slice(&a.octets()),
+        }
+
+        message.extend_from_slice(odcid);
+
+        message
+    }
+
+    fn validate_token<'a>(
+        &mut self,
+        token: &'a [u8],
+        src: &SocketAddr,
+    ) -> Result<quiche::ConnectionId<'a>> {
+        // issued_at(8) + odcid + tag(32)
+        if token.len() < 8 + ring::digest::SHA256_OUTPUT_LEN {
             return Err(Error::new(
                 ErrorKind::Interrupted,
-                format!("Invalid token, not start with 'quiche'"),
+                "Invalid token, token is too short",
             ));
         }
 
-        let token = &token[6..];
+        self.rotate_token_key()?;
 
-        let addr = match src.ip() {
-            std::net::IpAddr::V4(a) => a.octets().to_vec(),
-            std::net::IpAddr::V6(a) => a.octets().to_vec(),
-        };
+        let (issued_at, token) = token.split_at(8);
+        let (odcid, tag) = token.split_at(token.len() - ring::digest::SHA256_OUTPUT_LEN);
+
+        let message = Self::token_message(issued_at, src, odcid);
 
-        if token.len() < addr.len() || &token[..addr.len()] != addr.as_slice() {
+        let keys = [
+            Some(&self.token_keys.current),
+            self.token_keys.previous.as_ref(),
+        ];
+
+        if !keys
+            .into_iter()
+            .flatten()
+            .any(|key| ring::hmac::verify(key, &message, tag).is_ok())
+        {
             return Err(Error::new(
                 ErrorKind::Interrupted,
-                format!("Invalid token, address mismatch"),
+                "Invalid token, signature mismatch",
             ));
         }
 
-        Ok(quiche::ConnectionId::from_ref(&token[addr.len()..]))
+        let issued_at = Duration::from_millis(u64::from_be_bytes(issued_at.try_into().unwrap()));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        if now.saturating_sub(issued_at) > self.options.token_lifetime {
+            return Err(Error::new(ErrorKind::Interrupted, "Invalid token, expired"));
+        }
+
+        Ok(quiche::ConnectionId::from_ref(odcid))
     }
 
-    fn mint_token<'a>(&self, hdr: &quiche::Header<'a>, src: &SocketAddr) -> Vec<u8> {
-        let mut token = Vec::new();
+    fn mint_token<'a>(&mut self, hdr: &quiche::Header<'a>, src: &SocketAddr) -> Result<Vec<u8>> {
+        self.rotate_token_key()?;
 
-        token.extend_from_slice(b"quiche");
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
 
-        let addr = match src.ip() {
-            std::net::IpAddr::V4(a) => a.octets().to_vec(),
-            std::net::IpAddr::V6(a) => a.octets().to_vec(),
-        };
+        let issued_at = issued_at.to_be_bytes();
 
-        token.extend_from_slice(&addr);
+        let tag = ring::hmac::sign(
+            &self.token_keys.current,
+            &Self::token_message(&issued_at, src, &hdr.dcid),
+        );
+
+        let mut token = Vec::new();
+
+        token.extend_from_slice(&issued_at);
         token.extend_from_slice(&hdr.dcid);
+        token.extend_from_slice(tag.as_ref());
 
-        token
+        Ok(token)
     }
 }
 
@@ -307,9 +480,18 @@ impl QuicListener {
 impl QuicListener {
     /// Create a new `QuicListener` instance with provided `laddrs` and `config`.
     pub fn new<A: ToSocketAddrs>(laddrs: A, config: Config) -> Result<Self> {
+        Self::with_options(laddrs, config, QuicListenerOptions::default())
+    }
+
+    /// Create a new `QuicListener` instance with provided `laddrs`, `config` and `options`.
+    pub fn with_options<A: ToSocketAddrs>(
+        laddrs: A,
+        config: Config,
+        options: QuicListenerOptions,
+    ) -> Result<Self> {
         Ok(QuicListener {
             laddrs: Arc::new(laddrs.to_socket_addrs()?.collect()),
-            state: Arc::new(Mutex::new(QuicListenerState::new(config)?)),
+            state: Arc::new(Mutex::new(QuicListenerState::new(config, options)?)),
             event_map: Arc::new(KeyWaitMap::new()),
             send_map: FuturesUnorderedMap::new(),
         })
@@ -335,6 +517,8 @@ impl QuicListener {
                         conn.id,
                         err
                     );
+
+                    self.remove_conn(&conn.id).await;
                 }
             }
         }
@@ -367,7 +551,7 @@ impl QuicListener {
                 Err(err) => {
                     log::error!("conn recv, id={:?}, err={}", conn.id, err);
 
-                    self.remove_conn(&header.dcid).await;
+                    self.remove_conn(&conn.id).await;
 
                     return Ok((buf.len(), None));
                 }
@@ -377,9 +561,18 @@ impl QuicListener {
                 // relock the state.
                 state = self.state.lock().await;
                 // move the connection to established set and push state into incoming queue.
-                state.established(&header.dcid);
+                state.established(&conn.id);
 
                 self.event_map.insert(QuicListenerAccept, ());
+
+                drop(state);
+            }
+
+            // the peer may send packets with the new source ids, e.g, after migrating to a new path.
+            let (issued, retired) = conn.take_scid_changes().await;
+
+            if !issued.is_empty() || !retired.is_empty() {
+                self.state.lock().await.update_scids(&conn, issued, retired);
             }
 
             return Ok((recv_size, None));
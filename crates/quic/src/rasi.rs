@@ -3,7 +3,8 @@ use std::{
     future::Future,
     io::{Error, ErrorKind, Result},
     net::{SocketAddr, ToSocketAddrs},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use futures_map::FuturesUnorderedMap;
@@ -12,18 +13,21 @@ use rand::{seq::IteratorRandom, thread_rng};
 use rasi::{
     net::{get_network_driver, UdpSocket},
     task::spawn_ok,
-    timer::TimeoutExt,
+    timer::{sleep, TimeoutExt},
 };
 
 use futures::StreamExt;
 use ring::rand::{SecureRandom, SystemRandom};
 
-use crate::{errors::map_quic_error, QuicConn, QuicConnState, QuicListener};
+use crate::{
+    errors::map_quic_error, QuicConn, QuicConnState, QuicListener, QuicListenerOptions,
+    QuicSessionStore,
+};
 
 const MAX_MTU_SIZE: usize = 1600;
 
 #[derive(Debug)]
-struct PathInfo {
+pub(crate) struct PathInfo {
     /// The specified local address.
     pub from: SocketAddr,
     /// The destination address for the data sent to the peer.
@@ -31,9 +35,9 @@ struct PathInfo {
 }
 
 #[derive(Clone)]
-struct UdpGroup {
+pub(crate) struct UdpGroup {
     /// Group sockets pool.
-    sockets: Arc<HashMap<SocketAddr, Arc<UdpSocket>>>,
+    sockets: Arc<Mutex<HashMap<SocketAddr, Arc<UdpSocket>>>>,
     /// A futures waiting map for [`UdpSocket::recv_from`]
     recv_map: FuturesUnorderedMap<SocketAddr, Result<(Vec<u8>, SocketAddr)>>,
     /// The recv buf size.
@@ -54,30 +58,59 @@ impl UdpGroup {
     ) -> Result<Self> {
         let laddrs = laddrs.to_socket_addrs()?.collect::<Vec<_>>();
 
-        let mut sockets = HashMap::new();
-
-        let recv_map = FuturesUnorderedMap::new();
+        let group = Self::new(max_recv_udp_payload_size);
 
         for laddr in laddrs {
-            let socket = Arc::new(UdpSocket::bind_with([laddr].as_slice(), driver).await?);
+            group.add_socket(UdpSocket::bind_with([laddr].as_slice(), driver).await?)?;
+        }
 
-            // get the port allocated by OS.
-            let laddr = socket.local_addr()?;
+        Ok(group)
+    }
 
-            // Init recv_from loop.
-            recv_map.insert(
-                laddr.clone(),
-                Self::recv_from_owned(socket.clone(), max_recv_udp_payload_size),
-            );
+    /// Create an empty udp group.
+    pub fn new(max_recv_udp_payload_size: u16) -> Self {
+        Self {
+            sockets: Default::default(),
+            max_recv_udp_payload_size,
+            recv_map: FuturesUnorderedMap::new(),
+        }
+    }
+
+    /// Add a bound `socket` into this group, returns the local address of the socket.
+    pub fn add_socket(&self, socket: UdpSocket) -> Result<SocketAddr> {
+        let socket = Arc::new(socket);
+
+        // get the port allocated by OS.
+        let laddr = socket.local_addr()?;
+
+        self.sockets.lock().unwrap().insert(laddr, socket.clone());
+
+        // Init recv_from loop.
+        self.recv_map.insert(
+            laddr,
+            Self::recv_from_owned(socket, self.max_recv_udp_payload_size),
+        );
 
-            sockets.insert(laddr, socket);
+        Ok(laddr)
+    }
+
+    /// Bind a new socket to `laddr` and add it into this group.
+    pub async fn bind_one(&self, laddr: SocketAddr) -> Result<SocketAddr> {
+        self.add_socket(UdpSocket::bind(laddr).await?)
+    }
+
+    /// Remove the socket bound to `laddr` from this group and shut it down.
+    pub fn remove(&self, laddr: &SocketAddr) {
+        if let Some(socket) = self.sockets.lock().unwrap().remove(laddr) {
+            _ = socket.shutdown(std::net::Shutdown::Both);
         }
+    }
 
-        Ok(Self {
-            sockets: Arc::new(sockets),
-            max_recv_udp_payload_size,
-            recv_map,
-        })
+    /// Shut down all sockets of this group.
+    pub fn shutdown_all(&self) {
+        for (_, socket) in self.sockets.lock().unwrap().drain() {
+            _ = socket.shutdown(std::net::Shutdown::Both);
+        }
     }
 
     async fn recv_from_owned(
@@ -93,10 +126,9 @@ impl UdpGroup {
         Ok((buf, from))
     }
 
-    #[allow(unused)]
-    /// Returns an iterator over the local binding addresses for this `UdpGroup`.
-    pub fn local_addrs(&self) -> impl Iterator<Item = &SocketAddr> {
-        self.sockets.keys()
+    /// Returns the local binding addresses for this `UdpGroup`.
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.sockets.lock().unwrap().keys().cloned().collect()
     }
 
     /// Receives data from the socket.
@@ -104,13 +136,17 @@ impl UdpGroup {
     /// On success, returns the recv_buf and the path information.
     pub async fn recv_from(&self) -> Result<(Vec<u8>, PathInfo)> {
         while let Some((laddr, r)) = self.recv_map.as_ref().next().await {
+            let udp_socket = match self.sockets.lock().unwrap().get(&laddr) {
+                Some(udp_socket) => udp_socket.clone(),
+                // the socket was removed from this group, drops the result.
+                None => continue,
+            };
+
             // As a whole, when one socket int this group calling `recv_from` failed,
             // the state of the group is undefined. therefore, we raise the error to
             // the caller.
             let (buf, raddr) = r?;
 
-            let udp_socket = self.sockets.get(&laddr).unwrap().clone();
-
             // recv next packet.
             self.recv_map.insert(
                 laddr,
@@ -133,7 +169,9 @@ impl UdpGroup {
     ///
     /// On success, returns the number of bytes written.
     pub async fn send_to<Buf: AsRef<[u8]>>(&self, buf: Buf, path_info: PathInfo) -> Result<usize> {
-        if let Some(socket) = self.sockets.get(&path_info.from) {
+        let socket = self.sockets.lock().unwrap().get(&path_info.from).cloned();
+
+        if let Some(socket) = socket {
             socket.send_to(buf.as_ref(), path_info.to).await
         } else {
             Err(Error::new(
@@ -149,6 +187,15 @@ pub trait QuicListenerBind {
     fn bind<A: ToSocketAddrs + Send>(
         laddrs: A,
         config: Config,
+    ) -> impl Future<Output = Result<QuicListener>> + Send {
+        Self::bind_with(laddrs, config, QuicListenerOptions::default())
+    }
+
+    /// Create a new QuicListener instance with local addresses bound to `laddrs` and provided `options`.
+    fn bind_with<A: ToSocketAddrs + Send>(
+        laddrs: A,
+        config: Config,
+        options: QuicListenerOptions,
     ) -> impl Future<Output = Result<QuicListener>> + Send {
         async move {
             let laddrs = laddrs.to_socket_addrs()?.collect::<Vec<_>>();
@@ -157,9 +204,9 @@ pub trait QuicListenerBind {
             // generally, the maximum mtu is 1,500 bytes.
             let udp_group = UdpGroup::bind(laddrs.as_slice(), MAX_MTU_SIZE as u16).await?;
 
-            let laddrs = udp_group.local_addrs().cloned().collect::<Vec<_>>();
+            let laddrs = udp_group.local_addrs();
 
-            let listener = QuicListener::new(laddrs.as_slice(), config)?;
+            let listener = QuicListener::with_options(laddrs.as_slice(), config, options)?;
 
             spawn_ok(listener_recv_loop(udp_group.clone(), listener.clone()));
 
@@ -247,12 +294,62 @@ async fn listener_recv_loop_priv(group: UdpGroup, listener: QuicListener) -> Res
 
 impl QuicListenerBind for QuicListener {}
 
+/// The options of client connections, see [`QuicConnect::connect_with`].
+#[derive(Clone, Default)]
+pub struct QuicConnectOptions {
+    session_store: Option<Arc<dyn QuicSessionStore>>,
+    early_data: bool,
+}
+
+impl QuicConnectOptions {
+    /// Create options with default values, which performs full handshakes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Save the sessions issued by servers into `store`, and resume the later connections with them.
+    pub fn session_store(mut self, store: Arc<dyn QuicSessionStore>) -> Self {
+        self.session_store = Some(store);
+        self
+    }
+
+    /// Enable the 0-RTT mode, the default value is false.
+    ///
+    /// When a connection is resumed and the server allows early data, the connect functions
+    /// return before the handshake is complete, so the streams can be opened and written in 0-RTT.
+    /// Use [`QuicConnState::early_data_maybe_accepted`] to check whether the early data was likely
+    /// accepted.
+    ///
+    /// The [`Config`] of both sides must enable early data, see [`Config::enable_early_data`].
+    pub fn early_data(mut self, enabled: bool) -> Self {
+        self.early_data = enabled;
+        self
+    }
+}
+
 pub trait QuicConnect {
     fn connect<L: ToSocketAddrs + Send, R: ToSocketAddrs + Send>(
         server_name: Option<&str>,
         laddrs: L,
         raddrs: R,
         config: &mut Config,
+    ) -> impl Future<Output = Result<QuicConn>> + Send {
+        Self::connect_with(
+            server_name,
+            laddrs,
+            raddrs,
+            config,
+            QuicConnectOptions::default(),
+        )
+    }
+
+    /// Connect to one of `raddrs` with provided `options`.
+    fn connect_with<L: ToSocketAddrs + Send, R: ToSocketAddrs + Send>(
+        server_name: Option<&str>,
+        laddrs: L,
+        raddrs: R,
+        config: &mut Config,
+        options: QuicConnectOptions,
     ) -> impl Future<Output = Result<QuicConn>> + Send {
         async move {
             let laddrs = laddrs.to_socket_addrs()?.collect::<Vec<_>>();
@@ -275,7 +372,9 @@ pub trait QuicConnect {
                     continue;
                 };
 
-                match Self::connect_on_path(server_name, laddr, raddr, config).await {
+                match Self::connect_on_path_with(server_name, laddr, raddr, config, options.clone())
+                    .await
+                {
                     Ok(conn) => return Ok(conn),
                     Err(err) => last_error = Some(err),
                 }
@@ -290,6 +389,23 @@ pub trait QuicConnect {
         laddr: SocketAddr,
         raddr: SocketAddr,
         config: &mut Config,
+    ) -> impl Future<Output = Result<QuicConn>> + Send {
+        Self::connect_on_path_with(
+            server_name,
+            laddr,
+            raddr,
+            config,
+            QuicConnectOptions::default(),
+        )
+    }
+
+    /// Connect to `raddr` via the socket bound to `laddr` with provided `options`.
+    fn connect_on_path_with(
+        server_name: Option<&str>,
+        laddr: SocketAddr,
+        raddr: SocketAddr,
+        config: &mut Config,
+        options: QuicConnectOptions,
     ) -> impl Future<Output = Result<QuicConn>> + Send {
         async move {
             let udp_socket = UdpSocket::bind(laddr).await?;
@@ -304,9 +420,26 @@ pub trait QuicConnect {
 
             let scid = quiche::ConnectionId::from_vec(scid);
 
-            let conn = quiche::connect(server_name, &scid, laddr, raddr, config)
+            let mut conn = quiche::connect(server_name, &scid, laddr, raddr, config)
                 .map_err(map_quic_error)?;
 
+            let server = server_name
+                .map(|name| name.to_owned())
+                .unwrap_or_else(|| raddr.to_string());
+
+            let mut resumed = false;
+
+            if let Some(session) = options
+                .session_store
+                .as_ref()
+                .and_then(|store| store.get(&server))
+            {
+                match conn.set_session(&session) {
+                    Ok(_) => resumed = true,
+                    Err(err) => log::warn!("connect, server={}, invalid session: {}", server, err),
+                }
+            }
+
             let conn = QuicConnState::new(conn, 0, None);
 
             let mut buf = vec![0; MAX_MTU_SIZE];
@@ -316,6 +449,12 @@ pub trait QuicConnect {
 
                 udp_socket.send_to(&buf[..send_size], send_info.to).await?;
 
+                // the rest of handshake is driven by the connection loops.
+                if options.early_data && resumed && conn.is_in_early_data().await {
+                    log::trace!("connect in 0-RTT: scid={:?}", conn.scid());
+                    break;
+                }
+
                 let (read_size, from) = if let Some(timeout_at) = conn.timeout_instant().await {
                     log::trace!(
                         "connect recv packet: scid={:?}, timeout={:?}",
@@ -346,9 +485,17 @@ pub trait QuicConnect {
                 }
             }
 
-            spawn_ok(client_send_loop(udp_socket.clone(), conn.clone()));
+            let group = UdpGroup::new(MAX_MTU_SIZE as u16);
+
+            group.add_socket(udp_socket)?;
+
+            _ = conn.udp_group.set(group.clone());
+
+            spawn_ok(client_send_loop(group.clone(), conn.clone()));
+
+            let session_store = options.session_store.map(|store| (store, server));
 
-            spawn_ok(client_recv_loop(udp_socket, conn.clone()));
+            spawn_ok(client_recv_loop(group, conn.clone(), session_store));
 
             Ok(conn.into())
         }
@@ -357,15 +504,15 @@ pub trait QuicConnect {
 
 impl QuicConnect for QuicConn {}
 
-async fn client_send_loop(udp_socket: UdpSocket, conn: QuicConnState) {
-    if let Err(err) = client_send_loop_priv(&udp_socket, &conn).await {
+async fn client_send_loop(group: UdpGroup, conn: QuicConnState) {
+    if let Err(err) = client_send_loop_priv(&group, &conn).await {
         log::error!(target: "QuicConn","stop recv loop by error: {}",err);
     }
-    _ = udp_socket.shutdown(std::net::Shutdown::Both);
+    group.shutdown_all();
     _ = conn.close();
 }
 
-async fn client_send_loop_priv(udp_socket: &UdpSocket, conn: &QuicConnState) -> Result<()> {
+async fn client_send_loop_priv(group: &UdpGroup, conn: &QuicConnState) -> Result<()> {
     let mut buf = vec![0; MAX_MTU_SIZE];
     loop {
         let (send_size, send_info) = conn.send(&mut buf).await?;
@@ -377,37 +524,134 @@ async fn client_send_loop_priv(udp_socket: &UdpSocket, conn: &QuicConnState) ->
             send_info.to
         );
 
-        udp_socket.send_to(&buf[..send_size], send_info.to).await?;
+        let path_info = PathInfo {
+            from: send_info.from,
+            to: send_info.to,
+        };
+
+        match group.send_to(&buf[..send_size], path_info).await {
+            Ok(_) => {}
+            // the packet of one abandoned path, e.g, the old path after migration.
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                log::warn!("client_send: drop packet, {}", err);
+            }
+            Err(err) => return Err(err),
+        }
     }
 }
-async fn client_recv_loop(udp_socket: UdpSocket, conn: QuicConnState) {
-    if let Err(err) = client_recv_loop_prev(&udp_socket, &conn).await {
+async fn client_recv_loop(
+    group: UdpGroup,
+    conn: QuicConnState,
+    session_store: Option<(Arc<dyn QuicSessionStore>, String)>,
+) {
+    if let Err(err) = client_recv_loop_prev(&group, &conn, session_store).await {
         log::error!(target: "QuicConn","stop recv loop by error: {}",err);
     }
 
     _ = conn.close();
 }
 
-async fn client_recv_loop_prev(udp_socket: &UdpSocket, conn: &QuicConnState) -> Result<()> {
-    let laddr = udp_socket.local_addr()?;
-    let mut buf = vec![0; MAX_MTU_SIZE];
+async fn client_recv_loop_prev(
+    group: &UdpGroup,
+    conn: &QuicConnState,
+    session_store: Option<(Arc<dyn QuicSessionStore>, String)>,
+) -> Result<()> {
+    let mut last_session = None;
+
     loop {
-        let (read_size, raddr) = udp_socket.recv_from(&mut buf).await?;
+        let (mut buf, path_info) = group.recv_from().await?;
 
         log::trace!(
             "client_recv: tx len={}, from={}, to={}",
-            read_size,
-            raddr,
-            laddr
+            buf.len(),
+            path_info.from,
+            path_info.to
         );
 
         conn.recv(
-            &mut buf[..read_size],
+            &mut buf,
             RecvInfo {
-                from: raddr,
-                to: laddr,
+                from: path_info.from,
+                to: path_info.to,
             },
         )
         .await?;
+
+        // the server may issue session tickets at any time after the handshake.
+        if let Some((store, server)) = &session_store {
+            if let Some(session) = conn.session_if_changed(last_session.as_deref()).await {
+                log::trace!("client_recv: save session, server={}", server);
+
+                store.put(server, session.clone());
+
+                last_session = Some(session);
+            }
+        }
+    }
+}
+
+/// The probe timeout used when the old path has no rtt estimate.
+const DEFAULT_MIGRATION_PTO: Duration = Duration::from_secs(1);
+
+/// An extension trait to migrate client connections to new local addresses.
+pub trait QuicConnMigrate {
+    /// Migrate this connection to a new socket bound to `laddr`.
+    ///
+    /// This function probes the new path and waits until it is validated before switching over,
+    /// the socket of the old path is kept for three probe timeouts after migration,
+    /// so the packets in flight on the old path are still received, then it is closed.
+    /// On success, returns the local address of the new path.
+    fn migrate_to<L: ToSocketAddrs + Send>(
+        &self,
+        laddr: L,
+    ) -> impl Future<Output = Result<SocketAddr>> + Send;
+}
+
+impl QuicConnMigrate for QuicConnState {
+    async fn migrate_to<L: ToSocketAddrs + Send>(&self, laddr: L) -> Result<SocketAddr> {
+        let group = self.udp_group.get().ok_or(Error::new(
+            ErrorKind::Unsupported,
+            "Only the client connection can be migrated",
+        ))?;
+
+        let (old_laddr, raddr) = self.path().await.ok_or(Error::new(
+            ErrorKind::NotConnected,
+            "The connection has no active path",
+        ))?;
+
+        let laddr = laddr
+            .to_socket_addrs()?
+            .find(|laddr| laddr.is_ipv4() == raddr.is_ipv4())
+            .ok_or(Error::new(
+                ErrorKind::InvalidInput,
+                format!("No local address matches the peer address {}", raddr),
+            ))?;
+
+        let laddr = group.bind_one(laddr).await?;
+
+        if let Err(err) = self.probe_path(laddr, raddr).await {
+            group.remove(&laddr);
+            return Err(err);
+        }
+
+        let drain = self
+            .path_pto(old_laddr, raddr)
+            .await
+            .unwrap_or(DEFAULT_MIGRATION_PTO)
+            * 3;
+
+        if let Err(err) = self.migrate(laddr, raddr).await {
+            group.remove(&laddr);
+            return Err(err);
+        }
+
+        let group = group.clone();
+
+        spawn_ok(async move {
+            sleep(drain).await;
+            group.remove(&old_laddr);
+        });
+
+        Ok(laddr)
     }
 }
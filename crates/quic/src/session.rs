@@ -0,0 +1,26 @@
+use std::{collections::HashMap, sync::Mutex};
+
+/// A storage of the TLS sessions, which is used by clients to resume the connections.
+///
+/// The sessions are keyed by the server name, or the server address if no server name is provided.
+pub trait QuicSessionStore: Send + Sync {
+    /// Returns the latest session of the `server`.
+    fn get(&self, server: &str) -> Option<Vec<u8>>;
+
+    /// Save the `session` of the `server`, the older one is replaced.
+    fn put(&self, server: &str, session: Vec<u8>);
+}
+
+/// An in-memory [`QuicSessionStore`] implementation.
+#[derive(Default)]
+pub struct MemorySessionStore(Mutex<HashMap<String, Vec<u8>>>);
+
+impl QuicSessionStore for MemorySessionStore {
+    fn get(&self, server: &str) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().get(server).cloned()
+    }
+
+    fn put(&self, server: &str, session: Vec<u8>) {
+        self.0.lock().unwrap().insert(server.to_owned(), session);
+    }
+}
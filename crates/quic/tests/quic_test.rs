@@ -1,9 +1,20 @@
-use std::sync::Once;
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex, Once},
+    time::Duration,
+};
 
 use futures::{AsyncReadExt, AsyncWriteExt, TryStreamExt};
-use futures_quic::{QuicConn, QuicConnect, QuicListener, QuicListenerBind};
-use quiche::Config;
-use rasi::task::spawn_ok;
+use futures_quic::{
+    MemorySessionStore, QuicConn, QuicConnMigrate, QuicConnect, QuicConnectOptions, QuicListener,
+    QuicListenerBind, QuicListenerOptions, QuicRetryPolicy, QuicSessionStore,
+};
+use quiche::{Config, PathEvent, RecvInfo};
+use rasi::{
+    net::UdpSocket,
+    task::spawn_ok,
+    timer::{sleep, TimeoutExt},
+};
 use rasi_mio::{net::register_mio_network, timer::register_mio_timer};
 
 fn init() {
@@ -26,6 +37,7 @@ fn mock_config(is_server: bool) -> Config {
     config.set_initial_max_stream_data_bidi_remote(1024 * 1024);
     config.set_initial_max_streams_bidi(100);
     config.set_initial_max_streams_uni(100);
+    config.set_initial_max_stream_data_uni(1024 * 1024);
 
     config.verify_peer(true);
 
@@ -363,3 +375,560 @@ async fn test_stream_server_close_with_fin() {
         assert!(fin);
     }
 }
+
+#[futures_test::test]
+async fn test_datagram() {
+    init();
+
+    let mut config = mock_config(true);
+
+    config.enable_dgram(true, 16, 4);
+
+    let listener = QuicListener::bind("127.0.0.1:0", config).await.unwrap();
+
+    let raddr = *listener.local_addrs().next().unwrap();
+
+    spawn_ok(async move {
+        while let Some(conn) = listener.incoming().try_next().await.unwrap() {
+            spawn_ok(async move {
+                let mut buf = vec![0; 1200];
+
+                while let Ok(read_size) = conn.recv_datagram(&mut buf).await {
+                    conn.send_datagram(&buf[..read_size]).await.unwrap();
+                }
+            });
+        }
+    });
+
+    let mut config = mock_config(false);
+
+    config.enable_dgram(true, 16, 4);
+
+    let client = QuicConn::connect(None, "127.0.0.1:0", raddr, &mut config)
+        .await
+        .unwrap();
+
+    let max_writable_len = client.dgram_max_writable_len().await.unwrap();
+
+    client
+        .send_datagram(vec![0; max_writable_len + 1])
+        .await
+        .expect_err("DATAGRAM is too large");
+
+    for _ in 0..10 {
+        client.send_datagram(b"hello world").await.unwrap();
+
+        let mut buf = vec![0; 100];
+
+        let read_size = client.recv_datagram(&mut buf).await.unwrap();
+
+        assert_eq!(&buf[..read_size], b"hello world");
+    }
+
+    // the senders wait for the send queue instead of failing.
+    for _ in 0..100 {
+        client.send_datagram(b"hello world").await.unwrap();
+    }
+
+    let mut buf = vec![0; 5];
+
+    // the datagram is kept on a short buffer.
+    client
+        .recv_datagram(&mut buf)
+        .await
+        .expect_err("short buffer");
+
+    let mut buf = vec![0; 100];
+
+    let read_size = client.recv_datagram(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+}
+
+#[futures_test::test]
+async fn test_uni_stream() {
+    init();
+
+    let listener = QuicListener::bind("127.0.0.1:0", mock_config(true))
+        .await
+        .unwrap();
+
+    let raddr = *listener.local_addrs().next().unwrap();
+
+    spawn_ok(async move {
+        while let Some(conn) = listener.incoming().try_next().await.unwrap() {
+            spawn_ok(async move {
+                // echo the data of inbound uni stream via an outbound uni stream.
+                while let Ok(mut inbound) = conn.accept_uni().await {
+                    let mut buf = vec![];
+
+                    inbound.read_to_end(&mut buf).await.unwrap();
+
+                    let mut outbound = conn.open_uni(false).await.unwrap();
+
+                    outbound.write_all(&buf).await.unwrap();
+                    outbound.close().await.unwrap();
+                }
+            });
+        }
+    });
+
+    let client = QuicConn::connect(None, "127.0.0.1:0", raddr, &mut mock_config(false))
+        .await
+        .unwrap();
+
+    for i in 0..10 {
+        let mut outbound = client.open_uni(false).await.unwrap();
+
+        // client-initiated unidirectional stream ids.
+        assert_eq!(outbound.id(), 2 + i * 4);
+
+        outbound.write_all(b"hello world").await.unwrap();
+        outbound.close().await.unwrap();
+
+        let mut inbound = client.accept_uni().await.unwrap();
+
+        // server-initiated unidirectional stream ids.
+        assert_eq!(inbound.id(), 3 + i * 4);
+
+        let mut buf = vec![];
+
+        inbound.read_to_end(&mut buf).await.unwrap();
+
+        assert_eq!(buf, b"hello world");
+    }
+}
+
+#[futures_test::test]
+async fn test_migration() {
+    init();
+
+    let listener = QuicListener::bind("127.0.0.1:0", mock_config(true))
+        .await
+        .unwrap();
+
+    let raddr = *listener.local_addrs().next().unwrap();
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+
+    spawn_ok(async move {
+        let conn = listener.accept().await.unwrap();
+
+        let mut stream = conn.accept().await.unwrap();
+
+        spawn_ok(async move {
+            let mut buf = vec![0; 100];
+
+            loop {
+                let read_size = stream.read(&mut buf).await.unwrap();
+
+                if read_size == 0 {
+                    break;
+                }
+
+                stream.write_all(&buf[..read_size]).await.unwrap();
+            }
+        });
+
+        let mut sender = Some(sender);
+
+        while let Ok(event) = conn.path_event().await {
+            if let PathEvent::PeerMigrated(_, peer) = event {
+                if let Some(sender) = sender.take() {
+                    _ = sender.send(peer);
+                }
+            }
+        }
+    });
+
+    let client = QuicConn::connect(None, "127.0.0.1:0", raddr, &mut mock_config(false))
+        .await
+        .unwrap();
+
+    let mut stream = client.open(false).await.unwrap();
+
+    let mut buf = vec![0; 100];
+
+    stream.write_all(b"hello world").await.unwrap();
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    let (old_laddr, _) = client.path().await.unwrap();
+
+    let laddr = client.migrate_to("127.0.0.1:0").await.unwrap();
+
+    assert_ne!(laddr, old_laddr);
+
+    assert_eq!(client.path().await, Some((laddr, raddr)));
+
+    // the stream survives the migration.
+    stream.write_all(b"hello world").await.unwrap();
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    assert_eq!(receiver.await.unwrap(), laddr);
+}
+
+/// The session ticket key shared by the early data test servers.
+const TICKET_KEY: [u8; 48] = [7; 48];
+
+/// Returns a server config with early data enabled.
+fn early_data_config() -> Config {
+    let mut config = mock_config(true);
+
+    config.set_ticket_key(&TICKET_KEY).unwrap();
+
+    config.enable_early_data();
+
+    config
+}
+
+/// Start an echo server, returns its address.
+async fn spawn_echo_server(config: Config) -> SocketAddr {
+    let listener = QuicListener::bind("127.0.0.1:0", config).await.unwrap();
+
+    let raddr = *listener.local_addrs().next().unwrap();
+
+    spawn_ok(async move {
+        while let Some(conn) = listener.incoming().try_next().await.unwrap() {
+            spawn_ok(async move {
+                while let Ok(mut stream) = conn.accept().await {
+                    let mut buf = vec![0; 100];
+
+                    let read_size = stream.read(&mut buf).await.unwrap();
+
+                    stream.write_all(&buf[..read_size]).await.unwrap();
+                }
+            });
+        }
+    });
+
+    raddr
+}
+
+/// Start an udp relay to `raddr`, which delays the packets from `raddr` by `delay`.
+async fn spawn_delay_relay(raddr: SocketAddr, delay: Duration) -> SocketAddr {
+    let front = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+    let back = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+
+    let laddr = front.local_addr().unwrap();
+
+    let client = Arc::new(Mutex::new(None));
+
+    spawn_ok({
+        let front = front.clone();
+        let back = back.clone();
+        let client = client.clone();
+
+        async move {
+            let mut buf = vec![0; 65535];
+
+            while let Ok((read_size, from)) = front.recv_from(&mut buf).await {
+                *client.lock().unwrap() = Some(from);
+
+                _ = back.send_to(&buf[..read_size], raddr).await;
+            }
+        }
+    });
+
+    spawn_ok(async move {
+        let mut buf = vec![0; 65535];
+
+        while let Ok((read_size, _)) = back.recv_from(&mut buf).await {
+            let Some(client) = *client.lock().unwrap() else {
+                continue;
+            };
+
+            let packet = buf[..read_size].to_vec();
+
+            let front = front.clone();
+
+            spawn_ok(async move {
+                sleep(delay).await;
+
+                _ = front.send_to(&packet, client).await;
+            });
+        }
+    });
+
+    laddr
+}
+
+/// Performs a full handshake with `raddr`, and waits for the session ticket saved in `store`.
+async fn fetch_session(
+    raddr: SocketAddr,
+    config: &mut Config,
+    store: &Arc<MemorySessionStore>,
+    options: QuicConnectOptions,
+) {
+    let client = QuicConn::connect_with(None, "127.0.0.1:0", raddr, config, options)
+        .await
+        .unwrap();
+
+    let mut stream = client.open(false).await.unwrap();
+
+    stream.write_all(b"hello world").await.unwrap();
+
+    let mut buf = vec![0; 100];
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    assert!(!client.is_resumed().await);
+    assert!(!client.early_data_maybe_accepted().await.unwrap());
+
+    while store.get(&raddr.to_string()).is_none() {
+        sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[futures_test::test]
+async fn test_early_data() {
+    init();
+
+    let raddr = spawn_echo_server(early_data_config()).await;
+
+    let store = Arc::new(MemorySessionStore::default());
+
+    let options = QuicConnectOptions::new()
+        .session_store(store.clone())
+        .early_data(true);
+
+    let mut config = mock_config(false);
+
+    config.enable_early_data();
+
+    // the first connection performs a full handshake.
+    fetch_session(raddr, &mut config, &store, options.clone()).await;
+
+    let client = QuicConn::connect_with(None, "127.0.0.1:0", raddr, &mut config, options)
+        .await
+        .unwrap();
+
+    // the stream data is sent in 0-RTT.
+    let mut stream = client.open(false).await.unwrap();
+
+    stream.write_all(b"hello world").await.unwrap();
+
+    let mut buf = vec![0; 100];
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    assert!(client.early_data_maybe_accepted().await.unwrap());
+    assert!(client.is_resumed().await);
+}
+
+#[futures_test::test]
+async fn test_early_data_open() {
+    init();
+
+    let raddr = spawn_echo_server(early_data_config()).await;
+
+    let store = Arc::new(MemorySessionStore::default());
+
+    let options = QuicConnectOptions::new()
+        .session_store(store.clone())
+        .early_data(true);
+
+    let mut config = mock_config(false);
+
+    config.enable_early_data();
+
+    fetch_session(raddr, &mut config, &store, options.clone()).await;
+
+    // the server's handshake reply is delayed by the relay.
+    let relay = spawn_delay_relay(raddr, Duration::from_millis(500)).await;
+
+    store.put(&relay.to_string(), store.get(&raddr.to_string()).unwrap());
+
+    let client = QuicConn::connect_with(None, "127.0.0.1:0", relay, &mut config, options)
+        .await
+        .unwrap();
+
+    assert!(client.is_in_early_data().await);
+
+    let mut stream = client.open(false).await.unwrap();
+
+    stream.write_all(b"hello world").await.unwrap();
+
+    assert!(client.is_in_early_data().await);
+
+    let mut buf = vec![0; 100];
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    assert!(client.is_resumed().await);
+}
+
+#[futures_test::test]
+async fn test_early_data_rejected() {
+    init();
+
+    let raddr = spawn_echo_server(early_data_config()).await;
+
+    // resumes the sessions issued by `raddr` with the shared ticket key, but rejects early data.
+    let mut config = mock_config(true);
+
+    config.set_ticket_key(&TICKET_KEY).unwrap();
+
+    let rejecting = spawn_echo_server(config).await;
+
+    let store = Arc::new(MemorySessionStore::default());
+
+    let options = QuicConnectOptions::new()
+        .session_store(store.clone())
+        .early_data(true);
+
+    let mut config = mock_config(false);
+
+    config.enable_early_data();
+
+    fetch_session(raddr, &mut config, &store, options.clone()).await;
+
+    // delays the handshake reply, so the stream data is written in 0-RTT.
+    let relay = spawn_delay_relay(rejecting, Duration::from_millis(500)).await;
+
+    store.put(&relay.to_string(), store.get(&raddr.to_string()).unwrap());
+
+    let client = QuicConn::connect_with(None, "127.0.0.1:0", relay, &mut config, options)
+        .await
+        .unwrap();
+
+    let mut stream = client.open(false).await.unwrap();
+
+    stream.write_all(b"hello world").await.unwrap();
+
+    // the rejected early data is retransmitted after the handshake.
+    let mut buf = vec![0; 100];
+
+    let read_size = stream.read(&mut buf).await.unwrap();
+
+    assert_eq!(&buf[..read_size], b"hello world");
+
+    assert!(client.is_resumed().await);
+
+    // the heuristic can't tell the rejection from the acceptance.
+    assert!(client.early_data_maybe_accepted().await.unwrap());
+}
+
+/// Create a client connection with source id filled by `seed`, returns the connection and its first Initial packet.
+fn client_initial(
+    seed: u8,
+    laddr: SocketAddr,
+    raddr: SocketAddr,
+) -> (quiche::Connection, Vec<u8>, RecvInfo) {
+    let scid = quiche::ConnectionId::from_vec(vec![seed; quiche::MAX_CONN_ID_LEN]);
+
+    let mut conn = quiche::connect(None, &scid, laddr, raddr, &mut mock_config(false)).unwrap();
+
+    let mut buf = vec![0; 1600];
+
+    let (send_size, send_info) = conn.send(&mut buf).unwrap();
+
+    buf.truncate(send_size);
+
+    let recv_info = RecvInfo {
+        from: send_info.from,
+        to: send_info.to,
+    };
+
+    (conn, buf, recv_info)
+}
+
+#[futures_test::test]
+async fn test_retry_policy() {
+    init();
+
+    let laddr: SocketAddr = "127.0.0.1:1".parse().unwrap();
+    let raddr: SocketAddr = "127.0.0.1:2".parse().unwrap();
+
+    let listener = |policy| {
+        QuicListener::with_options(
+            raddr,
+            mock_config(true),
+            QuicListenerOptions::new().retry_policy(policy),
+        )
+        .unwrap()
+    };
+
+    let cases = [
+        (QuicRetryPolicy::Always, [true, true, true]),
+        (QuicRetryPolicy::Never, [false, false, false]),
+        // retries the third connection.
+        (QuicRetryPolicy::HandshakesAbove(1), [false, false, true]),
+    ];
+
+    for (policy, retries) in cases {
+        let listener = listener(policy);
+
+        for (seed, retry) in retries.into_iter().enumerate() {
+            let (_, packet, recv_info) = client_initial(seed as u8, laddr, raddr);
+
+            let (_, response) = listener.recv(packet, recv_info).await.unwrap();
+
+            assert_eq!(response.is_some(), retry, "{:?}", policy);
+        }
+    }
+}
+
+#[futures_test::test]
+async fn test_retry_token_lifetime() {
+    init();
+
+    let laddr: SocketAddr = "127.0.0.1:1".parse().unwrap();
+    let raddr: SocketAddr = "127.0.0.1:2".parse().unwrap();
+
+    for expired in [false, true] {
+        let listener = QuicListener::with_options(
+            raddr,
+            mock_config(true),
+            QuicListenerOptions::new().token_lifetime(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+        let (mut client, packet, recv_info) = client_initial(0, laddr, raddr);
+
+        let (_, retry) = listener.recv(packet, recv_info).await.unwrap();
+
+        let mut retry = retry.unwrap();
+
+        client
+            .recv(
+                &mut retry,
+                RecvInfo {
+                    from: raddr,
+                    to: laddr,
+                },
+            )
+            .unwrap();
+
+        if expired {
+            sleep(Duration::from_millis(200)).await;
+        }
+
+        // the Initial packet with the retry token.
+        let mut buf = vec![0; 1600];
+
+        let (send_size, _) = client.send(&mut buf).unwrap();
+
+        buf.truncate(send_size);
+
+        let (_, response) = listener.recv(buf, recv_info).await.unwrap();
+
+        assert!(response.is_none());
+
+        // the connection is accepted only if the token is valid.
+        let handshake = listener.send().timeout(Duration::from_millis(200)).await;
+
+        assert_eq!(handshake.is_none(), expired);
+    }
+}